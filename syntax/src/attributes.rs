@@ -0,0 +1,247 @@
+//! From the spec:
+//! > 3.4 Attributes
+//! >
+//! > Several syntactic categories in the language contain attributes. These are generally pairs
+//! > consisting of an attribute name and an associated value, although attributes with no value
+//! > are also allowed. Attribute names are represented by keywords. Attribute values are in
+//! > general s-expressions other than keywords, although most predefined attributes use a more
+//! > restricted category for their values.
+//! > ```text
+//! >  〈attribute_value〉 ::= 〈spec_constant〉 | 〈symbol〉 | ( 〈s_expr〉∗ )
+//! >  〈attribute〉       ::= 〈keyword〉 | 〈keyword〉 〈attribute_value〉
+//! > ```
+//!
+//! The attributes that annotate terms with `!` and have a meaning of their own, i.e. `:named`,
+//! `:pattern` and `:no-pattern`, are read into their own variants of [`Attribute`].
+
+use std::fmt::Display;
+
+use super::lexicon::{Keyword, Symbol, Text};
+use super::sexprs::{self, Elements, FromSexprError, Production, Sexpr, SpecialConstant};
+use super::terms::Term;
+use super::writer;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AttributeValue<T = String> {
+    SpecialConstant(SpecialConstant<T>),
+    Symbol(Symbol<T>),
+    Sexprs(Vec<Sexpr<T>>),
+}
+
+impl<T: Text> AttributeValue<T> {
+    pub fn into_owned(self) -> AttributeValue {
+        match self {
+            AttributeValue::SpecialConstant(sc) => AttributeValue::SpecialConstant(sc.into_owned()),
+            AttributeValue::Symbol(sym) => AttributeValue::Symbol(sym.into_owned()),
+            AttributeValue::Sexprs(seq) => {
+                AttributeValue::Sexprs(sexprs::into_owned(seq, Sexpr::into_owned))
+            }
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for AttributeValue<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::SpecialConstant(sc) => Ok(AttributeValue::SpecialConstant(sc)),
+            Sexpr::Symbol(sym) => Ok(AttributeValue::Symbol(sym)),
+            Sexpr::Sequence(seq) => Ok(AttributeValue::Sexprs(seq)),
+            other => Err(FromSexprError::unexpected(
+                Production::AttributeValue,
+                &other,
+            )),
+        }
+    }
+}
+
+impl From<AttributeValue> for Sexpr {
+    fn from(value: AttributeValue) -> Self {
+        match value {
+            AttributeValue::SpecialConstant(sc) => sc.into(),
+            AttributeValue::Symbol(sym) => sym.into(),
+            AttributeValue::Sexprs(seq) => Sexpr::Sequence(seq),
+        }
+    }
+}
+
+impl<T: Text> Display for AttributeValue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmptyPatternError;
+
+impl Display for EmptyPatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a `:pattern` attribute needs at least one term")
+    }
+}
+
+impl std::error::Error for EmptyPatternError {}
+
+/// The terms of a `:pattern` attribute, of which there is at least one, as in
+/// `:pattern ((f x) (g y))`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MultiPattern<T = String>(pub(crate) Vec<Term<T>>);
+
+impl MultiPattern {
+    pub fn new(terms: Vec<Term>) -> Result<Self, EmptyPatternError> {
+        if terms.is_empty() {
+            return Err(EmptyPatternError);
+        }
+
+        Ok(MultiPattern(terms))
+    }
+}
+
+impl<T: Text> MultiPattern<T> {
+    pub fn terms(&self) -> &[Term<T>] {
+        &self.0
+    }
+
+    pub fn into_terms(self) -> Vec<Term<T>> {
+        self.0
+    }
+
+    pub fn into_owned(self) -> MultiPattern {
+        MultiPattern(sexprs::into_owned(self.0, Term::into_owned))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Attribute<T = String> {
+    /// `:named`, which gives a name to an asserted term, e.g. to refer to it in unsat cores.
+    Named(Symbol<T>),
+    /// `:pattern`, the terms that trigger the instantiation of a quantifier.
+    Pattern(MultiPattern<T>),
+    /// `:no-pattern`, a term that must not be used as a trigger. Not part of the standard, but
+    /// understood by most solvers.
+    NoPattern(Term<T>),
+    Other(Keyword<T>, Option<AttributeValue<T>>),
+}
+
+impl<T: Text> Attribute<T> {
+    /// The keyword that names the attribute.
+    pub fn keyword(&self) -> Keyword {
+        match self {
+            Attribute::Other(kw, _) => kw.clone().into_owned(),
+            _ => Keyword::new(self.name().to_string()).expect("attribute names are valid keywords"),
+        }
+    }
+
+    /// The keyword without the colon.
+    fn name(&self) -> &str {
+        match self {
+            Attribute::Named(_) => "named",
+            Attribute::Pattern(_) => "pattern",
+            Attribute::NoPattern(_) => "no-pattern",
+            Attribute::Other(kw, _) => kw.keyword(),
+        }
+    }
+
+    pub fn into_owned(self) -> Attribute {
+        match self {
+            Attribute::Named(name) => Attribute::Named(name.into_owned()),
+            Attribute::Pattern(terms) => Attribute::Pattern(terms.into_owned()),
+            Attribute::NoPattern(term) => Attribute::NoPattern(term.into_owned()),
+            Attribute::Other(kw, value) => {
+                Attribute::Other(kw.into_owned(), value.map(AttributeValue::into_owned))
+            }
+        }
+    }
+}
+
+impl Attribute {
+    pub(crate) fn into_sexprs(self) -> Vec<Sexpr> {
+        let kw = self.keyword().into();
+        match self {
+            Attribute::Named(name) => vec![kw, name.into()],
+            Attribute::Pattern(terms) => vec![
+                kw,
+                Sexpr::Sequence(terms.0.into_iter().map(Sexpr::from).collect()),
+            ],
+            Attribute::NoPattern(term) => vec![kw, term.into()],
+            Attribute::Other(_, value) => {
+                std::iter::once(kw).chain(value.map(Sexpr::from)).collect()
+            }
+        }
+    }
+}
+
+/// Prints the keyword and the value, separated by a space, as they appear in annotated terms.
+impl<T: Text> Display for Attribute<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display_elements(self, f)
+    }
+}
+
+/// Reads the remaining elements as one or more attributes. A keyword is followed by a value
+/// unless the next element is a keyword again, except for the standard attributes, which always
+/// need one.
+pub(crate) fn read_attributes<T: Text>(
+    mut elems: Elements<Sexpr<T>>,
+) -> Result<Vec<Attribute<T>>, FromSexprError> {
+    let mut attributes = vec![];
+
+    loop {
+        let kw = elems.next("attribute", Keyword::try_from)?;
+        let attribute = match kw.keyword() {
+            "named" => Attribute::Named(elems.next("symbol", Symbol::try_from)?),
+            "pattern" => Attribute::Pattern(MultiPattern(
+                elems.next_nonempty_list("term", Term::try_from)?,
+            )),
+            "no-pattern" => Attribute::NoPattern(elems.next("term", Term::try_from)?),
+            _ => match elems.peek() {
+                None | Some(Sexpr::Keyword(_)) => Attribute::Other(kw, None),
+                Some(_) => Attribute::Other(
+                    kw,
+                    Some(elems.next("attribute value", AttributeValue::try_from)?),
+                ),
+            },
+        };
+        attributes.push(attribute);
+
+        if elems.peek().is_none() {
+            return Ok(attributes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_term;
+
+    #[test]
+    fn annotated_terms() {
+        let input = "(forall ((x Int)) (! (> (f x) 0) :pattern ((f x)) :no-pattern (g x) :weight 2 :lemma))";
+        let term = parse_term(input).unwrap();
+        assert_eq!(term.to_string(), input);
+        assert_eq!(Sexpr::from(term.clone()).to_string(), input);
+
+        let borrowed = crate::borrowed::parse_term(input).unwrap();
+        assert_eq!(Sexpr::from(borrowed.into_owned()).to_string(), input);
+
+        let a0 = Symbol::new("a0".to_string()).unwrap();
+        let named = Term::from(1).named(a0.clone());
+        assert_eq!(named.name(), Some(&a0));
+        assert_eq!(Sexpr::from(named).to_string(), "(! 1 :named a0)");
+
+        let err = parse_term("(! p :named (q))").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:13: expected symbol, found list of 1 elements"
+        );
+
+        let err = parse_term("(! p)").unwrap_err();
+        assert_eq!(err.to_string(), "1:1: incomplete term: missing attribute");
+
+        assert_eq!(MultiPattern::new(vec![]), Err(EmptyPatternError));
+        let pattern = MultiPattern::new(vec![Term::from(1)]).unwrap();
+        assert_eq!(pattern.terms(), &[Term::from(1)]);
+    }
+}
@@ -0,0 +1,152 @@
+//! Syntax types that borrow their text from the input.
+//!
+//! The types in this module are the ones of the rest of the crate, except that symbols, keywords
+//! and string literals point into the parsed text instead of owning a copy of it. Only string
+//! literals containing an escaped `""` need an allocation. This makes parsing large inputs for
+//! read-only analyses much cheaper, since neither memory for the text nor an allocation per atom
+//! is needed.
+//!
+//! Every type has an `into_owned` method that converts it into its owned counterpart.
+//!
+//! ```
+//! use smtlib_syntax::borrowed::{self, Command};
+//!
+//! let input = "(declare-const x Int) (assert (> x 0))";
+//! let script = borrowed::parse_script(input).unwrap();
+//! if let Command::DeclareConst(name, _) = &script.0[0] {
+//!     assert_eq!(name.symbol(), "x");
+//! }
+//!
+//! let owned: smtlib_syntax::scripts::Script = script.into_owned();
+//! assert_eq!(owned.0.len(), 2);
+//! ```
+
+use std::borrow::Cow;
+
+use super::parser::{self, ParseError, SexprParser};
+use super::{attributes, identifiers, lexicon, scripts, sexprs, sorts, terms};
+
+pub type StringConstant<'a> = lexicon::StringConstant<Cow<'a, str>>;
+pub type Symbol<'a> = lexicon::Symbol<Cow<'a, str>>;
+pub type Keyword<'a> = lexicon::Keyword<Cow<'a, str>>;
+pub type SpecialConstant<'a> = sexprs::SpecialConstant<Cow<'a, str>>;
+pub type Sexpr<'a> = sexprs::Sexpr<Cow<'a, str>>;
+pub type Index<'a> = identifiers::Index<Cow<'a, str>>;
+pub type Identifier<'a> = identifiers::Identifier<Cow<'a, str>>;
+pub type Sort<'a> = sorts::Sort<Cow<'a, str>>;
+pub type QualifiedIdentifier<'a> = terms::QualifiedIdentifier<Cow<'a, str>>;
+pub type VarBinding<'a> = terms::VarBinding<Cow<'a, str>>;
+pub type SortedVar<'a> = terms::SortedVar<Cow<'a, str>>;
+pub type Pattern<'a> = terms::Pattern<Cow<'a, str>>;
+pub type MatchCase<'a> = terms::MatchCase<Cow<'a, str>>;
+pub type Term<'a> = terms::Term<Cow<'a, str>>;
+pub type AttributeValue<'a> = attributes::AttributeValue<Cow<'a, str>>;
+pub type MultiPattern<'a> = attributes::MultiPattern<Cow<'a, str>>;
+pub type Attribute<'a> = attributes::Attribute<Cow<'a, str>>;
+pub type SortDec<'a> = scripts::SortDec<Cow<'a, str>>;
+pub type SelectorDec<'a> = scripts::SelectorDec<Cow<'a, str>>;
+pub type ConstructorDec<'a> = scripts::ConstructorDec<Cow<'a, str>>;
+pub type DatatypeDec<'a> = scripts::DatatypeDec<Cow<'a, str>>;
+pub type FunctionDec<'a> = scripts::FunctionDec<Cow<'a, str>>;
+pub type FunctionDef<'a> = scripts::FunctionDef<Cow<'a, str>>;
+pub type PropLiteral<'a> = scripts::PropLiteral<Cow<'a, str>>;
+pub type SmtOption<'a> = scripts::SmtOption<Cow<'a, str>>;
+pub type InfoFlag<'a> = scripts::InfoFlag<Cow<'a, str>>;
+pub type Source<'a> = scripts::Source<Cow<'a, str>>;
+pub type SmtInfo<'a> = scripts::SmtInfo<Cow<'a, str>>;
+pub type Command<'a> = scripts::Command<Cow<'a, str>>;
+pub type Script<'a> = scripts::Script<Cow<'a, str>>;
+
+/// Parses exactly one s-expression.
+pub fn parse_sexpr(input: &str) -> Result<Sexpr<'_>, ParseError> {
+    parser::parse_single(input, Ok)
+}
+
+/// Parses a single command.
+pub fn parse_command(input: &str) -> Result<Command<'_>, ParseError> {
+    parser::parse_single(input, Command::try_from)
+}
+
+/// Parses a single term.
+pub fn parse_term(input: &str) -> Result<Term<'_>, ParseError> {
+    parser::parse_single(input, Term::try_from)
+}
+
+/// Parses a single sort.
+pub fn parse_sort(input: &str) -> Result<Sort<'_>, ParseError> {
+    parser::parse_single(input, Sort::try_from)
+}
+
+/// Parses a script, i.e. a sequence of commands.
+pub fn parse_script(input: &str) -> Result<Script<'_>, ParseError> {
+    let mut parser = SexprParser::new(input);
+    let mut commands = vec![];
+    while let Some(command) = parser.next_tree_converted::<Sexpr, _>(Command::try_from)? {
+        commands.push(command);
+    }
+    Ok(scripts::Script(commands))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    const SCRIPT: &str = r#"(set-logic QF_UF)
+(declare-datatypes ((Pair 0)) (((pair (fst Int) (snd Bool)))))
+(declare-fun |f x| ((_ BitVec 8)) (Array Int Bool))
+(define-fun g ((a Int)) Int (let ((b (+ a 1))) (as b Int)))
+(define-funs-rec ((h ((n Int)) Bool)) ((forall ((m Int)) (exists ((k Int)) (h k)))))
+(check-sat-assuming (p (not q)))
+(echo "say ""hi""")
+(get-value ((f 1) (_ bv1 8)))
+(get-option :produce-models)
+(push 1)
+(exit)"#;
+
+    #[test]
+    fn borrows_from_input() {
+        let script = parse_script(SCRIPT).unwrap();
+
+        let Command::DeclareFun(name, _, _) = &script.0[2] else {
+            panic!("expected declare-fun");
+        };
+        assert_eq!(name.symbol(), "f x");
+        let input = SCRIPT.as_bytes().as_ptr_range();
+        assert!(input.contains(&name.symbol().as_ptr()));
+
+        let Command::Echo(string) = &script.0[6] else {
+            panic!("expected echo");
+        };
+        assert_eq!(string.string_constant(), r#"say "hi""#);
+    }
+
+    #[test]
+    fn same_as_owned() {
+        let owned: Vec<_> = parser::parse_script(SCRIPT)
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|command| sexprs::Sexpr::from(command).to_string())
+            .collect();
+        let borrowed: Vec<_> = parse_script(SCRIPT)
+            .unwrap()
+            .into_owned()
+            .0
+            .into_iter()
+            .map(|command| sexprs::Sexpr::from(command).to_string())
+            .collect();
+        assert_eq!(borrowed, owned);
+
+        let sexpr = parse_sexpr(SCRIPT.lines().nth(2).unwrap()).unwrap();
+        assert_eq!(sexpr.to_string(), SCRIPT.lines().nth(2).unwrap());
+        assert_eq!(
+            sexpr.into_owned().to_string(),
+            SCRIPT.lines().nth(2).unwrap()
+        );
+
+        let err = parse_term("(f (g))").unwrap_err();
+        let owned_err = parser::parse_term("(f (g))").unwrap_err();
+        assert_eq!(err.to_string(), owned_err.to_string());
+    }
+}
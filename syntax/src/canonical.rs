@@ -0,0 +1,649 @@
+//! A canonical form of terms and scripts, e.g. to use them as keys when caching the results of a
+//! solver.
+//!
+//! [`Script::canonicalize`] renames the symbols that a script declares or binds, in the order
+//! they are introduced, and puts the arguments of `and`, `or`, `+`, `=` and `distinct` and the
+//! bindings of `let` in order. So scripts that only differ in how they name things or in the order
+//! of the arguments of these functions have the same canonical form, and the same
+//! [`Script::canonical_hash`].
+//!
+//! ```
+//! use smtlib_syntax::parser::parse_script;
+//!
+//! let a = parse_script("(declare-const x Int) (assert (> (+ x 1) 0))").unwrap();
+//! let b = parse_script("(declare-const y Int) (assert (> (+ 1 y) 0))").unwrap();
+//! assert_eq!(a.canonicalize(), b.canonicalize());
+//! assert_eq!(a.canonicalize().to_string(), "(declare-const f1 Int)\n(assert (> (+ 1 f1) 0))\n");
+//! assert_eq!(a.canonical_hash(), b.canonical_hash());
+//! ```
+//!
+//! The new names are `f1`, `f2`, ... for functions, constants, constructors, selectors and the
+//! names given by `:named`, `S1`, `S2`, ... for sorts, and `v1`, `v2`, ... and `P1`, `P2`, ...
+//! for variables and sort parameters. Variables are numbered by how many variables are bound
+//! around them, so that sibling binders get the same names no matter their order. Names that the
+//! input uses for something that is not renamed, e.g. `v1` for a constant that a term does not
+//! declare, are skipped.
+
+use std::collections::{HashMap, HashSet};
+
+use super::attributes::{Attribute, MultiPattern};
+use super::identifiers::Identifier;
+use super::lexicon::Symbol;
+use super::scripts::{
+    Command, ConstructorDec, DatatypeDec, FunctionDec, FunctionDef, PropLiteral, Script,
+    SelectorDec, SortDec,
+};
+use super::sorts::Sort;
+use super::terms::{MatchCase, Pattern, QualifiedIdentifier, SortedVar, Term, VarBinding};
+
+/// The functions whose arguments can be put in any order.
+const COMMUTATIVE: [&str; 5] = ["and", "or", "+", "=", "distinct"];
+
+impl Term {
+    /// Renames the variables that the term binds and the names it gives with `:named`, drops empty
+    /// binders, and puts the arguments of commutative functions and the bindings of `let` in
+    /// order, see the [module documentation](super::canonical). The free symbols of the term
+    /// are left as they are, as the term does not tell whether they were declared by a user.
+    pub fn canonicalize(&self) -> Term {
+        let mut first = Canonicalizer::new(HashSet::new(), false);
+        first.canonical_term(self);
+        let mut canonicalizer = Canonicalizer::new(first.unrenamed, false);
+        canonicalizer.canonical_term(self)
+    }
+
+    /// A hash of [`Term::canonicalize`] that stays the same across runs and platforms.
+    pub fn canonical_hash(&self) -> u64 {
+        fnv_hash(&self.canonicalize())
+    }
+}
+
+impl Script {
+    /// Renames the symbols that the script declares, defines or binds, and rewrites the terms as
+    /// [`Term::canonicalize`] does, see the [module documentation](super::canonical). The
+    /// literals of `check-sat-assuming` are put in order as well, while the terms of `get-value`
+    /// keep theirs, as it is the order of the response.
+    pub fn canonicalize(&self) -> Script {
+        let mut first = Canonicalizer::new(HashSet::new(), true);
+        for command in &self.0 {
+            first.command(command);
+        }
+        let mut canonicalizer = Canonicalizer::new(first.unrenamed, true);
+        Script(
+            self.0
+                .iter()
+                .map(|command| canonicalizer.command(command))
+                .collect(),
+        )
+    }
+
+    /// A hash of [`Script::canonicalize`] that stays the same across runs and platforms.
+    pub fn canonical_hash(&self) -> u64 {
+        fnv_hash(&self.canonicalize())
+    }
+}
+
+/// The 64-bit FNV-1a hash of the printed form, which unlike the hashers of the standard library
+/// is guaranteed not to change.
+fn fnv_hash(value: &impl std::fmt::Display) -> u64 {
+    struct Fnv(u64);
+
+    impl std::fmt::Write for Fnv {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            for byte in s.bytes() {
+                self.0 ^= u64::from(byte);
+                self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+            Ok(())
+        }
+    }
+
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    std::fmt::write(&mut hasher, format_args!("{value}")).expect("hashing does not fail");
+    hasher.0
+}
+
+struct Canonicalizer {
+    /// The symbols that are not renamed, which the new names avoid.
+    kept: HashSet<String>,
+    /// The symbols that were left as they are, to find out `kept`.
+    unrenamed: HashSet<String>,
+    /// Whether the declarations are known, so that undeclared pattern symbols are variables.
+    declarations: bool,
+    functions: HashMap<Symbol, Symbol>,
+    sorts: HashMap<Symbol, Symbol>,
+    /// How many functions and sorts were declared, which can be more than the maps hold when a
+    /// symbol is declared again after `pop`.
+    declared: (usize, usize),
+    /// The variables in scope and their new names, innermost last.
+    variables: Vec<(Symbol, Symbol)>,
+    /// The sort parameters in scope and their new names.
+    parameters: Vec<(Symbol, Symbol)>,
+    /// The last number tried and the new names that were made so far, by prefix.
+    names: HashMap<char, (usize, Vec<Symbol>)>,
+}
+
+impl Canonicalizer {
+    fn new(kept: HashSet<String>, declarations: bool) -> Self {
+        Canonicalizer {
+            kept,
+            unrenamed: HashSet::new(),
+            declarations,
+            functions: HashMap::new(),
+            sorts: HashMap::new(),
+            declared: (0, 0),
+            variables: vec![],
+            parameters: vec![],
+            names: HashMap::new(),
+        }
+    }
+
+    /// The `index`th name with the prefix that is not kept, counting from 0.
+    fn name(&mut self, prefix: char, index: usize) -> Symbol {
+        let (n, names) = self.names.entry(prefix).or_default();
+        while names.len() <= index {
+            *n += 1;
+            let name = format!("{prefix}{n}");
+            if !self.kept.contains(&name) {
+                names.push(Symbol::new(name).expect("a letter followed by digits is a symbol"));
+            }
+        }
+        names[index].clone()
+    }
+
+    fn declare_function(&mut self, symbol: &Symbol) -> Symbol {
+        let name = self.name('f', self.declared.0);
+        self.declared.0 += 1;
+        self.functions.insert(symbol.clone(), name.clone());
+        name
+    }
+
+    fn declare_sort(&mut self, symbol: &Symbol) -> Symbol {
+        let name = self.name('S', self.declared.1);
+        self.declared.1 += 1;
+        self.sorts.insert(symbol.clone(), name.clone());
+        name
+    }
+
+    fn bind_variable(&mut self, symbol: &Symbol) -> Symbol {
+        let name = self.name('v', self.variables.len());
+        self.variables.push((symbol.clone(), name.clone()));
+        name
+    }
+
+    fn bind_parameters(&mut self, symbols: &[Symbol]) -> Vec<Symbol> {
+        symbols
+            .iter()
+            .map(|symbol| {
+                let name = self.name('P', self.parameters.len());
+                self.parameters.push((symbol.clone(), name.clone()));
+                name
+            })
+            .collect()
+    }
+
+    fn unbind_variables(&mut self, count: usize) {
+        self.variables.truncate(self.variables.len() - count);
+    }
+
+    /// The new name of a symbol that refers to a variable or function.
+    fn function(&mut self, symbol: &Symbol) -> Symbol {
+        let bound = self.variables.iter().rev().find(|(old, _)| old == symbol);
+        match bound
+            .map(|(_, new)| new)
+            .or_else(|| self.functions.get(symbol))
+        {
+            Some(name) => name.clone(),
+            None => {
+                self.unrenamed.insert(symbol.symbol().to_string());
+                symbol.clone()
+            }
+        }
+    }
+
+    /// The new name of a symbol that refers to a sort or sort parameter.
+    fn sort_symbol(&mut self, symbol: &Symbol) -> Symbol {
+        let param = self.parameters.iter().rev().find(|(old, _)| old == symbol);
+        match param.map(|(_, new)| new).or_else(|| self.sorts.get(symbol)) {
+            Some(name) => name.clone(),
+            None => {
+                self.unrenamed.insert(symbol.symbol().to_string());
+                symbol.clone()
+            }
+        }
+    }
+
+    fn is_renamed(&self, symbol: &Symbol) -> bool {
+        self.variables.iter().any(|(old, _)| old == symbol) || self.functions.contains_key(symbol)
+    }
+
+    fn identifier(
+        &mut self,
+        id: &Identifier,
+        rename: fn(&mut Self, &Symbol) -> Symbol,
+    ) -> Identifier {
+        if !id.indexes.is_empty() {
+            self.unrenamed.insert(id.symbol.symbol().to_string());
+            return id.clone();
+        }
+        Identifier::new(rename(self, &id.symbol))
+    }
+
+    fn qualified_identifier(&mut self, qi: &QualifiedIdentifier) -> QualifiedIdentifier {
+        QualifiedIdentifier(
+            self.identifier(qi.identifier(), Self::function),
+            qi.sort().map(|sort| self.sort(sort)),
+        )
+    }
+
+    fn sort(&mut self, sort: &Sort) -> Sort {
+        Sort {
+            identifier: self.identifier(&sort.identifier, Self::sort_symbol),
+            parameters: sort
+                .parameters
+                .iter()
+                .map(|param| self.sort(param))
+                .collect(),
+        }
+    }
+
+    fn sorted_vars(&mut self, vars: &[SortedVar]) -> Vec<SortedVar> {
+        vars.iter()
+            .map(|SortedVar(symbol, sort)| {
+                let sort = self.sort(sort);
+                SortedVar(self.bind_variable(symbol), sort)
+            })
+            .collect()
+    }
+
+    /// Canonicalizes the term and then renames the names given by `:named`, in the order in which
+    /// they occur in the result, as the order in the term can change.
+    fn canonical_term(&mut self, term: &Term) -> Term {
+        let mut term = self.term(term);
+        self.name_terms(&mut term);
+        term
+    }
+
+    fn term(&mut self, term: &Term) -> Term {
+        match term {
+            Term::SpecialConstant(_) => term.clone(),
+            Term::QualifiedIdentifier(qi) => {
+                Term::QualifiedIdentifier(self.qualified_identifier(qi))
+            }
+            Term::Application(qi, args) => {
+                let id = qi.identifier();
+                let commutative = qi.sort().is_none()
+                    && id.indexes.is_empty()
+                    && COMMUTATIVE.contains(&id.symbol.symbol())
+                    && !self.is_renamed(&id.symbol);
+                let qi = self.qualified_identifier(qi);
+                let mut args: Vec<Term> = args.iter().map(|arg| self.term(arg)).collect();
+                if commutative {
+                    // the names given by `:named` are only renamed afterwards, so they must not
+                    // decide the order
+                    let label = Symbol::new("name".to_string()).expect("`name` is a valid symbol");
+                    args.sort_by_cached_key(|arg| {
+                        let mut arg = arg.clone();
+                        for_each_name(&mut arg, &mut |name| *name = label.clone());
+                        arg
+                    });
+                }
+                Term::Application(qi, args)
+            }
+            Term::Let(bindings, body) if bindings.is_empty() => self.term(body),
+            Term::Let(bindings, body) => {
+                // the bound terms are outside of the scope of the `let`, so they can be put in
+                // order before the variables are named
+                let mut bound: Vec<(Term, &Symbol)> = bindings
+                    .iter()
+                    .map(|VarBinding(symbol, term)| (self.term(term), symbol))
+                    .collect();
+                bound.sort_by(|a, b| a.0.cmp(&b.0));
+                let bindings: Vec<VarBinding> = bound
+                    .into_iter()
+                    .map(|(term, symbol)| VarBinding(self.bind_variable(symbol), term))
+                    .collect();
+                let body = self.term(body);
+                self.unbind_variables(bindings.len());
+                Term::Let(bindings, Box::new(body))
+            }
+            Term::Forall(vars, body) | Term::Exists(vars, body) if vars.is_empty() => {
+                self.term(body)
+            }
+            Term::Forall(vars, body) | Term::Exists(vars, body) | Term::Lambda(vars, body) => {
+                let vars = self.sorted_vars(vars);
+                let body = Box::new(self.term(body));
+                self.unbind_variables(vars.len());
+                match term {
+                    Term::Forall(_, _) => Term::Forall(vars, body),
+                    Term::Exists(_, _) => Term::Exists(vars, body),
+                    _ => Term::Lambda(vars, body),
+                }
+            }
+            Term::Match(term, cases) => {
+                let term = self.term(term);
+                let cases = cases
+                    .iter()
+                    .map(|MatchCase(pattern, term)| self.match_case(pattern, term))
+                    .collect();
+                Term::Match(Box::new(term), cases)
+            }
+            Term::Annotated(term, attributes) if attributes.is_empty() => self.term(term),
+            Term::Annotated(term, attributes) => {
+                let term = self.term(term);
+                let attributes = attributes
+                    .iter()
+                    .map(|attribute| match attribute {
+                        Attribute::Pattern(terms) => Attribute::Pattern(MultiPattern(
+                            terms.terms().iter().map(|term| self.term(term)).collect(),
+                        )),
+                        Attribute::NoPattern(term) => Attribute::NoPattern(self.term(term)),
+                        attribute @ (Attribute::Named(_) | Attribute::Other(_, _)) => {
+                            attribute.clone()
+                        }
+                    })
+                    .collect();
+                Term::Annotated(Box::new(term), attributes)
+            }
+            Term::Apply(fun, args) => Term::Apply(
+                Box::new(self.term(fun)),
+                args.iter().map(|arg| self.term(arg)).collect(),
+            ),
+        }
+    }
+
+    fn match_case(&mut self, pattern: &Pattern, term: &Term) -> MatchCase {
+        let head = pattern.head();
+        // without declarations, a single symbol may be a constructor, so it is left as it is
+        let is_variable =
+            pattern.binders().is_empty() && self.declarations && !self.functions.contains_key(head);
+
+        let (pattern, bound) = if is_variable {
+            (Pattern::symbol(self.bind_variable(head)), 1)
+        } else {
+            let head = self.function(head);
+            let binders: Vec<Symbol> = pattern
+                .binders()
+                .iter()
+                .map(|binder| self.bind_variable(binder))
+                .collect();
+            let bound = binders.len();
+            let pattern = Pattern::constructor(head, binders).expect("the new names are distinct");
+            (pattern, bound)
+        };
+        let term = self.term(term);
+        self.unbind_variables(bound);
+        MatchCase(pattern, term)
+    }
+
+    /// Renames the names given by `:named` in the order in which they occur.
+    fn name_terms(&mut self, term: &mut Term) {
+        for_each_name(term, &mut |name| *name = self.declare_function(name));
+    }
+
+    fn function_body(&mut self, args: &[SortedVar], body: &Term) -> (Vec<SortedVar>, Term) {
+        let args = self.sorted_vars(args);
+        let body = self.canonical_term(body);
+        self.unbind_variables(args.len());
+        (args, body)
+    }
+
+    fn function_def(&mut self, def: &FunctionDef, name: Symbol) -> FunctionDef {
+        let sort = self.sort(&def.sort);
+        let (args, body) = self.function_body(&def.args, &def.body);
+        FunctionDef {
+            name,
+            args,
+            sort,
+            body,
+        }
+    }
+
+    fn datatype_dec(&mut self, dec: &DatatypeDec) -> DatatypeDec {
+        let par = self.bind_parameters(&dec.par);
+        let constructors = dec
+            .constructors
+            .iter()
+            .map(|constructor| ConstructorDec {
+                name: self.declare_function(&constructor.name),
+                selectors: constructor
+                    .selectors
+                    .iter()
+                    .map(|selector| SelectorDec {
+                        name: self.declare_function(&selector.name),
+                        sort: self.sort(&selector.sort),
+                    })
+                    .collect(),
+            })
+            .collect();
+        self.parameters.clear();
+        DatatypeDec { par, constructors }
+    }
+
+    fn command(&mut self, command: &Command) -> Command {
+        match command {
+            Command::Assert(term) => Command::Assert(self.canonical_term(term)),
+            Command::CheckSatAssuming(literals) => {
+                let mut literals: Vec<PropLiteral> = literals
+                    .iter()
+                    .map(|literal| PropLiteral {
+                        negate: literal.negate,
+                        symbol: self.function(&literal.symbol),
+                    })
+                    .collect();
+                literals.sort();
+                Command::CheckSatAssuming(literals)
+            }
+            Command::DeclareConst(symbol, sort) => {
+                Command::DeclareConst(self.declare_function(symbol), self.sort(sort))
+            }
+            Command::DeclareDatatype(symbol, dec) => {
+                let name = self.declare_sort(symbol);
+                Command::DeclareDatatype(name, self.datatype_dec(dec))
+            }
+            Command::DeclareDatatypes(entries) => {
+                // the datatypes can refer to each other
+                let sort_decs: Vec<SortDec> = entries
+                    .iter()
+                    .map(|(SortDec(symbol, arity), _)| {
+                        SortDec(self.declare_sort(symbol), arity.clone())
+                    })
+                    .collect();
+                let decs = entries.iter().map(|(_, dec)| self.datatype_dec(dec));
+                Command::DeclareDatatypes(
+                    sort_decs
+                        .into_iter()
+                        .zip(decs.collect::<Vec<_>>())
+                        .collect(),
+                )
+            }
+            Command::DeclareFun(symbol, args, sort) => {
+                let name = self.declare_function(symbol);
+                let args = args.iter().map(|arg| self.sort(arg)).collect();
+                Command::DeclareFun(name, args, self.sort(sort))
+            }
+            Command::DeclareSort(symbol, arity) => {
+                Command::DeclareSort(self.declare_sort(symbol), arity.clone())
+            }
+            Command::DeclareSortParameter(symbol) => {
+                Command::DeclareSortParameter(self.declare_sort(symbol))
+            }
+            Command::DefineConst(symbol, sort, term) => {
+                let name = self.declare_function(symbol);
+                Command::DefineConst(name, self.sort(sort), self.canonical_term(term))
+            }
+            Command::DefineFun(def) => {
+                // the body can not refer to the function, but its name comes first
+                let name = self.declare_function(&def.name);
+                Command::DefineFun(self.function_def(def, name))
+            }
+            Command::DefineFunRec(def) => {
+                let name = self.declare_function(&def.name);
+                Command::DefineFunRec(self.function_def(def, name))
+            }
+            Command::DefineFunsRec(entries) => {
+                let names: Vec<Symbol> = entries
+                    .iter()
+                    .map(|(dec, _)| self.declare_function(&dec.name))
+                    .collect();
+                let entries = entries
+                    .iter()
+                    .zip(names)
+                    .map(|((dec, body), name)| {
+                        let sort = self.sort(&dec.sort);
+                        let (args, body) = self.function_body(&dec.args, body);
+                        (FunctionDec { name, args, sort }, body)
+                    })
+                    .collect();
+                Command::DefineFunsRec(entries)
+            }
+            Command::DefineSort(symbol, params, sort) => {
+                let name = self.declare_sort(symbol);
+                let params = self.bind_parameters(params);
+                let sort = self.sort(sort);
+                self.parameters.clear();
+                Command::DefineSort(name, params, sort)
+            }
+            Command::GetValue(terms) => {
+                Command::GetValue(terms.iter().map(|term| self.canonical_term(term)).collect())
+            }
+            command => command.clone(),
+        }
+    }
+}
+
+/// Calls `f` on the names given by `:named` in the term, in the order in which they occur.
+fn for_each_name(term: &mut Term, f: &mut impl FnMut(&mut Symbol)) {
+    match term {
+        Term::SpecialConstant(_) | Term::QualifiedIdentifier(_) => {}
+        Term::Application(_, args) => args.iter_mut().for_each(|arg| for_each_name(arg, f)),
+        Term::Let(bindings, body) => {
+            for VarBinding(_, term) in bindings {
+                for_each_name(term, f);
+            }
+            for_each_name(body, f);
+        }
+        Term::Forall(_, body) | Term::Exists(_, body) | Term::Lambda(_, body) => {
+            for_each_name(body, f)
+        }
+        Term::Match(term, cases) => {
+            for_each_name(term, f);
+            for MatchCase(_, term) in cases {
+                for_each_name(term, f);
+            }
+        }
+        Term::Annotated(term, attributes) => {
+            for_each_name(term, f);
+            for attribute in attributes {
+                match attribute {
+                    Attribute::Named(name) => f(name),
+                    Attribute::Pattern(terms) => {
+                        terms.0.iter_mut().for_each(|term| for_each_name(term, f))
+                    }
+                    Attribute::NoPattern(term) => for_each_name(term, f),
+                    Attribute::Other(_, _) => {}
+                }
+            }
+        }
+        Term::Apply(fun, args) => {
+            for_each_name(fun, f);
+            args.iter_mut().for_each(|arg| for_each_name(arg, f));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_script, parse_term};
+
+    fn canonical(input: &str) -> String {
+        parse_term(input).unwrap().canonicalize().to_string()
+    }
+
+    #[test]
+    fn canonical_terms() {
+        assert_eq!(
+            canonical("(and (forall ((y Int)) (> y c)) (exists ((x Int) (z Int)) (= z x)))"),
+            "(and (forall ((v1 Int)) (> v1 c)) (exists ((v1 Int) (v2 Int)) (= v1 v2)))"
+        );
+        assert_eq!(
+            canonical("(let ((b (f 2)) (a (f 1))) (+ b (* a 2)))"),
+            "(let ((v1 (f 1)) (v2 (f 2))) (+ v2 (* v1 2)))"
+        );
+        // the free `v1` is not captured
+        assert_eq!(
+            canonical("(forall ((x Int) (y Int) (z Int)) (= x y z v1))"),
+            "(forall ((v2 Int) (v3 Int) (v4 Int)) (= v1 v2 v3 v4))"
+        );
+        assert_eq!(
+            canonical("(and (! (p f1) :named a) (! (q f1) :named b))"),
+            "(and (! (p f1) :named f2) (! (q f1) :named f3))"
+        );
+        assert_eq!(
+            canonical("(forall ((x Int)) (! (= x v1) :named n :pattern ((g x))))"),
+            "(forall ((v2 Int)) (! (= v1 v2) :named f1 :pattern ((g v2))))"
+        );
+        assert_eq!(
+            canonical("(match l (((cons h t) (+ t h)) (x x)))"),
+            "(match l (((cons v1 v2) (+ v1 v2)) (x x)))"
+        );
+
+        let a = parse_term("(or (p x) (forall ((u Bool)) (and u (q x))))").unwrap();
+        let b = parse_term("(or (forall ((w Bool)) (and (q x) w)) (p x))").unwrap();
+        assert_eq!(a.canonicalize(), b.canonicalize());
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+        assert_ne!(
+            a.canonical_hash(),
+            parse_term("(p x)").unwrap().canonical_hash()
+        );
+
+        // the labels do not decide the order of arguments that are otherwise equal
+        let a = parse_term("(and (! p :named a :weight 1) (! p :named b :weight 2))").unwrap();
+        let b = parse_term("(and (! p :named b :weight 1) (! p :named a :weight 2))").unwrap();
+        assert_eq!(a.canonicalize(), b.canonicalize());
+        assert_eq!(
+            b.canonicalize().to_string(),
+            "(and (! p :named f1 :weight 1) (! p :named f2 :weight 2))"
+        );
+        assert_eq!(
+            canonical("(and (! p :named b) (! p :named a))"),
+            "(and (! p :named f1) (! p :named f2))"
+        );
+    }
+
+    #[test]
+    fn canonical_scripts() {
+        let a = parse_script(
+            "(declare-datatype List (par (T) ((nil) (cons (head T) (tail (List T)))))) \
+             (declare-fun p (Int) Bool) (declare-const l (List Int)) \
+             (define-fun q ((x Int)) Bool (p x)) \
+             (assert (match l ((nil false) ((cons h t) (q h)) (other true)))) \
+             (assert (! (or (q 1) (p 2)) :named a1)) \
+             (check-sat-assuming (a1 (not a1))) (get-value ((p 2) (p 1)))",
+        )
+        .unwrap();
+        let b = parse_script(
+            "(declare-datatype Lst (par (X) ((empty) (ins (first X) (rest (Lst X)))))) \
+             (declare-fun r (Int) Bool) (declare-const m (Lst Int)) \
+             (define-fun s ((y Int)) Bool (r y)) \
+             (assert (match m ((empty false) ((ins a b) (s a)) (z true)))) \
+             (assert (! (or (r 2) (s 1)) :named n)) \
+             (check-sat-assuming ((not n) n)) (get-value ((r 2) (r 1)))",
+        )
+        .unwrap();
+
+        let expected = "\
+(declare-datatype S1 (par (P1) ((f1) (f2 (f3 P1) (f4 (S1 P1))))))
+(declare-fun f5 (Int) Bool)
+(declare-const f6 (S1 Int))
+(define-fun f7 ((v1 Int)) Bool (f5 v1))
+(assert (match f6 ((f1 false) ((f2 v1 v2) (f7 v1)) (v1 true))))
+(assert (! (or (f5 2) (f7 1)) :named f8))
+(check-sat-assuming (f8 (not f8)))
+(get-value ((f5 2) (f5 1)))
+";
+        assert_eq!(a.canonicalize().to_string(), expected);
+        assert_eq!(b.canonicalize(), a.canonicalize());
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+        assert_eq!(a.canonicalize().canonicalize(), a.canonicalize());
+    }
+}
@@ -0,0 +1,465 @@
+//! A lossless concrete syntax tree.
+//!
+//! Unlike [`Sexpr`], the tree returned by [`parse_cst`] keeps the whitespace and comments of the
+//! input, and the original spelling of every atom. Printing it yields exactly the text it was
+//! parsed from, so a file can be edited, e.g. by renaming a symbol or replacing a command, without
+//! changing any of the parts that were not touched.
+//!
+//! Nodes convert to [`Sexpr`] and the typed syntax ([`CstNode::to_command`], [`CstNode::to_term`])
+//! and back ([`CstNode::from`]). Nodes created from the typed syntax are laid out the same way
+//! [`Sexpr`] is printed.
+
+use std::fmt::Display;
+
+use super::lexer::{Lexer, Span, Token, TokenKind};
+use super::parser::{atom, ParseError, ParseErrorKind, SpanTree};
+use super::scripts::{Command, Script};
+use super::sexprs::{FromSexprError, Sexpr, SpecialConstant};
+use super::sorts::Sort;
+use super::terms::Term;
+
+/// Text between tokens that does not affect the meaning of the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Trivia {
+    Whitespace(String),
+    /// A comment, including the leading `;`, but not the line break that ends it.
+    Comment(String),
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trivia::Whitespace(text) | Trivia::Comment(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// An atom as it was spelled in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CstAtom {
+    kind: TokenKind,
+    text: String,
+}
+
+impl CstAtom {
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// The text of the atom, including quotes and the `:` of keywords.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn token(&self, span: Span) -> Token<'_> {
+        Token {
+            kind: self.kind,
+            text: self.text.as_str().into(),
+            span,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CstList {
+    pub children: Vec<CstNode>,
+    /// The trivia between the last child and the closing parenthesis.
+    pub trailing: Vec<Trivia>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CstKind {
+    Atom(CstAtom),
+    List(CstList),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CstNode {
+    /// The trivia in front of the node.
+    pub leading: Vec<Trivia>,
+    pub kind: CstKind,
+    span: Option<Span>,
+}
+
+impl CstNode {
+    /// Where the node was in the parsed text. Nodes that were created or replaced later have no
+    /// span.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn children(&self) -> &[CstNode] {
+        match &self.kind {
+            CstKind::Atom(_) => &[],
+            CstKind::List(list) => &list.children,
+        }
+    }
+
+    pub fn children_mut(&mut self) -> &mut [CstNode] {
+        match &mut self.kind {
+            CstKind::Atom(_) => &mut [],
+            CstKind::List(list) => &mut list.children,
+        }
+    }
+
+    /// Replaces the node, keeping the trivia in front of it.
+    pub fn replace(&mut self, node: impl Into<CstNode>) {
+        let leading = std::mem::take(&mut self.leading);
+        *self = node.into();
+        self.leading = leading;
+    }
+
+    /// Calls `f` on the node and all of its descendants, parents before their children.
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut CstNode)) {
+        f(self);
+        for child in self.children_mut() {
+            child.walk_mut(f);
+        }
+    }
+
+    pub fn to_sexpr(&self) -> Result<Sexpr, ParseError> {
+        self.spanned(Span::default()).map(|(sexpr, _)| sexpr)
+    }
+
+    pub fn to_command(&self) -> Result<Command, ParseError> {
+        self.convert(Command::try_from)
+    }
+
+    pub fn to_term(&self) -> Result<Term, ParseError> {
+        self.convert(Term::try_from)
+    }
+
+    pub fn to_sort(&self) -> Result<Sort, ParseError> {
+        self.convert(Sort::try_from)
+    }
+
+    fn convert<T>(
+        &self,
+        convert: impl FnOnce(Sexpr) -> Result<T, FromSexprError>,
+    ) -> Result<T, ParseError> {
+        let (sexpr, spans) = self.spanned(Span::default())?;
+        convert(sexpr).map_err(|err| {
+            let span = spans.locate(err.path());
+            ParseError::new(ParseErrorKind::Syntax(err), span)
+        })
+    }
+
+    /// Converts the node, using the span of the closest ancestor for nodes that have none.
+    fn spanned(&self, parent: Span) -> Result<(Sexpr, SpanTree), ParseError> {
+        let span = self.span.unwrap_or(parent);
+        match &self.kind {
+            CstKind::Atom(a) => Ok((atom(a.token(span))?, SpanTree::leaf(span))),
+            CstKind::List(list) => {
+                let mut seq = Vec::with_capacity(list.children.len());
+                let mut spans = SpanTree::leaf(span);
+                for child in &list.children {
+                    let (sexpr, child_spans) = child.spanned(span)?;
+                    seq.push(sexpr);
+                    spans.children.push(child_spans);
+                }
+                Ok((Sexpr::Sequence(seq), spans))
+            }
+        }
+    }
+}
+
+impl Display for CstNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading {
+            trivia.fmt(f)?;
+        }
+
+        match &self.kind {
+            CstKind::Atom(a) => write!(f, "{}", a.text),
+            CstKind::List(list) => {
+                write!(f, "(")?;
+                for child in &list.children {
+                    child.fmt(f)?;
+                }
+                for trivia in &list.trailing {
+                    trivia.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl From<Sexpr> for CstNode {
+    fn from(sexpr: Sexpr) -> Self {
+        let kind = match sexpr {
+            Sexpr::Sequence(seq) => {
+                let children = seq
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, sexpr)| {
+                        let mut child = CstNode::from(sexpr);
+                        if i > 0 {
+                            child.leading.push(Trivia::Whitespace(" ".to_string()));
+                        }
+                        child
+                    })
+                    .collect();
+
+                CstKind::List(CstList {
+                    children,
+                    trailing: vec![],
+                })
+            }
+            atom => {
+                let kind = match &atom {
+                    Sexpr::SpecialConstant(SpecialConstant::Numeral(_)) => TokenKind::Numeral,
+                    Sexpr::SpecialConstant(SpecialConstant::Decimal(_)) => TokenKind::Decimal,
+                    Sexpr::SpecialConstant(SpecialConstant::Hexadecimal(_)) => {
+                        TokenKind::Hexadecimal
+                    }
+                    Sexpr::SpecialConstant(SpecialConstant::Binary(_)) => TokenKind::Binary,
+                    Sexpr::SpecialConstant(SpecialConstant::String(_)) => TokenKind::String,
+                    Sexpr::Symbol(sym) if sym.is_quoted() => TokenKind::QuotedSymbol,
+                    Sexpr::Symbol(_) => TokenKind::Symbol,
+                    Sexpr::Keyword(_) => TokenKind::Keyword,
+                    Sexpr::Reserved(_) => TokenKind::Reserved,
+                    Sexpr::Sequence(_) => unreachable!("handled above"),
+                };
+
+                CstKind::Atom(CstAtom {
+                    kind,
+                    text: atom.to_string(),
+                })
+            }
+        };
+
+        CstNode {
+            leading: vec![],
+            kind,
+            span: None,
+        }
+    }
+}
+
+impl From<Command> for CstNode {
+    fn from(value: Command) -> Self {
+        Sexpr::from(value).into()
+    }
+}
+
+impl From<Term> for CstNode {
+    fn from(value: Term) -> Self {
+        Sexpr::from(value).into()
+    }
+}
+
+impl From<Sort> for CstNode {
+    fn from(value: Sort) -> Self {
+        Sexpr::from(value).into()
+    }
+}
+
+/// A whole file: its top-level forms and the trivia after the last one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cst {
+    pub nodes: Vec<CstNode>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl Cst {
+    /// Converts each top-level form into a command.
+    pub fn commands(&self) -> impl Iterator<Item = Result<Command, ParseError>> + '_ {
+        self.nodes.iter().map(CstNode::to_command)
+    }
+
+    pub fn to_script(&self) -> Result<Script, ParseError> {
+        self.commands().collect::<Result<_, _>>().map(Script)
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in &self.nodes {
+            node.fmt(f)?;
+        }
+        for trivia in &self.trailing {
+            trivia.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lays out each command on its own line.
+impl From<Script> for Cst {
+    fn from(script: Script) -> Self {
+        let nodes = script
+            .0
+            .into_iter()
+            .enumerate()
+            .map(|(i, command)| {
+                let mut node = CstNode::from(command);
+                if i > 0 {
+                    node.leading.push(Trivia::Whitespace("\n".to_string()));
+                }
+                node
+            })
+            .collect();
+
+        Cst {
+            nodes,
+            trailing: vec![],
+        }
+    }
+}
+
+/// Parses the input into a tree that prints back to exactly the same text.
+pub fn parse_cst(input: &str) -> Result<Cst, ParseError> {
+    let mut stack: Vec<(CstNode, Span)> = vec![];
+    let mut nodes = vec![];
+    let mut trivia = vec![];
+    let mut offset = 0;
+
+    for tok in Lexer::new(input) {
+        let tok = tok?;
+
+        let start = tok.span.start.offset;
+        if start > offset {
+            trivia.push(Trivia::Whitespace(input[offset..start].to_string()));
+        }
+        offset = tok.span.end.offset;
+
+        let node = match tok.kind {
+            TokenKind::Comment => {
+                trivia.push(Trivia::Comment(tok.text.into_owned()));
+                continue;
+            }
+            TokenKind::LeftParen => {
+                let list = CstList {
+                    children: vec![],
+                    trailing: vec![],
+                };
+                let node = CstNode {
+                    leading: std::mem::take(&mut trivia),
+                    kind: CstKind::List(list),
+                    span: None,
+                };
+                stack.push((node, tok.span));
+                continue;
+            }
+            TokenKind::RightParen => {
+                let (mut node, open) = stack.pop().ok_or_else(|| {
+                    ParseError::new(ParseErrorKind::UnexpectedCloseParen, tok.span)
+                })?;
+                if let CstKind::List(list) = &mut node.kind {
+                    list.trailing = std::mem::take(&mut trivia);
+                }
+                node.span = Some(open.to(tok.span));
+                node
+            }
+            _ => CstNode {
+                leading: std::mem::take(&mut trivia),
+                kind: CstKind::Atom(CstAtom {
+                    kind: tok.kind,
+                    text: tok.text.into_owned(),
+                }),
+                span: Some(tok.span),
+            },
+        };
+
+        match stack.last_mut() {
+            Some((
+                CstNode {
+                    kind: CstKind::List(list),
+                    ..
+                },
+                _,
+            )) => list.children.push(node),
+            Some(_) => unreachable!("only lists are pushed on the stack"),
+            None => nodes.push(node),
+        }
+    }
+
+    if let Some((_, open)) = stack.into_iter().next() {
+        return Err(ParseError::new(ParseErrorKind::UnclosedParen, open));
+    }
+
+    if offset < input.len() {
+        trivia.push(Trivia::Whitespace(input[offset..].to_string()));
+    }
+
+    Ok(Cst {
+        nodes,
+        trailing: trivia,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::Symbol;
+    use crate::parser::{parse_command, parse_script};
+
+    const BENCHMARK: &str = "(set-info :smt-lib-version 2.6)
+; declarations
+(declare-fun  x () Int) ; the unknown
+(declare-fun y () Int)
+
+(assert
+  ; both positive
+  (and (> x 0)   (> y |x|)))
+(check-sat)
+(exit)
+; end
+";
+
+    #[test]
+    fn prints_input_unchanged() {
+        for input in [BENCHMARK, "", "  ; only a comment", "(a\r\n b)\t"] {
+            assert_eq!(parse_cst(input).unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn converts_to_ast() {
+        let cst = parse_cst(BENCHMARK).unwrap();
+        let commands: Vec<_> = cst
+            .nodes
+            .iter()
+            .map(|node| Sexpr::from(node.to_command().unwrap()).to_string())
+            .collect();
+        let expected: Vec<_> = parse_script(BENCHMARK)
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|command| Sexpr::from(command).to_string())
+            .collect();
+        assert_eq!(commands, expected);
+
+        let err = parse_cst("(assert (f)) (assert (g x))").unwrap().nodes[0]
+            .to_command()
+            .unwrap_err();
+        assert_eq!(err.span.start.column, 9);
+    }
+
+    #[test]
+    fn edits_keep_layout() {
+        let mut cst = parse_cst(BENCHMARK).unwrap();
+
+        for node in &mut cst.nodes {
+            node.walk_mut(&mut |node| {
+                if matches!(&node.kind, CstKind::Atom(a) if a.text() == "x" || a.text() == "|x|") {
+                    let y = Symbol::new("z".to_string()).unwrap();
+                    node.replace(Sexpr::from(y));
+                }
+            });
+        }
+        cst.nodes[4].replace(parse_command("(check-sat-assuming (p))").unwrap());
+
+        assert_eq!(
+            cst.to_string(),
+            BENCHMARK
+                .replace(" x ", " z ")
+                .replace("|x|", "z")
+                .replace("(check-sat)", "(check-sat-assuming (p))")
+        );
+
+        let cst = Cst::from(parse_script("(check-sat)(exit)").unwrap());
+        assert_eq!(cst.to_string(), "(check-sat)\n(exit)");
+    }
+}
@@ -0,0 +1,481 @@
+//! Parsing that does not stop at the first error.
+//!
+//! [`parse_script_recovering`] reads as much of a script as it can and reports every problem it
+//! finds as a [`Diagnostic`]. It recovers
+//! - after lexical errors, by dropping the command they occur in,
+//! - from unbalanced parentheses, assuming that a `(` followed by a command name at the start of
+//!   a line starts a new command,
+//! - inside commands, by replacing a malformed term, sort or identifier with a placeholder and
+//!   looking for more errors in the rest of the command.
+//!
+//! Commands with errors are left out of the returned script.
+
+use std::fmt::Display;
+use std::iter::Peekable;
+
+use super::lexer::{LexErrorKind, Lexer, Span, Token, TokenKind};
+use super::lexicon::Symbol;
+use super::parser::{atom, ParseError, ParseErrorKind, SpanTree};
+use super::scripts::{Command, CommandType, Script};
+use super::sexprs::{FromSexprError, FromSexprErrorKind, Production, Sexpr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A proposed edit that fixes a problem: replace the text at `span` with `replacement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+    pub suggestion: Option<Suggestion>,
+}
+
+impl Diagnostic {
+    fn error(span: Span, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            span,
+            message: message.into(),
+            expected: None,
+            found: None,
+            suggestion: None,
+        }
+    }
+
+    fn with_suggestion(
+        mut self,
+        message: impl Into<String>,
+        span: Span,
+        replacement: &str,
+    ) -> Self {
+        self.suggestion = Some(Suggestion {
+            message: message.into(),
+            span,
+            replacement: replacement.to_string(),
+        });
+        self
+    }
+
+    fn from_parse_error(err: ParseError, input: &str) -> Self {
+        let diagnostic = Diagnostic::error(err.span, err.kind.to_string());
+        match err.kind {
+            ParseErrorKind::Lex(LexErrorKind::BadEscape) => {
+                let text = &input[err.span.start.offset..err.span.end.offset];
+                let fixed = text.replace(r#"\""#, r#""""#);
+                diagnostic.with_suggestion("write quotes as `\"\"`", err.span, &fixed)
+            }
+            ParseErrorKind::Lex(LexErrorKind::LeadingZero) => {
+                let text = &input[err.span.start.offset..err.span.end.offset];
+                let trimmed = text.trim_start_matches('0');
+                let fixed = match trimmed {
+                    "" => "0".to_string(),
+                    t if t.starts_with('.') => format!("0{t}"),
+                    t => t.to_string(),
+                };
+                diagnostic.with_suggestion("remove the leading zeroes", err.span, &fixed)
+            }
+            ParseErrorKind::Syntax(err) => Self::from_sexpr_error(err, diagnostic.span),
+            _ => diagnostic,
+        }
+    }
+
+    fn from_sexpr_error(err: FromSexprError, span: Span) -> Self {
+        let mut diagnostic = Diagnostic::error(span, err.to_string());
+        match err.kind() {
+            FromSexprErrorKind::Unexpected { found } => {
+                diagnostic.expected = Some(err.production().to_string());
+                diagnostic.found = Some(found.clone());
+            }
+            FromSexprErrorKind::Missing { expected } => {
+                diagnostic.expected = Some(expected.to_string());
+                diagnostic.found = Some("`)`".to_string());
+            }
+            FromSexprErrorKind::TrailingElement { found } => {
+                diagnostic.expected = Some("`)`".to_string());
+                diagnostic.found = Some(found.clone());
+            }
+            FromSexprErrorKind::UnknownCommand(name) => {
+                if let Some(command_type) = closest_command(name) {
+                    let suggested = command_type.name();
+                    diagnostic.message =
+                        format!("`{name}` is not a command, did you mean `{suggested}`?");
+                    diagnostic = diagnostic.with_suggestion(
+                        format!("replace with `{suggested}`"),
+                        span,
+                        suggested,
+                    );
+                }
+            }
+            FromSexprErrorKind::LengthMismatch { .. }
+            | FromSexprErrorKind::ArityMismatch { .. }
+            | FromSexprErrorKind::DuplicateBinder(_)
+            | FromSexprErrorKind::Version(_) => {}
+        }
+
+        diagnostic
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}",
+            self.severity, self.span.start, self.message
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  help: {}", suggestion.message)?;
+        }
+        Ok(())
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The command a misspelled command name most likely refers to.
+fn closest_command(name: &str) -> Option<CommandType> {
+    let normalized = name.to_ascii_lowercase().replace('_', "-");
+    CommandType::ALL
+        .into_iter()
+        .map(|command_type| {
+            (
+                edit_distance(&normalized, command_type.name()),
+                command_type,
+            )
+        })
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, command_type)| command_type)
+}
+
+/// The productions that a symbol can stand in for when repairing a command.
+fn accepts_placeholder(production: Production) -> bool {
+    matches!(
+        production,
+        Production::Symbol
+            | Production::Index
+            | Production::Identifier
+            | Production::Sort
+            | Production::QualifiedIdentifier
+            | Production::Term
+            | Production::PropLiteral
+            | Production::Pattern
+    )
+}
+
+/// Replaces the node at `path` with a placeholder, or removes it if `remove` is set. Returns
+/// whether the node exists.
+fn patch(sexpr: &mut Sexpr, path: &[usize], remove: bool) -> bool {
+    match (path, sexpr) {
+        ([i], Sexpr::Sequence(seq)) if *i < seq.len() && remove => {
+            seq.remove(*i);
+            true
+        }
+        ([i, rest @ ..], Sexpr::Sequence(seq)) if *i < seq.len() => {
+            if rest.is_empty() {
+                seq[*i] = Symbol::new("error".to_string())
+                    .expect("placeholder is a valid symbol")
+                    .into();
+                true
+            } else {
+                patch(&mut seq[*i], rest, remove)
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Converts a complete form into a command, collecting all errors that can be found in it.
+fn convert(sexpr: Sexpr, spans: &SpanTree, diagnostics: &mut Vec<Diagnostic>) -> Option<Command> {
+    const MAX_REPAIRS: usize = 32;
+
+    let mut sexpr = sexpr;
+    let mut failed = false;
+    let mut last_path: Option<Vec<usize>> = None;
+
+    for _ in 0..MAX_REPAIRS {
+        let err = match Command::try_from(sexpr.clone()) {
+            Ok(command) if !failed => return Some(command),
+            Ok(_) => return None,
+            Err(err) => err,
+        };
+
+        failed = true;
+        let path = err.path().to_vec();
+        let remove = matches!(err.kind(), FromSexprErrorKind::TrailingElement { .. });
+        let repairable = accepts_placeholder(err.production()) || remove;
+        diagnostics.push(Diagnostic::from_sexpr_error(
+            err.clone(),
+            spans.locate(&path),
+        ));
+
+        // paths refer to the original form, so they are only reliable until we remove something
+        if !repairable || remove || last_path.as_ref() == Some(&path) {
+            return None;
+        }
+        if !patch(&mut sexpr, &path, remove) {
+            return None;
+        }
+        last_path = Some(path);
+    }
+
+    None
+}
+
+fn is_command_start<'a, I>(tokens: &mut Peekable<I>) -> bool
+where
+    I: Iterator<Item = Result<Token<'a>, ParseError>>,
+{
+    matches!(
+        tokens.peek(),
+        Some(Ok(tok)) if tok.kind == TokenKind::Symbol && CommandType::from_name(&tok.text).is_some()
+    )
+}
+
+/// Parses a script, recovering from errors. Returns the commands that could be parsed, along
+/// with diagnostics for everything that went wrong.
+pub fn parse_script_recovering(input: &str) -> (Script, Vec<Diagnostic>) {
+    let mut commands = vec![];
+    let mut diagnostics = vec![];
+    let mut exit: Option<Span> = None;
+
+    let mut tokens = Lexer::new(input)
+        .filter(|tok| !matches!(tok, Ok(tok) if tok.kind == TokenKind::Comment))
+        .map(|tok| tok.map_err(ParseError::from))
+        .peekable();
+
+    // the open lists of the current form, and whether the form had lexical errors
+    let mut stack: Vec<(Vec<Sexpr>, SpanTree)> = vec![];
+    let mut poisoned = false;
+    let mut last_end = Span::default();
+
+    loop {
+        let tok = match tokens.next() {
+            None => break,
+            Some(Err(err)) => {
+                diagnostics.push(Diagnostic::from_parse_error(err, input));
+                poisoned = !stack.is_empty();
+                continue;
+            }
+            Some(Ok(tok)) => tok,
+        };
+
+        // a command name at the start of a line, while a form is still open
+        if tok.kind == TokenKind::LeftParen
+            && tok.span.start.column == 1
+            && !stack.is_empty()
+            && is_command_start(&mut tokens)
+        {
+            close_unclosed(&mut stack, last_end, &mut diagnostics);
+            let (seq, spans) = stack.pop().expect("stack is not empty");
+            if !poisoned {
+                commands.extend(convert(Sexpr::Sequence(seq), &spans, &mut diagnostics));
+            }
+            poisoned = false;
+        }
+
+        last_end = Span::new(tok.span.end, tok.span.end);
+
+        let node = match tok.kind {
+            TokenKind::LeftParen => {
+                stack.push((vec![], SpanTree::leaf(tok.span)));
+                None
+            }
+            TokenKind::RightParen => match stack.pop() {
+                Some((seq, mut spans)) => {
+                    spans.span = spans.span.to(tok.span);
+                    Some((Sexpr::Sequence(seq), spans))
+                }
+                None => {
+                    diagnostics.push(
+                        Diagnostic::error(tok.span, "unexpected `)`").with_suggestion(
+                            "remove this `)`",
+                            tok.span,
+                            "",
+                        ),
+                    );
+                    None
+                }
+            },
+            _ => {
+                let span = tok.span;
+                match atom(tok) {
+                    Ok(sexpr) => Some((sexpr, SpanTree::leaf(span))),
+                    Err(err) => {
+                        diagnostics.push(Diagnostic::from_parse_error(err, input));
+                        poisoned = !stack.is_empty();
+                        None
+                    }
+                }
+            }
+        };
+
+        let (sexpr, spans) = match node {
+            Some(node) => node,
+            None => continue,
+        };
+
+        match stack.last_mut() {
+            Some((seq, parent)) => {
+                seq.push(sexpr);
+                parent.children.push(spans);
+            }
+            None => {
+                if let Some(exit) = exit {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        ..Diagnostic::error(
+                            spans.span,
+                            format!("command after `(exit)` at {} is never executed", exit.start),
+                        )
+                    });
+                }
+                if std::mem::take(&mut poisoned) {
+                    continue;
+                }
+                if let Some(command) = convert(sexpr, &spans, &mut diagnostics) {
+                    if matches!(command, Command::Exit) && exit.is_none() {
+                        exit = Some(spans.span);
+                    }
+                    commands.push(command);
+                }
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        close_unclosed(&mut stack, last_end, &mut diagnostics);
+        let (seq, spans) = stack.pop().expect("stack is not empty");
+        if !poisoned {
+            commands.extend(convert(Sexpr::Sequence(seq), &spans, &mut diagnostics));
+        }
+    }
+
+    (Script(commands), diagnostics)
+}
+
+/// Reports the outermost open list as unclosed and closes all open lists, leaving the complete
+/// form on the stack.
+fn close_unclosed(
+    stack: &mut Vec<(Vec<Sexpr>, SpanTree)>,
+    end: Span,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let missing = ")".repeat(stack.len());
+    let open = stack[0].1.span;
+    diagnostics.push(Diagnostic::error(open, "unclosed `(`").with_suggestion(
+        format!("insert `{missing}`"),
+        end,
+        &missing,
+    ));
+
+    while stack.len() > 1 {
+        let (seq, mut spans) = stack.pop().expect("stack has more than one element");
+        spans.span = spans.span.to(end);
+        let (parent_seq, parent_spans) = stack.last_mut().expect("stack is not empty");
+        parent_seq.push(Sexpr::Sequence(seq));
+        parent_spans.children.push(spans);
+    }
+
+    let (seq, spans) = stack.pop().expect("stack is not empty");
+    let mut spans = spans;
+    spans.span = spans.span.to(end);
+    stack.push((seq, spans));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_all_errors() {
+        let input = "(declare-const x Int)
+(define_sort S () Int)
+(assert (and (f) (> x 007) x))
+(assert (g (h) (_ bv)))
+(check-sat";
+
+        let (script, diagnostics) = parse_script_recovering(input);
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            [
+                "error: 2:2: `define_sort` is not a command, did you mean `define-sort`?\n  help: replace with `define-sort`",
+                "error: 3:23: numerals must not have leading zeroes\n  help: remove the leading zeroes",
+                "error: 4:12: incomplete term: missing argument",
+                "error: 4:16: incomplete identifier: missing index",
+                "error: 5:1: unclosed `(`\n  help: insert `)`",
+            ]
+        );
+
+        let suggestion = diagnostics[0].suggestion.as_ref().unwrap();
+        assert_eq!(suggestion.replacement, "define-sort");
+        assert_eq!(
+            &input[suggestion.span.start.offset..suggestion.span.end.offset],
+            "define_sort"
+        );
+
+        // `declare-const` and the auto-closed `check-sat` survive
+        assert_eq!(script.0.len(), 2);
+        assert!(matches!(script.0[1], Command::CheckSat));
+    }
+
+    #[test]
+    fn unclosed_command_is_cut_at_next_command() {
+        let input = "(assert (> x 0)\n(check-sat)\n)";
+        let (script, diagnostics) = parse_script_recovering(input);
+
+        assert_eq!(script.0.len(), 2);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "unclosed `(`");
+        assert_eq!(diagnostics[0].span.start.line, 1);
+        assert_eq!(diagnostics[1].message, "unexpected `)`");
+    }
+
+    #[test]
+    fn expected_and_found() {
+        let (_, diagnostics) =
+            parse_script_recovering("(declare-fun f (Int) :Int)\n(exit)\n(exit)");
+        assert_eq!(diagnostics[0].expected.as_deref(), Some("sort"));
+        assert_eq!(diagnostics[0].found.as_deref(), Some("keyword `:Int`"));
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+    }
+}
@@ -5,7 +5,7 @@
 //! > theories it is convenient to have indexed identifiers as well. Instead of having a special token
 //! > syntax for that, indexed identifiers are defined more systematically as the application of the
 //! > reserved word _ to a symbol and one or more indices. Indices can be numerals or symbols.(8)
-//! > ```
+//! > ```text
 //! >  〈index 〉      ::= 〈numeral 〉 | 〈symbol 〉
 //! >  〈identifier 〉 ::= 〈symbol 〉 | ( _ 〈symbol 〉 〈index 〉+ )
 //! > ```
@@ -13,14 +13,36 @@
 use std::fmt::Display;
 
 use super::{
-    lexicon::{Reserved, Symbol},
-    sexprs::{Sexpr, SpecialConstant::Numeral},
+    lexicon::{self, Reserved, Symbol, Text},
+    sexprs::{self, Elements, FromSexprError, Production, Sexpr, SpecialConstant::Numeral},
+    writer,
 };
 
-#[derive(Debug, Clone)]
-pub enum Index {
-    Numeral(i64),
-    Symbol(Symbol),
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Index<T = String> {
+    Numeral(lexicon::Numeral),
+    Symbol(Symbol<T>),
+}
+
+impl<T: Text> Index<T> {
+    pub fn into_owned(self) -> Index {
+        match self {
+            Index::Numeral(num) => Index::Numeral(num),
+            Index::Symbol(sym) => Index::Symbol(sym.into_owned()),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Index<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::SpecialConstant(Numeral(num)) => Ok(Index::Numeral(num)),
+            Sexpr::Symbol(sym) => Ok(Index::Symbol(sym)),
+            other => Err(FromSexprError::unexpected(Production::Index, &other)),
+        }
+    }
 }
 
 impl From<Index> for Sexpr {
@@ -32,7 +54,7 @@ impl From<Index> for Sexpr {
     }
 }
 
-impl Display for Index {
+impl<T: Text> Display for Index<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Index::Numeral(num) => num.fmt(f),
@@ -41,10 +63,74 @@ impl Display for Index {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Identifier {
-    symbol: Symbol,
-    indexes: Vec<Index>,
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Identifier<T = String> {
+    pub(crate) symbol: Symbol<T>,
+    pub(crate) indexes: Vec<Index<T>>,
+}
+
+impl<T: Text> Identifier<T> {
+    /// An identifier that is just a symbol.
+    pub fn new(symbol: Symbol<T>) -> Self {
+        Identifier {
+            symbol,
+            indexes: vec![],
+        }
+    }
+
+    /// An indexed identifier `(_ symbol indexes+)`, e.g. `(_ BitVec 32)`. Without indexes, this
+    /// is the same as [`Identifier::new`].
+    pub fn indexed(symbol: Symbol<T>, indexes: Vec<Index<T>>) -> Self {
+        Identifier { symbol, indexes }
+    }
+
+    pub fn symbol(&self) -> &Symbol<T> {
+        &self.symbol
+    }
+
+    pub fn indexes(&self) -> &[Index<T>] {
+        &self.indexes
+    }
+
+    pub fn into_owned(self) -> Identifier {
+        Identifier {
+            symbol: self.symbol.into_owned(),
+            indexes: sexprs::into_owned(self.indexes, Index::into_owned),
+        }
+    }
+
+    /// Whether the elements of a list start with `_`, i.e. form an indexed identifier.
+    pub(crate) fn is_indexed(seq: &[Sexpr<T>]) -> bool {
+        matches!(seq.first(), Some(Sexpr::Reserved(Reserved::Underscore)))
+    }
+}
+
+impl<T: Text> From<Symbol<T>> for Identifier<T> {
+    fn from(value: Symbol<T>) -> Self {
+        Identifier::new(value)
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Identifier<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Symbol(symbol) => Ok(Identifier {
+                symbol,
+                indexes: vec![],
+            }),
+            Sexpr::Sequence(seq) if Self::is_indexed(&seq) => {
+                let mut elems = Elements::new(Production::Identifier, seq);
+                elems.next("`_`", |_| Ok(()))?;
+                let symbol = elems.next("symbol", Symbol::try_from)?;
+                let indexes = elems.rest_nonempty("index", Index::try_from)?;
+
+                Ok(Identifier { symbol, indexes })
+            }
+            other => Err(FromSexprError::unexpected(Production::Identifier, &other)),
+        }
+    }
 }
 
 impl From<Identifier> for Sexpr {
@@ -62,18 +148,8 @@ impl From<Identifier> for Sexpr {
     }
 }
 
-impl Display for Identifier {
+impl<T: Text> Display for Identifier<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.indexes.is_empty() {
-            self.symbol.fmt(f)
-        } else {
-            write!(f, "(_ {sym}", sym = self.symbol)?;
-
-            for index in &self.indexes {
-                write!(f, " {index}")?;
-            }
-
-            write!(f, ")")
-        }
+        writer::display(self, f)
     }
 }
@@ -0,0 +1,795 @@
+//! From the spec:
+//! > 3.1 Lexicon
+//! >
+//! > The syntax rules in this chapter are given directly with respect to streams of lexical tokens.
+//! > The lexicon of SMT-LIB consists of the following lexical tokens: (...)
+//!
+//! This module turns text into that token stream. [`Lexer`] works on a `&str` and hands out
+//! tokens that borrow from it, [`ReaderLexer`] works on any [`BufRead`] and hands out owned
+//! tokens. Both use the same character classes as the printer (see
+//! [`predicates`](crate::lexicon::predicates)), so that anything we print lexes back to the same
+//! tokens.
+
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io::{self, BufRead};
+
+use super::lexicon::predicates::{
+    is_binary_digit, is_digit, is_hex_digit, is_numeral, is_printable, is_reserved_word,
+    is_simple_symbol_char, is_white_space,
+};
+
+/// A location in the input. Lines and columns start at 1, columns count characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn start() -> Self {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn advance(&mut self, c: char) {
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+
+    /// Like `advance`, but for a single byte of UTF-8 encoded input.
+    pub(crate) fn advance_byte(&mut self, b: u8) {
+        self.offset += 1;
+        if b == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if b & 0xc0 != 0x80 {
+            // only count the first byte of each character
+            self.column += 1;
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A half-open range `start..end` of the input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    /// The smallest span that covers both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    LeftParen,
+    RightParen,
+    Numeral,
+    Decimal,
+    Hexadecimal,
+    Binary,
+    String,
+    /// A simple symbol that is not a reserved word.
+    Symbol,
+    /// A symbol enclosed in `|`.
+    QuotedSymbol,
+    Keyword,
+    /// One of the reserved words that are not command names, e.g. `_`, `!` or `let`.
+    Reserved,
+    /// A `;` comment, up to but not including the line break.
+    Comment,
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TokenKind::LeftParen => "`(`",
+            TokenKind::RightParen => "`)`",
+            TokenKind::Numeral => "numeral",
+            TokenKind::Decimal => "decimal",
+            TokenKind::Hexadecimal => "hexadecimal",
+            TokenKind::Binary => "binary",
+            TokenKind::String => "string literal",
+            TokenKind::Symbol => "symbol",
+            TokenKind::QuotedSymbol => "quoted symbol",
+            TokenKind::Keyword => "keyword",
+            TokenKind::Reserved => "reserved word",
+            TokenKind::Comment => "comment",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// A lexical token. `text` is the token exactly as it appears in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: Cow<'a, str>,
+    pub span: Span,
+}
+
+impl<'a> Token<'a> {
+    /// The payload of the token, i.e. the text without the syntax around it: the name of a quoted
+    /// symbol without the `|`, a keyword without the `:`, the digits of a `#x`/`#b` literal, the
+    /// unescaped contents of a string literal and the text of a comment after the `;`.
+    pub fn content(&self) -> Cow<'_, str> {
+        payload(self.kind, &self.text)
+    }
+
+    /// Like `content`, but hands out the payload, so that it borrows from the input if the token
+    /// does.
+    pub fn into_content(self) -> Cow<'a, str> {
+        match self.text {
+            Cow::Borrowed(text) => payload(self.kind, text),
+            Cow::Owned(ref text) => Cow::Owned(payload(self.kind, text).into_owned()),
+        }
+    }
+
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            kind: self.kind,
+            text: Cow::Owned(self.text.into_owned()),
+            span: self.span,
+        }
+    }
+}
+
+/// The payload of a token with the given kind and text, see [`Token::content`].
+fn payload(kind: TokenKind, text: &str) -> Cow<'_, str> {
+    match kind {
+        TokenKind::String => {
+            let inner = &text[1..text.len() - 1];
+            if inner.contains(r#""""#) {
+                Cow::Owned(inner.replace(r#""""#, r#"""#))
+            } else {
+                Cow::Borrowed(inner)
+            }
+        }
+        TokenKind::QuotedSymbol => Cow::Borrowed(&text[1..text.len() - 1]),
+        TokenKind::Hexadecimal | TokenKind::Binary => Cow::Borrowed(&text[2..]),
+        TokenKind::Keyword | TokenKind::Comment => Cow::Borrowed(&text[1..]),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+#[derive(Debug)]
+pub enum LexErrorKind {
+    /// A character that can not start or continue a token here.
+    UnexpectedCharacter(char),
+    /// A character that is not allowed inside a string literal or quoted symbol.
+    IllegalCharacter(char),
+    /// A string literal that looks like it uses backslash escapes. SMT-LIB 2.6 only knows `""`.
+    BadEscape,
+    UnterminatedString,
+    UnterminatedQuotedSymbol,
+    /// A numeral (or the integer part of a decimal) with leading zeroes.
+    LeadingZero,
+    /// `#x`, `#b`, `1.` or `:` without the characters that have to follow.
+    IncompleteLiteral,
+    InvalidUtf8,
+    Io(io::Error),
+}
+
+impl Display for LexErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {c:?}"),
+            LexErrorKind::IllegalCharacter(c) => write!(f, "illegal character {c:?}"),
+            LexErrorKind::BadEscape => write!(
+                f,
+                r#"invalid escape sequence; the only escape in string literals is "" for ""#
+            ),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedQuotedSymbol => write!(f, "unterminated quoted symbol"),
+            LexErrorKind::LeadingZero => write!(f, "numerals must not have leading zeroes"),
+            LexErrorKind::IncompleteLiteral => write!(f, "incomplete literal"),
+            LexErrorKind::InvalidUtf8 => write!(f, "input is not valid UTF-8"),
+            LexErrorKind::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span.start, self.kind)
+    }
+}
+
+impl std::error::Error for LexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            LexErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Where the scanner gets its characters from, and where it keeps the text of the current token.
+trait Source<'a> {
+    fn peek(&mut self) -> Result<Option<char>, LexErrorKind>;
+
+    /// Consumes the character returned by the last call to `peek`.
+    fn bump(&mut self, c: char);
+
+    /// Starts recording a new token at the current position.
+    fn mark(&mut self);
+
+    /// The text consumed since the last call to `mark`.
+    fn current(&self) -> &str;
+
+    /// Like `current`, but hands the text out so it can be stored in a token.
+    fn text(&mut self) -> Cow<'a, str>;
+}
+
+struct StrSource<'a> {
+    input: &'a str,
+    pos: usize,
+    start: usize,
+}
+
+impl<'a> Source<'a> for StrSource<'a> {
+    fn peek(&mut self) -> Result<Option<char>, LexErrorKind> {
+        Ok(self.input[self.pos..].chars().next())
+    }
+
+    fn bump(&mut self, c: char) {
+        self.pos += c.len_utf8();
+    }
+
+    fn mark(&mut self) {
+        self.start = self.pos;
+    }
+
+    fn current(&self) -> &str {
+        &self.input[self.start..self.pos]
+    }
+
+    fn text(&mut self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.input[self.start..self.pos])
+    }
+}
+
+struct ReaderSource<R> {
+    reader: R,
+    peeked: Option<char>,
+    buf: String,
+}
+
+impl<R: BufRead> ReaderSource<R> {
+    fn next_byte(&mut self) -> Result<Option<u8>, LexErrorKind> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok([]) => return Ok(None),
+                Ok(buf) => {
+                    let b = buf[0];
+                    self.reader.consume(1);
+                    return Ok(Some(b));
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(LexErrorKind::Io(err)),
+            }
+        }
+    }
+
+    fn decode(&mut self) -> Result<Option<char>, LexErrorKind> {
+        let first = match self.next_byte()? {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+
+        let width = match first {
+            0x00..=0x7f => return Ok(Some(first as char)),
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Err(LexErrorKind::InvalidUtf8),
+        };
+
+        let mut bytes = [first, 0, 0, 0];
+        for byte in bytes.iter_mut().take(width).skip(1) {
+            *byte = self.next_byte()?.ok_or(LexErrorKind::InvalidUtf8)?;
+        }
+
+        std::str::from_utf8(&bytes[..width])
+            .map_err(|_| LexErrorKind::InvalidUtf8)
+            .map(|s| s.chars().next())
+    }
+}
+
+impl<R: BufRead> Source<'static> for ReaderSource<R> {
+    fn peek(&mut self) -> Result<Option<char>, LexErrorKind> {
+        if self.peeked.is_none() {
+            self.peeked = self.decode()?;
+        }
+
+        Ok(self.peeked)
+    }
+
+    fn bump(&mut self, c: char) {
+        self.peeked = None;
+        self.buf.push(c);
+    }
+
+    fn mark(&mut self) {
+        self.buf.clear();
+    }
+
+    fn current(&self) -> &str {
+        &self.buf
+    }
+
+    fn text(&mut self) -> Cow<'static, str> {
+        Cow::Owned(std::mem::take(&mut self.buf))
+    }
+}
+
+/// The characters that may directly follow an atom.
+fn is_delimiter(c: char) -> bool {
+    is_white_space(c) || matches!(c, '(' | ')' | ';' | '"' | '|')
+}
+
+struct Scanner<S> {
+    source: S,
+    pos: Position,
+    start: Position,
+}
+
+impl<'a, S: Source<'a>> Scanner<S> {
+    fn new(source: S, pos: Position) -> Self {
+        Scanner {
+            source,
+            pos,
+            start: pos,
+        }
+    }
+
+    fn error(&self, kind: LexErrorKind) -> LexError {
+        LexError {
+            kind,
+            span: Span::new(self.start, self.pos),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<char>, LexError> {
+        self.source.peek().map_err(|kind| LexError {
+            kind,
+            span: Span::new(self.pos, self.pos),
+        })
+    }
+
+    fn bump(&mut self, c: char) {
+        self.source.bump(c);
+        self.pos.advance(c);
+    }
+
+    fn bump_while(&mut self, pred: impl Fn(char) -> bool) -> Result<usize, LexError> {
+        let mut count = 0;
+        while let Some(c) = self.peek()? {
+            if !pred(c) {
+                break;
+            }
+            self.bump(c);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Consumes the rest of a malformed atom so that lexing can continue after it.
+    fn skip_atom(&mut self) -> Result<(), LexError> {
+        self.bump_while(|c| !is_delimiter(c)).map(|_| ())
+    }
+
+    fn token(&mut self, kind: TokenKind) -> Token<'a> {
+        Token {
+            kind,
+            text: self.source.text(),
+            span: Span::new(self.start, self.pos),
+        }
+    }
+
+    /// Makes sure an atom is followed by a delimiter, and returns the token.
+    fn finish_atom(&mut self, kind: TokenKind) -> Result<Token<'a>, LexError> {
+        match self.peek()? {
+            Some(c) if !is_delimiter(c) => {
+                self.skip_atom()?;
+                Err(self.error(LexErrorKind::UnexpectedCharacter(c)))
+            }
+            _ => Ok(self.token(kind)),
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Result<Token<'a>, LexError>> {
+        match self.bump_while(is_white_space) {
+            Ok(_) => {}
+            Err(err) => return Some(Err(err)),
+        }
+
+        self.start = self.pos;
+        self.source.mark();
+
+        match self.peek() {
+            Ok(None) => None,
+            Ok(Some(c)) => Some(self.scan(c)),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    fn scan(&mut self, c: char) -> Result<Token<'a>, LexError> {
+        self.bump(c);
+        match c {
+            '(' => Ok(self.token(TokenKind::LeftParen)),
+            ')' => Ok(self.token(TokenKind::RightParen)),
+            ';' => {
+                self.bump_while(|c| c != '\n' && c != '\r')?;
+                Ok(self.token(TokenKind::Comment))
+            }
+            '"' => self.scan_string(),
+            '|' => self.scan_quoted_symbol(),
+            '#' => self.scan_prefixed_literal(),
+            ':' => {
+                if self.bump_while(is_simple_symbol_char)? == 0 {
+                    self.skip_atom()?;
+                    return Err(self.error(LexErrorKind::IncompleteLiteral));
+                }
+                self.finish_atom(TokenKind::Keyword)
+            }
+            c if is_digit(c) => self.scan_number(),
+            c if is_simple_symbol_char(c) => {
+                self.bump_while(is_simple_symbol_char)?;
+                let kind = if is_reserved_word(self.source.current()) {
+                    TokenKind::Reserved
+                } else {
+                    TokenKind::Symbol
+                };
+                self.finish_atom(kind)
+            }
+            c => Err(self.error(LexErrorKind::UnexpectedCharacter(c))),
+        }
+    }
+
+    fn scan_number(&mut self) -> Result<Token<'a>, LexError> {
+        self.bump_while(is_digit)?;
+
+        if !is_numeral(self.source.current()) {
+            self.skip_atom()?;
+            return Err(self.error(LexErrorKind::LeadingZero));
+        }
+
+        if self.peek()? != Some('.') {
+            return self.finish_atom(TokenKind::Numeral);
+        }
+
+        self.bump('.');
+        if self.bump_while(is_digit)? == 0 {
+            self.skip_atom()?;
+            return Err(self.error(LexErrorKind::IncompleteLiteral));
+        }
+
+        self.finish_atom(TokenKind::Decimal)
+    }
+
+    fn scan_prefixed_literal(&mut self) -> Result<Token<'a>, LexError> {
+        let (kind, pred): (_, fn(char) -> bool) = match self.peek()? {
+            Some('x') => {
+                self.bump('x');
+                (TokenKind::Hexadecimal, is_hex_digit)
+            }
+            Some('b') => {
+                self.bump('b');
+                (TokenKind::Binary, is_binary_digit)
+            }
+            Some(c) => {
+                self.skip_atom()?;
+                return Err(self.error(LexErrorKind::UnexpectedCharacter(c)));
+            }
+            None => return Err(self.error(LexErrorKind::IncompleteLiteral)),
+        };
+
+        if self.bump_while(pred)? == 0 {
+            self.skip_atom()?;
+            return Err(self.error(LexErrorKind::IncompleteLiteral));
+        }
+
+        self.finish_atom(kind)
+    }
+
+    fn scan_string(&mut self) -> Result<Token<'a>, LexError> {
+        let mut illegal = None;
+        let mut last = None;
+
+        loop {
+            match self.peek()? {
+                None => return Err(self.error(LexErrorKind::UnterminatedString)),
+                Some('"') => {
+                    self.bump('"');
+                    if self.peek()? == Some('"') {
+                        // "" is an escaped quote, keep going
+                        self.bump('"');
+                        last = Some('"');
+                    } else {
+                        break;
+                    }
+                }
+                Some(c) => {
+                    if !(is_printable(c) || is_white_space(c)) && illegal.is_none() {
+                        illegal = Some(c);
+                    }
+                    self.bump(c);
+                    last = Some(c);
+                }
+            }
+        }
+
+        if let Some(c) = illegal {
+            return Err(self.error(LexErrorKind::IllegalCharacter(c)));
+        }
+
+        match self.peek()? {
+            // something like "a \"quoted\" word", which is not how SMT-LIB 2.6 escapes quotes
+            Some(c) if !is_delimiter(c) && last == Some('\\') => {
+                // skip to where the string was probably meant to end, i.e. the next quote that
+                // is not preceded by a backslash
+                let mut escaped = false;
+                while let Some(c) = self.peek()? {
+                    self.bump(c);
+                    match c {
+                        '"' if !escaped => break,
+                        '\\' => escaped = !escaped,
+                        _ => escaped = false,
+                    }
+                }
+                Err(self.error(LexErrorKind::BadEscape))
+            }
+            _ => self.finish_atom(TokenKind::String),
+        }
+    }
+
+    fn scan_quoted_symbol(&mut self) -> Result<Token<'a>, LexError> {
+        let mut illegal = None;
+
+        loop {
+            match self.peek()? {
+                None => return Err(self.error(LexErrorKind::UnterminatedQuotedSymbol)),
+                Some('|') => {
+                    self.bump('|');
+                    break;
+                }
+                Some(c) => {
+                    if (c == '\\' || !(is_printable(c) || is_white_space(c))) && illegal.is_none() {
+                        illegal = Some(c);
+                    }
+                    self.bump(c);
+                }
+            }
+        }
+
+        match illegal {
+            Some(c) => Err(self.error(LexErrorKind::IllegalCharacter(c))),
+            None => self.finish_atom(TokenKind::QuotedSymbol),
+        }
+    }
+}
+
+/// Lexes a string slice. Comments are returned as tokens, whitespace is skipped.
+///
+/// After an error the lexer skips the offending characters, so iteration can continue.
+pub struct Lexer<'a> {
+    scanner: Scanner<StrSource<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::starting_at(input, Position::start())
+    }
+
+    /// Creates a lexer for input that starts at `position` of some larger text, so that the spans
+    /// of the tokens refer to that text.
+    pub fn starting_at(input: &'a str, position: Position) -> Self {
+        Lexer {
+            scanner: Scanner::new(
+                StrSource {
+                    input,
+                    pos: 0,
+                    start: 0,
+                },
+                position,
+            ),
+        }
+    }
+
+    /// The position of the next character that has not been lexed yet.
+    pub fn position(&self) -> Position {
+        self.scanner.pos
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner.next_token()
+    }
+}
+
+/// Lexes the contents of a [`BufRead`], one character at a time. Only the text of the current
+/// token is buffered.
+pub struct ReaderLexer<R> {
+    scanner: Scanner<ReaderSource<R>>,
+}
+
+impl<R: BufRead> ReaderLexer<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderLexer {
+            scanner: Scanner::new(
+                ReaderSource {
+                    reader,
+                    peeked: None,
+                    buf: String::new(),
+                },
+                Position::start(),
+            ),
+        }
+    }
+
+    /// The position of the next character that has not been lexed yet.
+    pub fn position(&self) -> Position {
+        self.scanner.pos
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderLexer<R> {
+    type Item = Result<Token<'static>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner.next_token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, String)> {
+        Lexer::new(input)
+            .map(|tok| {
+                let tok = tok.unwrap();
+                (tok.kind, tok.content().into_owned())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lex_command() {
+        use TokenKind::*;
+
+        let input = r#"(assert (! (= |x y| #x0F "a ""b""" 12.50 #b01) :named a0)) ; done"#;
+        assert_eq!(
+            kinds(input),
+            vec![
+                (LeftParen, "(".to_string()),
+                (Symbol, "assert".to_string()),
+                (LeftParen, "(".to_string()),
+                (Reserved, "!".to_string()),
+                (LeftParen, "(".to_string()),
+                (Symbol, "=".to_string()),
+                (QuotedSymbol, "x y".to_string()),
+                (Hexadecimal, "0F".to_string()),
+                (String, r#"a "b""#.to_string()),
+                (Decimal, "12.50".to_string()),
+                (Binary, "01".to_string()),
+                (RightParen, ")".to_string()),
+                (Keyword, "named".to_string()),
+                (Symbol, "a0".to_string()),
+                (RightParen, ")".to_string()),
+                (RightParen, ")".to_string()),
+                (Comment, " done".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let tokens: Vec<_> = Lexer::new("(a\n  bc)").map(Result::unwrap).collect();
+        let bc = &tokens[2];
+        assert_eq!(
+            bc.span.start,
+            Position {
+                offset: 5,
+                line: 2,
+                column: 3
+            }
+        );
+        assert_eq!(
+            bc.span.end,
+            Position {
+                offset: 7,
+                line: 2,
+                column: 5
+            }
+        );
+    }
+
+    #[test]
+    fn errors_and_recovery() {
+        let results: Vec<_> = Lexer::new(r#"007 #xg "a \"b\" c" ok"#).collect();
+        assert!(matches!(
+            results[0].as_ref().unwrap_err().kind,
+            LexErrorKind::LeadingZero
+        ));
+        assert!(matches!(
+            results[1].as_ref().unwrap_err().kind,
+            LexErrorKind::IncompleteLiteral
+        ));
+        assert!(matches!(
+            results[2].as_ref().unwrap_err().kind,
+            LexErrorKind::BadEscape
+        ));
+        assert_eq!(results.last().unwrap().as_ref().unwrap().text, "ok");
+    }
+
+    #[test]
+    fn reader_matches_str() {
+        let input = "(declare-fun |λ| () Int) ; ünïcode\n(check-sat)";
+        let from_str: Vec<_> = Lexer::new(input)
+            .map(|tok| tok.unwrap().into_owned())
+            .collect();
+        let from_reader: Vec<_> = ReaderLexer::new(input.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(from_str, from_reader);
+    }
+}
@@ -9,22 +9,79 @@ pub fn escape_string(string: &str) -> String {
     string.replace(r#"""#, r#""""#)
 }
 
-use std::fmt::Display;
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use std::hash::Hash;
 
 use predicates::{is_printable, is_white_space};
 
 use super::{
     scripts::CommandType,
-    sexprs::{Sexpr, SpecialConstant},
+    sexprs::{FromSexprError, Production, Sexpr, SpecialConstant},
     terms::Term,
 };
 
-// TODO: impl Display
-#[derive(Debug, Clone)]
+macro_rules! invalid_text_errors {
+    ($($t:ident => $what:literal),*) => {$(
+        impl Display for $t {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{:?} is not a valid {}", self.0, $what)
+            }
+        }
+
+        impl std::error::Error for $t {}
+    )*};
+}
+
+invalid_text_errors!(
+    InvalidStringError => "string literal",
+    InvalidNumeralError => "numeral",
+    InvalidHexadecimalError => "hexadecimal",
+    InvalidBinaryError => "binary",
+    InvalidDecimalError => "decimal",
+    InvalidSymbolError => "symbol",
+    InvalidKeywordError => "keyword"
+);
+
+/// The text of symbols, keywords and string literals, which the syntax types are generic over. It
+/// is a [`String`] by default, or a [`Cow`] that borrows from the input for the types of the
+/// [`borrowed`](crate::borrowed) module.
+pub trait Text: AsRef<str> + Clone + fmt::Debug + Eq + Hash + Ord {
+    fn into_string(self) -> String;
+}
+
+impl Text for String {
+    fn into_string(self) -> String {
+        self
+    }
+}
+
+impl Text for Cow<'_, str> {
+    fn into_string(self) -> String {
+        self.into_owned()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InvalidStringError(String);
 
-#[derive(Clone, Debug)]
-pub struct StringConstant(String);
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StringConstant<T = String>(T);
+
+impl<T: Text> StringConstant<T> {
+    /// A string literal read by the lexer, which only accepts valid ones.
+    pub(crate) fn from_text(string: T) -> Self {
+        StringConstant(string)
+    }
+
+    pub fn string_constant(&self) -> &str {
+        self.0.as_ref()
+    }
+
+    pub fn into_owned(self) -> StringConstant {
+        StringConstant(self.0.into_string())
+    }
+}
 
 impl StringConstant {
     pub fn new(string: String) -> Result<Self, InvalidStringError> {
@@ -46,11 +103,22 @@ impl StringConstant {
     pub fn new_str_expect(string: &str, expect_msg: &str) -> Self {
         Self::new_expect(string.to_string(), expect_msg)
     }
+}
 
-    pub fn string_constant(&self) -> &str {
-        &self.0
+impl<T: Text> TryFrom<Sexpr<T>> for StringConstant<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::SpecialConstant(SpecialConstant::String(s)) => Ok(s),
+            other => Err(FromSexprError::unexpected(
+                Production::StringConstant,
+                &other,
+            )),
+        }
     }
 }
+
 impl From<StringConstant> for SpecialConstant {
     fn from(value: StringConstant) -> Self {
         SpecialConstant::String(value)
@@ -63,24 +131,503 @@ impl From<StringConstant> for Sexpr {
     }
 }
 
-impl From<i64> for Sexpr {
-    fn from(value: i64) -> Self {
+impl<T: Text> Display for StringConstant<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, r#""{}""#, escape_string(self.0.as_ref()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidNumeralError(String);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NumeralOutOfRangeError(Numeral);
+
+impl Display for NumeralOutOfRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "numeral {} is out of range", self.0)
+    }
+}
+
+impl std::error::Error for NumeralOutOfRangeError {}
+
+/// A numeral, i.e. a natural number of arbitrary size.
+///
+/// Numerals that fit into a `u64` are stored as such, larger ones as their decimal digits, so
+/// that only the latter need an allocation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Numeral(NumeralRepr);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum NumeralRepr {
+    Small(u64),
+    /// The digits of a numeral larger than `u64::MAX`, without leading zeroes.
+    Big(Box<str>),
+}
+
+impl Numeral {
+    /// Creates a numeral from its decimal digits. Leading zeroes are not allowed, as in SMT-LIB.
+    pub fn new(digits: String) -> Result<Numeral, InvalidNumeralError> {
+        if !predicates::is_numeral(&digits) {
+            return Err(InvalidNumeralError(digits));
+        }
+
+        let repr = match digits.parse::<u64>() {
+            Ok(num) => NumeralRepr::Small(num),
+            Err(_) => NumeralRepr::Big(digits.into_boxed_str()),
+        };
+
+        Ok(Numeral(repr))
+    }
+
+    pub fn new_str(digits: &str) -> Result<Self, InvalidNumeralError> {
+        match digits.parse::<u64>() {
+            Ok(num) if predicates::is_numeral(digits) => Ok(Numeral(NumeralRepr::Small(num))),
+            _ => Self::new(digits.to_string()),
+        }
+    }
+
+    /// The value of the numeral, if it fits into a `u64`.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.0 {
+            NumeralRepr::Small(num) => Some(num),
+            NumeralRepr::Big(_) => None,
+        }
+    }
+}
+
+impl Display for Numeral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            NumeralRepr::Small(num) => num.fmt(f),
+            NumeralRepr::Big(digits) => digits.fmt(f),
+        }
+    }
+}
+
+impl PartialOrd for Numeral {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Numeral {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (&self.0, &other.0) {
+            (NumeralRepr::Small(a), NumeralRepr::Small(b)) => a.cmp(b),
+            (NumeralRepr::Small(_), NumeralRepr::Big(_)) => std::cmp::Ordering::Less,
+            (NumeralRepr::Big(_), NumeralRepr::Small(_)) => std::cmp::Ordering::Greater,
+            // without leading zeroes, longer means larger
+            (NumeralRepr::Big(a), NumeralRepr::Big(b)) => {
+                a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Numeral {
+    type Err = InvalidNumeralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new_str(s)
+    }
+}
+
+macro_rules! numeral_from_unsigned {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Numeral {
+            fn from(value: $t) -> Self {
+                Numeral(NumeralRepr::Small(value.into()))
+            }
+        }
+    )*};
+}
+
+numeral_from_unsigned!(u8, u16, u32, u64);
+
+impl From<usize> for Numeral {
+    fn from(value: usize) -> Self {
+        Numeral(NumeralRepr::Small(value as u64))
+    }
+}
+
+impl From<u128> for Numeral {
+    fn from(value: u128) -> Self {
+        match u64::try_from(value) {
+            Ok(num) => Numeral(NumeralRepr::Small(num)),
+            Err(_) => Numeral(NumeralRepr::Big(value.to_string().into_boxed_str())),
+        }
+    }
+}
+
+macro_rules! integer_try_from_numeral {
+    ($($t:ty),*) => {$(
+        impl TryFrom<&Numeral> for $t {
+            type Error = NumeralOutOfRangeError;
+
+            fn try_from(value: &Numeral) -> Result<Self, Self::Error> {
+                let converted = match &value.0 {
+                    NumeralRepr::Small(num) => <$t>::try_from(*num).ok(),
+                    NumeralRepr::Big(digits) => digits.parse::<$t>().ok(),
+                };
+
+                converted.ok_or_else(|| NumeralOutOfRangeError(value.clone()))
+            }
+        }
+
+        impl TryFrom<Numeral> for $t {
+            type Error = NumeralOutOfRangeError;
+
+            fn try_from(value: Numeral) -> Result<Self, Self::Error> {
+                <$t>::try_from(&value)
+            }
+        }
+    )*};
+}
+
+integer_try_from_numeral!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl<T: Text> TryFrom<Sexpr<T>> for Numeral {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::SpecialConstant(SpecialConstant::Numeral(num)) => Ok(num),
+            other => Err(FromSexprError::unexpected(Production::Numeral, &other)),
+        }
+    }
+}
+
+impl From<Numeral> for SpecialConstant {
+    fn from(value: Numeral) -> Self {
+        SpecialConstant::Numeral(value)
+    }
+}
+
+impl From<Numeral> for Sexpr {
+    fn from(value: Numeral) -> Self {
         Sexpr::SpecialConstant(value.into())
     }
 }
 
-impl From<i64> for Term {
-    fn from(value: i64) -> Self {
+impl From<Numeral> for Term {
+    fn from(value: Numeral) -> Self {
         Term::SpecialConstant(value.into())
     }
 }
 
-impl Display for StringConstant {
+/// Converts a string of digits from one base into another, without leading zeroes.
+fn convert_radix(digits: &str, from: u32, to: u32) -> String {
+    // the digits of the result in base `to`, least significant first
+    let mut converted: Vec<u32> = vec![];
+    for c in digits.chars() {
+        let mut carry = c.to_digit(from).expect("digits are valid in their base");
+        for digit in converted.iter_mut() {
+            let value = *digit * from + carry;
+            *digit = value % to;
+            carry = value / to;
+        }
+        while carry > 0 {
+            converted.push(carry % to);
+            carry /= to;
+        }
+    }
+
+    if converted.is_empty() {
+        return "0".to_string();
+    }
+
+    converted
+        .iter()
+        .rev()
+        .map(|&digit| char::from_digit(digit, to).expect("digit is smaller than the base"))
+        .collect()
+}
+
+/// The digits of `value` in base `radix`, padded with zeroes to exactly `len` digits.
+fn padded_digits(value: &Numeral, radix: u32, len: usize) -> Option<String> {
+    let digits = convert_radix(&value.to_string(), 10, radix);
+    let digits = digits.trim_start_matches('0');
+    if digits.len() > len {
+        return None;
+    }
+
+    Some(format!("{}{digits}", "0".repeat(len - digits.len())))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidHexadecimalError(String);
+
+/// A hexadecimal like `#x0fA3`. The number of digits is significant, since each digit stands
+/// for 4 bits of a bit-vector constant, and the case of the digits is kept as written.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hexadecimal(String);
+
+impl Hexadecimal {
+    /// Creates a hexadecimal from its digits, i.e. without the leading `#x`.
+    pub fn new(digits: String) -> Result<Self, InvalidHexadecimalError> {
+        if !digits.is_empty() && digits.chars().all(predicates::is_hex_digit) {
+            Ok(Hexadecimal(digits))
+        } else {
+            Err(InvalidHexadecimalError(digits))
+        }
+    }
+
+    pub fn new_str(digits: &str) -> Result<Self, InvalidHexadecimalError> {
+        Self::new(digits.to_string())
+    }
+
+    /// The hexadecimal for `value` with `width` bits. Returns `None` if `width` is not a positive
+    /// multiple of 4, or `value` does not fit.
+    pub fn from_int(value: u128, width: usize) -> Option<Self> {
+        Self::from_numeral(&Numeral::from(value), width)
+    }
+
+    /// Like `from_int`, for numerals of any size.
+    pub fn from_numeral(value: &Numeral, width: usize) -> Option<Self> {
+        if width == 0 || width % 4 != 0 {
+            return None;
+        }
+
+        padded_digits(value, 16, width / 4).map(Hexadecimal)
+    }
+
+    pub fn digits(&self) -> &str {
+        &self.0
+    }
+
+    /// The number of bits, i.e. four times the number of digits.
+    pub fn width(&self) -> usize {
+        self.0.len() * 4
+    }
+
+    pub fn to_numeral(&self) -> Numeral {
+        Numeral::new(convert_radix(&self.0, 16, 10)).expect("conversion yields a valid numeral")
+    }
+
+    /// The binary with the same bits.
+    pub fn to_binary(&self) -> Binary {
+        let bits = self
+            .0
+            .chars()
+            .map(|c| {
+                let value = c.to_digit(16).expect("digits are valid");
+                format!("{value:04b}")
+            })
+            .collect();
+
+        Binary(bits)
+    }
+}
+
+impl Display for Hexadecimal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, r#""{}""#, escape_string(&self.0))
+        write!(f, "#x{}", self.0)
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidBinaryError(String);
+
+/// A binary like `#b0110`. The number of digits is significant, since each digit stands for one
+/// bit of a bit-vector constant.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Binary(String);
+
+impl Binary {
+    /// Creates a binary from its digits, i.e. without the leading `#b`.
+    pub fn new(digits: String) -> Result<Self, InvalidBinaryError> {
+        if !digits.is_empty() && digits.chars().all(predicates::is_binary_digit) {
+            Ok(Binary(digits))
+        } else {
+            Err(InvalidBinaryError(digits))
+        }
+    }
+
+    pub fn new_str(digits: &str) -> Result<Self, InvalidBinaryError> {
+        Self::new(digits.to_string())
+    }
+
+    /// The binary for `value` with `width` bits. Returns `None` if `width` is zero, or `value`
+    /// does not fit.
+    pub fn from_int(value: u128, width: usize) -> Option<Self> {
+        Self::from_numeral(&Numeral::from(value), width)
+    }
+
+    /// Like `from_int`, for numerals of any size.
+    pub fn from_numeral(value: &Numeral, width: usize) -> Option<Self> {
+        if width == 0 {
+            return None;
+        }
+
+        padded_digits(value, 2, width).map(Binary)
+    }
+
+    pub fn digits(&self) -> &str {
+        &self.0
+    }
+
+    /// The number of bits, i.e. digits.
+    pub fn width(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn to_numeral(&self) -> Numeral {
+        Numeral::new(convert_radix(&self.0, 2, 10)).expect("conversion yields a valid numeral")
+    }
+
+    /// The hexadecimal with the same bits, if the width is a multiple of 4.
+    pub fn to_hexadecimal(&self) -> Option<Hexadecimal> {
+        if self.0.len() % 4 != 0 {
+            return None;
+        }
+
+        let digits = self
+            .0
+            .as_bytes()
+            .chunks(4)
+            .map(|nibble| {
+                let value = nibble
+                    .iter()
+                    .fold(0, |acc, bit| acc * 2 + u32::from(bit - b'0'));
+                char::from_digit(value, 16).expect("a nibble is a hex digit")
+            })
+            .collect();
+
+        Some(Hexadecimal(digits))
+    }
+}
+
+impl Display for Binary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#b{}", self.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidDecimalError(String);
+
+/// A decimal like `123.0450`. It is kept exactly as written, including trailing zeroes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Decimal {
+    integer: Numeral,
+    fraction: String,
+}
+
+impl Decimal {
+    /// Parses a decimal in SMT-LIB syntax, i.e. a numeral, a `.` and at least one digit.
+    pub fn new(decimal: String) -> Result<Self, InvalidDecimalError> {
+        Self::new_str(&decimal).map_err(|_| InvalidDecimalError(decimal))
+    }
+
+    pub fn new_str(decimal: &str) -> Result<Self, InvalidDecimalError> {
+        let invalid = || InvalidDecimalError(decimal.to_string());
+        let (integer, fraction) = decimal.split_once('.').ok_or_else(invalid)?;
+        let integer = Numeral::new_str(integer).map_err(|_| invalid())?;
+        Self::from_parts(integer, fraction.to_string()).map_err(|_| invalid())
+    }
+
+    /// Creates a decimal from the part before the `.` and the digits after it.
+    pub fn from_parts(integer: Numeral, fraction: String) -> Result<Self, InvalidDecimalError> {
+        if !fraction.is_empty() && fraction.chars().all(predicates::is_digit) {
+            Ok(Decimal { integer, fraction })
+        } else {
+            Err(InvalidDecimalError(format!("{integer}.{fraction}")))
+        }
+    }
+
+    /// The decimal with the value `mantissa / 10^scale`, written with `scale` digits after the
+    /// `.`, or one if `scale` is 0. For example, `from_scaled(1230450, 4)` is `123.0450`.
+    pub fn from_scaled(mantissa: u128, scale: usize) -> Self {
+        let digits = format!("{mantissa:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        let fraction = if fraction.is_empty() { "0" } else { fraction };
+
+        Decimal {
+            integer: Numeral::new_str(integer).expect("digits without leading zeroes"),
+            fraction: fraction.to_string(),
+        }
+    }
+
+    /// The part before the `.`.
+    pub fn integer_part(&self) -> &Numeral {
+        &self.integer
+    }
+
+    /// The digits after the `.`.
+    pub fn fraction_digits(&self) -> &str {
+        &self.fraction
+    }
+
+    /// The numeral with the same value, if the decimal has no fractional part.
+    pub fn to_numeral(&self) -> Option<Numeral> {
+        if self.fraction.chars().all(|c| c == '0') {
+            Some(self.integer.clone())
+        } else {
+            None
+        }
+    }
+
+    /// The closest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string()
+            .parse()
+            .expect("decimals are valid floating point literals")
+    }
+}
+
+/// Writes the numeral as a decimal with a fractional part of `0`.
+impl From<Numeral> for Decimal {
+    fn from(value: Numeral) -> Self {
+        Decimal {
+            integer: value,
+            fraction: "0".to_string(),
+        }
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.integer, self.fraction)
+    }
+}
+
+macro_rules! special_constant_conversions {
+    ($($t:ident),*) => {$(
+        impl<T: Text> TryFrom<Sexpr<T>> for $t {
+            type Error = FromSexprError;
+
+            fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+                match sexpr {
+                    Sexpr::SpecialConstant(SpecialConstant::$t(value)) => Ok(value),
+                    other => Err(FromSexprError::unexpected(Production::$t, &other)),
+                }
+            }
+        }
+
+        impl From<$t> for SpecialConstant {
+            fn from(value: $t) -> Self {
+                SpecialConstant::$t(value)
+            }
+        }
+
+        impl From<$t> for Sexpr {
+            fn from(value: $t) -> Self {
+                Sexpr::SpecialConstant(value.into())
+            }
+        }
+
+        impl From<$t> for Term {
+            fn from(value: $t) -> Self {
+                Term::SpecialConstant(value.into())
+            }
+        }
+    )*};
+}
+
+special_constant_conversions!(Hexadecimal, Binary, Decimal);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Reserved {
     Binary,
     Decimal,
@@ -114,48 +661,44 @@ impl Display for Reserved {
             Reserved::Forall => write!(f, "forall"),
             Reserved::Match => write!(f, "match"),
             Reserved::Par => write!(f, "par"),
-            Reserved::Command(cmd) => match cmd {
-                CommandType::Assert => write!(f, "assert"),
-                CommandType::CheckSat => write!(f, "check-sat"),
-                CommandType::CheckSatAssuming => write!(f, "check-sat-assuming"),
-                CommandType::DeclareConst => write!(f, "declare-const"),
-                CommandType::DeclareDatatype => write!(f, "declare-datatype"),
-                CommandType::DeclareDatatypes => write!(f, "declare-datatypes"),
-                CommandType::DeclareFun => write!(f, "declare-fun"),
-                CommandType::DeclareSort => write!(f, "declare-sort"),
-                CommandType::DefineFun => write!(f, "define-fun"),
-                CommandType::DefineFunRec => write!(f, "define-fun-rec"),
-                CommandType::DefineFunsRec => write!(f, "define-funs-rec"),
-                CommandType::DefineSort => write!(f, "define_sort"),
-                CommandType::Echo => write!(f, "echo"),
-                CommandType::Exit => write!(f, "exit"),
-                CommandType::GetAssertions => write!(f, "get-assertions"),
-                CommandType::GetAssignment => write!(f, "get-assignment"),
-                CommandType::GetModel => write!(f, "get-model"),
-                CommandType::GetOption => write!(f, "get-option"),
-                CommandType::GetProof => write!(f, "get-proof"),
-                CommandType::GetUnsatAssumptions => write!(f, "get-unsat-assumptions"),
-                CommandType::GetUnsatCore => write!(f, "get-unsat-core"),
-                CommandType::GetValue => write!(f, "get-value"),
-                CommandType::Pop => write!(f, "pop"),
-                CommandType::Push => write!(f, "push"),
-                CommandType::Reset => write!(f, "reset"),
-                CommandType::ResetAssertions => write!(f, "reset-assertions"),
-                CommandType::SetLogic => write!(f, "set-logic"),
-            },
+            Reserved::Command(cmd) => write!(f, "{}", cmd.name()),
         }
     }
 }
 
+impl Reserved {
+    /// Looks up the reserved word (including command names) with the given spelling.
+    pub fn from_word(word: &str) -> Option<Reserved> {
+        let reserved = match word {
+            "BINARY" => Reserved::Binary,
+            "DECIMAL" => Reserved::Decimal,
+            "HEXADECIMAL" => Reserved::Hexadecimal,
+            "NUMERAL" => Reserved::Numeral,
+            "STRING" => Reserved::String,
+            "_" => Reserved::Underscore,
+            "!" => Reserved::Bang,
+            "as" => Reserved::As,
+            "let" => Reserved::Let,
+            "exists" => Reserved::Exists,
+            "forall" => Reserved::Forall,
+            "match" => Reserved::Match,
+            "par" => Reserved::Par,
+            _ => Reserved::Command(CommandType::from_name(word)?),
+        };
+
+        Some(reserved)
+    }
+}
+
 impl From<Reserved> for Sexpr {
     fn from(value: Reserved) -> Self {
         Sexpr::Reserved(value)
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Symbol {
-    symbol: String,
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol<T = String> {
+    symbol: T,
     is_quoted: bool,
 }
 
@@ -165,14 +708,14 @@ impl From<Symbol> for Sexpr {
     }
 }
 
-impl Display for Symbol {
+impl<T: Text> Display for Symbol<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.is_quoted() {
             write!(f, "|")?;
-            write!(f, "{}", self.symbol)?;
+            write!(f, "{}", self.symbol())?;
             write!(f, "|")
         } else {
-            write!(f, "{}", self.symbol)
+            write!(f, "{}", self.symbol())
         }
     }
 }
@@ -181,8 +724,11 @@ impl Display for Symbol {
 pub struct InvalidSymbolError(String);
 
 impl Symbol {
+    /// Creates a symbol, which is quoted when printed unless it is a simple symbol. Symbols can
+    /// not contain `|`, `\\` or unprintable characters other than whitespace.
     pub fn new(symbol: String) -> Result<Symbol, InvalidSymbolError> {
-        if symbol.contains('|') || symbol.contains('\\') {
+        let printable = symbol.chars().all(|c| is_printable(c) || is_white_space(c));
+        if !printable || symbol.contains('|') || symbol.contains('\\') {
             Err(InvalidSymbolError(symbol))
         } else {
             let is_quoted = !predicates::is_simple_symbol(&symbol);
@@ -191,20 +737,89 @@ impl Symbol {
         }
     }
 
+    pub fn new_str(symbol: &str) -> Result<Symbol, InvalidSymbolError> {
+        Self::new(symbol.to_string())
+    }
+}
+
+impl<T: Text> Symbol<T> {
+    /// A symbol read by the lexer, which only accepts valid ones.
+    pub(crate) fn from_text(symbol: T) -> Self {
+        let is_quoted = !predicates::is_simple_symbol(symbol.as_ref());
+        Symbol { symbol, is_quoted }
+    }
+
     pub fn symbol(&self) -> &str {
-        &self.symbol
+        self.symbol.as_ref()
     }
     pub fn is_quoted(&self) -> bool {
         self.is_quoted
     }
+
+    pub fn into_owned(self) -> Symbol {
+        Symbol {
+            symbol: self.symbol.into_string(),
+            is_quoted: self.is_quoted,
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Symbol<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Symbol(sym) => Ok(sym),
+            other => Err(FromSexprError::unexpected(Production::Symbol, &other)),
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct Keyword(String);
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidKeywordError(String);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Keyword<T = String>(T);
 
 impl Keyword {
+    /// Creates a keyword from its name, i.e. without the leading `:`.
+    pub fn new(keyword: String) -> Result<Keyword, InvalidKeywordError> {
+        if !keyword.is_empty() && keyword.chars().all(predicates::is_simple_symbol_char) {
+            Ok(Keyword(keyword))
+        } else {
+            Err(InvalidKeywordError(keyword))
+        }
+    }
+
+    pub fn new_str(keyword: &str) -> Result<Keyword, InvalidKeywordError> {
+        Self::new(keyword.to_string())
+    }
+}
+
+impl<T: Text> Keyword<T> {
+    /// A keyword read by the lexer, which only accepts valid ones.
+    pub(crate) fn from_text(keyword: T) -> Self {
+        Keyword(keyword)
+    }
+
+    /// The name of the keyword, without the leading `:`.
     pub fn keyword(&self) -> &str {
-        &self.0
+        self.0.as_ref()
+    }
+
+    pub fn into_owned(self) -> Keyword {
+        Keyword(self.0.into_string())
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Keyword<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Keyword(kw) => Ok(kw),
+            other => Err(FromSexprError::unexpected(Production::Keyword, &other)),
+        }
     }
 }
 
@@ -214,13 +829,15 @@ impl From<Keyword> for Sexpr {
     }
 }
 
-impl std::fmt::Display for Keyword {
+impl<T: Text> std::fmt::Display for Keyword<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, ":{}", self.0)
+        write!(f, ":{}", self.keyword())
     }
 }
 
 pub mod predicates {
+    use super::Reserved;
+
     const RESERVED: [&str; 13] = [
         "!",
         "BINARY",
@@ -265,9 +882,31 @@ pub mod predicates {
         }
     }
 
+    pub fn is_hex_digit(c: char) -> bool {
+        c.is_ascii_hexdigit()
+    }
+
+    pub fn is_binary_digit(c: char) -> bool {
+        matches!(c, '0' | '1')
+    }
+
+    /// Returns true for the characters that may occur in a simple symbol, i.e. letters, digits
+    /// and `~ ! @ $ % ^ & * _ - + = < > . ? /`.
+    pub fn is_simple_symbol_char(c: char) -> bool {
+        let legal_nonletters = "~!@$%^&*_-+=<>.?/";
+
+        is_letter(c) || is_digit(c) || legal_nonletters.contains(c)
+    }
+
+    pub fn is_reserved_word(s: &str) -> bool {
+        RESERVED.contains(&s)
+    }
+
     pub fn is_numeral(s: &str) -> bool {
-        if s == "0" {
-            return true;
+        match s {
+            "" => return false,
+            "0" => return true,
+            _ => {}
         }
 
         for (i, c) in s.chars().enumerate() {
@@ -293,21 +932,100 @@ pub mod predicates {
          * that does not start with a digit and is not a reserved word.
          * */
 
-        let legal_nonletters = "~!@$%^&*_-+=<>.?/";
-
         for (i, c) in s.chars().enumerate() {
             match (i, c) {
                 // no leading digits allowed
                 (0, c) if is_digit(c) => return false,
 
                 // a non-empty sequence of letters, digits, and the characters (...)
-                (_, c) if is_letter(c) || is_digit(c) || legal_nonletters.contains(c) => {}
+                (_, c) if is_simple_symbol_char(c) => {}
 
                 // anything else is forbidden
                 _ => return false,
             }
         }
 
-        !s.is_empty() && !RESERVED.contains(&s)
+        // command names are reserved words too
+        !s.is_empty() && Reserved::from_word(s).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_command;
+
+    #[test]
+    fn big_numerals() {
+        let big = "340282366920938463463374607431768211456";
+        let input = format!("(assert (= ((_ extract {big} 0) x) {big}))");
+        let command = parse_command(&input).unwrap();
+        assert_eq!(Sexpr::from(command).to_string(), input);
+
+        let num: Numeral = big.parse().unwrap();
+        assert_eq!(num.to_string(), big);
+        assert!(u128::try_from(&num).is_err());
+        assert!(num > Numeral::from(u64::MAX));
+        assert_eq!(Numeral::from(u128::MAX).to_string(), u128::MAX.to_string());
+        assert_eq!(u128::try_from(Numeral::from(u128::MAX)), Ok(u128::MAX));
+        assert_eq!(i8::try_from(Numeral::from(127u8)), Ok(127));
+        assert!(i8::try_from(Numeral::from(128u8)).is_err());
+        assert!(Numeral::new_str("0123").is_err());
+        assert!(Numeral::new_str("").is_err());
+        assert!(Numeral::new(String::new()).is_err());
+        assert!("".parse::<Numeral>().is_err());
+
+        assert_eq!(Sexpr::from(-5).to_string(), "(- 5)");
+        assert_eq!(Sexpr::from(i64::MIN).to_string(), "(- 9223372036854775808)");
+    }
+
+    #[test]
+    fn bit_vector_and_decimal_literals() {
+        let input = "(assert (= (bvadd #x0fA3 #b0000111110100011) (f 123.0450 0.0)))";
+        let command = parse_command(input).unwrap();
+        assert_eq!(Sexpr::from(command).to_string(), input);
+
+        let hex = Hexadecimal::new_str("0fA3").unwrap();
+        assert_eq!(hex.width(), 16);
+        assert_eq!(hex.to_numeral(), Numeral::from(4003u32));
+        assert_eq!(hex.to_binary().to_string(), "#b0000111110100011");
+        assert_eq!(
+            hex.to_binary().to_hexadecimal().unwrap().to_string(),
+            "#x0fa3"
+        );
+        assert_eq!(
+            Hexadecimal::from_int(4003, 16),
+            Some(hex.to_binary().to_hexadecimal().unwrap())
+        );
+        assert_eq!(Hexadecimal::from_int(4003, 8), None);
+        assert_eq!(Hexadecimal::from_int(1, 6), None);
+        assert_eq!(Binary::from_int(5, 4).unwrap().to_string(), "#b0101");
+        assert_eq!(Binary::new_str("101").unwrap().to_hexadecimal(), None);
+        assert!(Hexadecimal::new_str("xyz").is_err());
+
+        let big = Hexadecimal::new_str(&"f".repeat(40)).unwrap();
+        let num = big.to_numeral();
+        assert_eq!(
+            num.to_string(),
+            "1461501637330902918203684832716283019655932542975"
+        );
+        assert_eq!(Hexadecimal::from_numeral(&num, 160), Some(big));
+
+        let dec = Decimal::new_str("123.0450").unwrap();
+        assert_eq!(dec, Decimal::from_scaled(1230450, 4));
+        assert_eq!(dec.to_string(), "123.0450");
+        assert_eq!(dec.to_f64(), 123.045);
+        assert_eq!(dec.to_numeral(), None);
+        assert_eq!(Decimal::from_scaled(5, 3).to_string(), "0.005");
+        assert_eq!(Decimal::from_scaled(7, 0).to_string(), "7.0");
+        assert_eq!(
+            Decimal::from(Numeral::from(7u8)).to_numeral(),
+            Some(Numeral::from(7u8))
+        );
+        assert!(Decimal::new_str("01.5").is_err());
+        assert!(Decimal::new_str("1.").is_err());
+        assert!(Decimal::new_str(".5").is_err());
+        assert!(Decimal::new_str("5.").is_err());
+        assert!(Decimal::new(".5".to_string()).is_err());
     }
 }
@@ -1,14 +1,39 @@
 pub mod lexicon;
 
+pub mod lexer;
+
 pub mod sexprs;
 
 pub mod identifiers;
 
-// skip for now
-mod attributes {}
+pub mod attributes;
 
 pub mod sorts;
 
 pub mod terms;
 
 pub mod scripts;
+
+pub mod responses;
+
+pub mod parser;
+
+pub mod pretty;
+
+pub mod reader;
+
+pub mod diagnostics;
+
+pub mod cst;
+
+pub mod borrowed;
+
+pub mod version;
+
+pub mod strict;
+
+pub mod sharing;
+
+pub mod canonical;
+
+pub mod writer;
@@ -0,0 +1,543 @@
+//! Reading SMT-LIB text into the types of this crate.
+//!
+//! [`parse_sexpr`] and [`parse_sexprs`] read s-expressions. Atoms are classified the same way the
+//! printer emits them: reserved words (including command names) become [`Sexpr::Reserved`],
+//! everything else a [`Sexpr::Symbol`], [`Sexpr::Keyword`] or [`Sexpr::SpecialConstant`].
+//!
+//! [`parse_script`], [`parse_command`], [`parse_term`] and [`parse_sort`] go on to read the
+//! s-expressions as the respective grammar productions. They accept everything the printer emits,
+//! so printing and parsing again yields the same value. [`parse_response`] reads what a solver
+//! sends back.
+//!
+//! The grammar is the one of SMT-LIB 2.7, which 2.6 is a subset of, except that `lambda` followed
+//! by a list of terms is read as an application of a function named `lambda`, as in 2.6.
+//! [`parse_script_with_version`] and [`parse_command_with_version`] only accept what the given
+//! [`Version`] has.
+
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use super::lexer::{LexError, LexErrorKind, Lexer, Position, Span, Token, TokenKind};
+use super::lexicon::{
+    Binary, Decimal, Hexadecimal, Keyword, Numeral, Reserved, StringConstant, Symbol, Text,
+};
+use super::responses::GeneralResponse;
+use super::scripts::{Command, Script};
+use super::sexprs::{FromSexprError, FromSexprErrorKind, Production, Sexpr, SpecialConstant};
+use super::sorts::Sort;
+use super::terms::Term;
+use super::version::{check_command, Version};
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Lex(LexErrorKind),
+    /// The input ended where more was expected.
+    UnexpectedEof,
+    /// A `)` without a matching `(`.
+    UnexpectedCloseParen,
+    /// A `(` without a matching `)`. The span points at the opening parenthesis.
+    UnclosedParen,
+    /// More input after the s-expression that was requested.
+    TrailingInput,
+    /// The s-expressions are well-formed, but do not match the grammar.
+    Syntax(FromSexprError),
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Lex(kind) => kind.fmt(f),
+            ParseErrorKind::UnexpectedEof => write!(f, "unexpected end of input"),
+            ParseErrorKind::UnexpectedCloseParen => write!(f, "unexpected `)`"),
+            ParseErrorKind::UnclosedParen => write!(f, "unclosed `(`"),
+            ParseErrorKind::TrailingInput => write!(f, "unexpected input after s-expression"),
+            ParseErrorKind::Syntax(err) => err.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, span: Span) -> Self {
+        ParseError { kind, span }
+    }
+}
+
+impl From<LexError> for ParseError {
+    fn from(err: LexError) -> Self {
+        ParseError {
+            kind: ParseErrorKind::Lex(err.kind),
+            span: err.span,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span.start, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Reads s-expressions from a token stream, skipping comments.
+pub(crate) struct SexprParser<'a, I> {
+    tokens: I,
+    peeked: Option<Token<'a>>,
+    end: Span,
+}
+
+impl<'a> SexprParser<'a, Lexer<'a>> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self::starting_at(input, Position::start())
+    }
+
+    pub(crate) fn starting_at(input: &'a str, position: Position) -> Self {
+        let lexer = Lexer::starting_at(input, position);
+        let end = Span::new(lexer.position(), lexer.position());
+        SexprParser {
+            tokens: lexer,
+            peeked: None,
+            end,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<Token<'a>, LexError>>> SexprParser<'a, I> {
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        if let Some(tok) = self.peeked.take() {
+            return Ok(Some(tok));
+        }
+
+        for tok in &mut self.tokens {
+            let tok = tok?;
+            self.end = Span::new(tok.span.end, tok.span.end);
+            if tok.kind != TokenKind::Comment {
+                return Ok(Some(tok));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the next s-expression, or returns `None` at the end of the input.
+    pub(crate) fn next_sexpr(&mut self) -> Result<Option<Sexpr>, ParseError> {
+        Ok(self.read(false)?.map(|(sexpr, _)| sexpr))
+    }
+
+    /// Reads the next tree, of any type that can be built from tokens.
+    pub(crate) fn read<S: Tree<'a>>(
+        &mut self,
+        with_spans: bool,
+    ) -> Result<Option<(S, SpanTree)>, ParseError> {
+        let tok = match self.next_token()? {
+            Some(tok) => tok,
+            None => return Ok(None),
+        };
+
+        // the open sequences, along with the spans of their opening parentheses
+        let mut stack: Vec<(Vec<S>, SpanTree)> = vec![];
+        let mut tok = tok;
+
+        loop {
+            let node = match tok.kind {
+                TokenKind::LeftParen => {
+                    stack.push((vec![], SpanTree::leaf(tok.span)));
+                    None
+                }
+                TokenKind::RightParen => match stack.pop() {
+                    Some((seq, mut spans)) => {
+                        spans.span = spans.span.to(tok.span);
+                        Some((S::list(seq), spans))
+                    }
+                    None => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnexpectedCloseParen,
+                            tok.span,
+                        ))
+                    }
+                },
+                _ => {
+                    let span = tok.span;
+                    Some((S::atom(tok)?, SpanTree::leaf(span)))
+                }
+            };
+
+            if let Some((sexpr, spans)) = node {
+                match stack.last_mut() {
+                    Some((seq, parent)) => {
+                        seq.push(sexpr);
+                        if with_spans {
+                            parent.children.push(spans);
+                        }
+                    }
+                    None => return Ok(Some((sexpr, spans))),
+                }
+            }
+
+            tok = match self.next_token()? {
+                Some(tok) => tok,
+                None => {
+                    let (_, open) = stack.pop().expect("stack is not empty");
+                    return Err(ParseError::new(ParseErrorKind::UnclosedParen, open.span));
+                }
+            };
+        }
+    }
+
+    /// Like `next_sexpr`, but treats the end of the input as an error.
+    pub(crate) fn expect_sexpr(&mut self) -> Result<Sexpr, ParseError> {
+        self.next_sexpr()?
+            .ok_or_else(|| ParseError::new(ParseErrorKind::UnexpectedEof, self.end))
+    }
+
+    /// Reads the next s-expression and converts it with `convert`.
+    pub(crate) fn next_converted<T>(
+        &mut self,
+        convert: impl FnOnce(Sexpr) -> Result<T, FromSexprError>,
+    ) -> Result<Option<T>, ParseError> {
+        self.next_tree_converted(convert)
+    }
+
+    /// Like `next_converted`, for any type of tree.
+    pub(crate) fn next_tree_converted<S: Tree<'a>, T>(
+        &mut self,
+        convert: impl FnOnce(S) -> Result<T, FromSexprError>,
+    ) -> Result<Option<T>, ParseError> {
+        match self.read(true)? {
+            None => Ok(None),
+            Some((sexpr, spans)) => convert(sexpr).map(Some).map_err(|err| {
+                let span = spans.locate(err.path());
+                ParseError::new(ParseErrorKind::Syntax(err), span)
+            }),
+        }
+    }
+
+    /// Like `next_converted`, but treats the end of the input as an error.
+    pub(crate) fn expect_spanned<T>(
+        &mut self,
+        convert: impl FnOnce(Sexpr) -> Result<T, FromSexprError>,
+    ) -> Result<T, ParseError> {
+        self.expect_tree_converted(convert)
+    }
+
+    /// Like `expect_spanned`, for any type of tree.
+    pub(crate) fn expect_tree_converted<S: Tree<'a>, T>(
+        &mut self,
+        convert: impl FnOnce(S) -> Result<T, FromSexprError>,
+    ) -> Result<T, ParseError> {
+        self.next_tree_converted(convert)?
+            .ok_or_else(|| ParseError::new(ParseErrorKind::UnexpectedEof, self.end))
+    }
+
+    /// Fails if there are tokens left.
+    pub(crate) fn expect_eof(&mut self) -> Result<(), ParseError> {
+        match self.next_token()? {
+            None => Ok(()),
+            Some(tok) => Err(ParseError::new(ParseErrorKind::TrailingInput, tok.span)),
+        }
+    }
+}
+
+/// The trees that [`SexprParser`] can build.
+pub(crate) trait Tree<'a>: Sized {
+    fn atom(tok: Token<'a>) -> Result<Self, ParseError>;
+    fn list(seq: Vec<Self>) -> Self;
+}
+
+/// Owned trees copy the text of the tokens, borrowed ones keep pointing into the input.
+impl<'a, T: Text + From<Cow<'a, str>>> Tree<'a> for Sexpr<T> {
+    fn atom(tok: Token<'a>) -> Result<Self, ParseError> {
+        atom(tok)
+    }
+
+    fn list(seq: Vec<Self>) -> Self {
+        Sexpr::Sequence(seq)
+    }
+}
+
+/// The spans of an s-expression and, for lists, of their elements.
+pub(crate) struct SpanTree {
+    pub(crate) span: Span,
+    pub(crate) children: Vec<SpanTree>,
+}
+
+impl SpanTree {
+    pub(crate) fn leaf(span: Span) -> Self {
+        SpanTree {
+            span,
+            children: vec![],
+        }
+    }
+
+    /// The span of the node at the end of `path`, or of the deepest node on the way there.
+    pub(crate) fn locate(&self, path: &[usize]) -> Span {
+        match path.split_first() {
+            Some((&i, rest)) if i < self.children.len() => self.children[i].locate(rest),
+            _ => self.span,
+        }
+    }
+}
+
+/// Turns a single token that is not a parenthesis or comment into an s-expression.
+pub(crate) fn atom<'a, T: Text + From<Cow<'a, str>>>(
+    tok: Token<'a>,
+) -> Result<Sexpr<T>, ParseError> {
+    let sexpr = match tok.kind {
+        TokenKind::Numeral => {
+            let num = Numeral::new_str(&tok.text).expect("lexer only accepts valid numerals");
+            Sexpr::SpecialConstant(SpecialConstant::Numeral(num))
+        }
+        TokenKind::String => {
+            let string = StringConstant::from_text(tok.into_content().into());
+            Sexpr::SpecialConstant(SpecialConstant::String(string))
+        }
+        TokenKind::Symbol | TokenKind::Reserved => match Reserved::from_word(&tok.text) {
+            Some(reserved) => Sexpr::Reserved(reserved),
+            None => Sexpr::Symbol(Symbol::from_text(tok.into_content().into())),
+        },
+        TokenKind::QuotedSymbol => Sexpr::Symbol(Symbol::from_text(tok.into_content().into())),
+        TokenKind::Keyword => Sexpr::Keyword(Keyword::from_text(tok.into_content().into())),
+        TokenKind::Decimal => Sexpr::SpecialConstant(SpecialConstant::Decimal(
+            Decimal::new_str(&tok.text).expect("lexer only accepts valid decimals"),
+        )),
+        TokenKind::Hexadecimal => Sexpr::SpecialConstant(SpecialConstant::Hexadecimal(
+            Hexadecimal::new(tok.content().into_owned())
+                .expect("lexer only accepts valid hexadecimals"),
+        )),
+        TokenKind::Binary => Sexpr::SpecialConstant(SpecialConstant::Binary(
+            Binary::new(tok.content().into_owned()).expect("lexer only accepts valid binaries"),
+        )),
+        TokenKind::LeftParen | TokenKind::RightParen | TokenKind::Comment => {
+            unreachable!("not an atom: {:?}", tok.kind)
+        }
+    };
+
+    Ok(sexpr)
+}
+
+/// Parses exactly one s-expression, surrounded by nothing but whitespace and comments.
+pub fn parse_sexpr(input: &str) -> Result<Sexpr, ParseError> {
+    let mut parser = SexprParser::new(input);
+    let sexpr = parser.expect_sexpr()?;
+    parser.expect_eof()?;
+    Ok(sexpr)
+}
+
+/// Parses all s-expressions in the input.
+pub fn parse_sexprs(input: &str) -> Result<Vec<Sexpr>, ParseError> {
+    let mut parser = SexprParser::new(input);
+    let mut sexprs = vec![];
+    while let Some(sexpr) = parser.next_sexpr()? {
+        sexprs.push(sexpr);
+    }
+    Ok(sexprs)
+}
+
+/// Parses one s-expression and converts it, locating conversion errors in the input.
+pub(crate) fn parse_single<'a, T: Text + From<Cow<'a, str>>, U>(
+    input: &'a str,
+    convert: impl FnOnce(Sexpr<T>) -> Result<U, FromSexprError>,
+) -> Result<U, ParseError> {
+    let mut parser = SexprParser::new(input);
+    let parsed = parser.expect_tree_converted(convert)?;
+    parser.expect_eof()?;
+    Ok(parsed)
+}
+
+/// Parses a single command.
+pub fn parse_command(input: &str) -> Result<Command, ParseError> {
+    parse_single(input, Command::try_from)
+}
+
+/// Parses a single term.
+pub fn parse_term(input: &str) -> Result<Term, ParseError> {
+    parse_single(input, Term::try_from)
+}
+
+/// Parses a single sort.
+pub fn parse_sort(input: &str) -> Result<Sort, ParseError> {
+    parse_single(input, Sort::try_from)
+}
+
+/// Parses a script, i.e. a sequence of commands.
+pub fn parse_script(input: &str) -> Result<Script, ParseError> {
+    let mut parser = SexprParser::new(input);
+    let mut commands = vec![];
+    while let Some(command) = parser.next_converted(Command::try_from)? {
+        commands.push(command);
+    }
+    Ok(Script(commands))
+}
+
+/// Converts a command, failing on constructs that `version` does not have.
+fn command_with_version(sexpr: Sexpr, version: Version) -> Result<Command, FromSexprError> {
+    let command = Command::try_from(sexpr)?;
+    check_command(&command, version).map_err(|err| {
+        FromSexprError::new(Production::Command, FromSexprErrorKind::Version(err))
+    })?;
+    Ok(command)
+}
+
+/// Parses a single command of the given version of SMT-LIB.
+pub fn parse_command_with_version(input: &str, version: Version) -> Result<Command, ParseError> {
+    parse_single(input, |sexpr| command_with_version(sexpr, version))
+}
+
+/// Parses a script of the given version of SMT-LIB, e.g. one that may not contain `lambda`s for
+/// [`Version::V2_6`].
+pub fn parse_script_with_version(input: &str, version: Version) -> Result<Script, ParseError> {
+    let mut parser = SexprParser::new(input);
+    let mut commands = vec![];
+    while let Some(command) = parser.next_converted(|sexpr| command_with_version(sexpr, version))? {
+        commands.push(command);
+    }
+    Ok(Script(commands))
+}
+
+/// Parses a solver response. `T` is the specific response expected for the command that was
+/// sent, e.g. [`CheckSatResponse`](crate::responses::CheckSatResponse) for `check-sat`.
+pub fn parse_response<T>(input: &str) -> Result<GeneralResponse<T>, ParseError>
+where
+    T: TryFrom<Sexpr, Error = FromSexprError>,
+{
+    parse_single(input, GeneralResponse::try_from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_parse_roundtrip() {
+        let input = r#"(declare-fun |x y| () Int) (assert (! (= |x y| 5 "a ""b""") :named a0)) (check-sat)"#;
+        let printed: Vec<_> = parse_sexprs(input)
+            .unwrap()
+            .into_iter()
+            .map(|sexpr| sexpr.to_string())
+            .collect();
+
+        assert_eq!(printed.join(" "), input);
+    }
+
+    #[test]
+    fn classify_atoms() {
+        let seq = match parse_sexpr("(define-sort _ as foo :bar)").unwrap() {
+            Sexpr::Sequence(seq) => seq,
+            _ => panic!("expected a sequence"),
+        };
+
+        assert!(matches!(seq[0], Sexpr::Reserved(Reserved::Command(_))));
+        assert!(matches!(seq[1], Sexpr::Reserved(Reserved::Underscore)));
+        assert!(matches!(seq[2], Sexpr::Reserved(Reserved::As)));
+        assert!(matches!(seq[3], Sexpr::Symbol(_)));
+        assert!(matches!(seq[4], Sexpr::Keyword(_)));
+    }
+
+    #[test]
+    fn script_roundtrip() {
+        let commands = [
+            "(set-option :produce-models true)",
+            "(set-option :random-seed 42)",
+            r#"(set-option :regular-output-channel "out.txt")"#,
+            "(set-option :smt.mbqi false)",
+            "(set-info :smt-lib-version 2.6)",
+            "(set-info :source |Generated by\nhand|)",
+            r#"(set-info :license "https://creativecommons.org/licenses/by/4.0/")"#,
+            r#"(set-info :category "crafted")"#,
+            "(set-info :status unsat)",
+            "(set-info :notes (a b))",
+            "(set-logic QF_UFLIA)",
+            "(declare-sort U 0)",
+            "(define-sort Arr (X) (Array Int X))",
+            "(declare-const |x y| (_ BitVec 32))",
+            "(declare-fun f (Int (Arr Bool)) Int)",
+            "(declare-datatype Pair ((mk-pair (first Int) (second Bool))))",
+            "(declare-datatypes ((Nat 0) (Tree 0)) (((zero) (succ (pred Nat))) ((leaf) (node (l Tree) (r Tree)))))",
+            "(declare-datatype Option (par (T) ((none) (some (value T)))))",
+            "(declare-datatypes ((List 1) (Pair 2)) ((par (T) ((nil) (cons (head T) (tail (List T))))) (par (A B) ((pair (fst A) (snd B))))))",
+            "(declare-sort-parameter X)",
+            "(define-const k (-> Int Bool) (lambda ((n Int)) (> n 0)))",
+            "(assert ((lambda ((f (-> Int Int)) (n Int)) (f n)) (g 1) 2))",
+            "(define-fun g ((a Int) (b Int)) Int (+ a b))",
+            "(define-fun-rec h ((n Int)) Int (ite (= n 0) 0 (h (- n 1))))",
+            "(define-funs-rec ((ev ((n Int)) Bool) (od ((n Int)) Bool)) ((ite (= n 0) true (od (- n 1))) (ite (= n 0) false (ev (- n 1)))))",
+            "(assert (let ((a 1) (b ((as f Int) 2 x))) (forall ((y Int)) (exists ((z U)) (= (+ a y) b)))))",
+            "(assert ((_ extract 7 0) |x y|))",
+            "(push 1)",
+            "(check-sat-assuming (p (not q)))",
+            "(check-sat)",
+            "(get-value (x (f 1 2)))",
+            "(get-option :produce-models)",
+            "(get-info :reason-unknown)",
+            "(get-info :custom)",
+            r#"(echo "say ""hi""")"#,
+            "(get-model)",
+            "(get-assertions)",
+            "(get-assignment)",
+            "(get-proof)",
+            "(get-unsat-core)",
+            "(get-unsat-assumptions)",
+            "(pop 1)",
+            "(reset-assertions)",
+            "(reset)",
+            "(exit)",
+        ];
+
+        let script = parse_script(&commands.join("\n")).unwrap();
+        assert_eq!(script.0.len(), commands.len());
+
+        let mut text = commands.join("\n");
+        text.push('\n');
+        assert_eq!(script.to_string(), text);
+        let mut written = vec![];
+        script.write_to(&mut written).unwrap();
+        assert_eq!(written, text.as_bytes());
+
+        for (command, expected) in script.0.into_iter().zip(commands) {
+            assert_eq!(command.to_string(), expected);
+            assert_eq!(Sexpr::from(command).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn syntax_error_spans() {
+        let input = "(assert (f x))\n(declare-fun f (Int) :Int)";
+        let err = parse_script(input).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Syntax(_)));
+        assert_eq!(err.span.start.line, 2);
+        assert_eq!(err.span.start.column, 22);
+        assert_eq!(err.to_string(), "2:22: expected sort, found keyword `:Int`");
+
+        let err = parse_command("(define_sort S () Int)").unwrap_err();
+        assert_eq!(err.to_string(), "1:2: unknown command `define_sort`");
+
+        let err = parse_term("(f)").unwrap_err();
+        assert_eq!(err.to_string(), "1:1: incomplete term: missing argument");
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse_sexpr("(a (b c)").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnclosedParen));
+        assert_eq!(err.span.start.offset, 0);
+
+        let err = parse_sexprs("(a))").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedCloseParen));
+        assert_eq!(err.span.start.offset, 3);
+
+        let err = parse_sexpr("(echo \"\x07\")").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::Lex(LexErrorKind::IllegalCharacter('\x07'))
+        ));
+
+        let err = parse_sexpr("a b").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::TrailingInput));
+    }
+}
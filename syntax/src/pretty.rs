@@ -0,0 +1,435 @@
+//! Printing s-expressions across several lines, so that large terms stay readable.
+//!
+//! [`Display`](std::fmt::Display) prints every s-expression on a single line. A
+//! [`PrettyPrinter`] instead breaks lists that do not fit into the line width, putting their
+//! elements on lines of their own. It only ever adds whitespace between tokens, so its output
+//! reads back as exactly the same s-expressions as the compact form.
+//!
+//! ```
+//! use smtlib_syntax::parser::parse_term;
+//! use smtlib_syntax::pretty::PrettyPrinter;
+//!
+//! let term = parse_term("(let ((a (f x y)) (b (g x y))) (and (p a b) (q b a)))").unwrap();
+//! let printer = PrettyPrinter {
+//!     width: 25,
+//!     ..PrettyPrinter::default()
+//! };
+//! assert_eq!(
+//!     printer.term(&term),
+//!     "(let ((a (f x y))\n      (b (g x y)))\n  (and (p a b) (q b a)))"
+//! );
+//! ```
+
+use super::lexicon::Reserved;
+use super::scripts::{Command, CommandType, Script};
+use super::sexprs::Sexpr;
+use super::terms::Term;
+
+/// The options of the pretty-printer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrettyPrinter {
+    /// How far the elements of a broken list are indented relative to its opening parenthesis.
+    pub indent: usize,
+    /// The column that lines should not go past. Single tokens that are longer than the line
+    /// still go past it.
+    pub width: usize,
+    /// Starts every command of a script on a new line. Otherwise, commands that fit are packed
+    /// onto a line together, e.g. `(push 1) (check-sat) (pop 1)`.
+    pub one_command_per_line: bool,
+    /// Keeps the variables of `let`, `forall`, `exists`, `lambda` and `par` next to the binder,
+    /// with the bindings aligned beneath each other if they do not fit on a line, and the body
+    /// indented below. Otherwise, a broken binder puts each of its elements on a line of its own.
+    pub hang_binders: bool,
+    /// Keeps the name, arguments and sort of `define-fun` and `define-fun-rec` on the first line,
+    /// with the arguments aligned beneath each other if they do not fit on a line, and the body
+    /// indented below. Otherwise, a broken definition puts each of its elements on a line of its
+    /// own.
+    pub align_fun_args: bool,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> Self {
+        PrettyPrinter {
+            indent: 2,
+            width: 80,
+            one_command_per_line: true,
+            hang_binders: true,
+            align_fun_args: true,
+        }
+    }
+}
+
+impl PrettyPrinter {
+    pub fn sexpr(&self, sexpr: &Sexpr) -> String {
+        let mut out = Output::default();
+        self.print(&Doc::new(sexpr), 0, &mut out);
+        out.text
+    }
+
+    pub fn term(&self, term: &Term) -> String {
+        self.sexpr(&term.clone().into())
+    }
+
+    pub fn command(&self, command: &Command) -> String {
+        self.sexpr(&command.clone().into())
+    }
+
+    /// Prints the commands one after the other, see [`PrettyPrinter::one_command_per_line`].
+    /// The result ends with a newline unless the script is empty.
+    pub fn script(&self, script: &Script) -> String {
+        let mut out = Output::default();
+
+        for (i, command) in script.0.iter().enumerate() {
+            let doc = Doc::new(&command.clone().into());
+            if i > 0 {
+                if !self.one_command_per_line && self.fits(&out, 1 + doc.width) {
+                    out.push(" ");
+                } else {
+                    out.newline(0);
+                }
+            }
+            self.print(&doc, 0, &mut out);
+        }
+
+        if !script.0.is_empty() {
+            out.newline(0);
+        }
+        out.text
+    }
+
+    fn fits(&self, out: &Output, width: usize) -> bool {
+        out.column + width <= self.width
+    }
+
+    /// Prints the document at the current column, where `trailing` is the number of closing
+    /// parentheses that will directly follow it.
+    fn print(&self, doc: &Doc, trailing: usize, out: &mut Output) {
+        let items = match &doc.kind {
+            DocKind::Atom(text) => return out.push(text),
+            DocKind::List(items) => items,
+        };
+        if items.is_empty() || self.fits(out, doc.width + trailing) {
+            return self.print_flat(doc, out);
+        }
+
+        let column = out.column;
+        out.push("(");
+        let last = items.len() - 1;
+        let trailing_at = |i: usize| if i == last { trailing + 1 } else { 0 };
+
+        match doc.layout {
+            Layout::Binder if self.hang_binders && items.len() == 3 => {
+                self.print(&items[0], 0, out);
+                out.push(" ");
+                self.print(&items[1], 0, out);
+                out.newline(column + self.indent);
+                self.print(&items[2], trailing + 1, out);
+            }
+            Layout::FunctionDef if self.align_fun_args && items.len() == 5 => {
+                for (i, item) in items[..4].iter().enumerate() {
+                    if i > 0 {
+                        out.push(" ");
+                    }
+                    // the arguments are followed by the sort on the same line
+                    let rest = if i < 3 { 1 + items[i + 1].width } else { 0 };
+                    self.print(item, rest, out);
+                }
+                out.newline(column + self.indent);
+                self.print(&items[4], trailing + 1, out);
+            }
+            _ if items[0].is_list() => {
+                // a list of lists, e.g. bindings or match cases, is aligned after the parenthesis
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.newline(column + 1);
+                    }
+                    self.print(item, trailing_at(i), out);
+                }
+            }
+            _ => {
+                self.print(&items[0], trailing_at(0), out);
+                for (i, item) in items.iter().enumerate().skip(1) {
+                    // the first argument stays next to the function, unless it only fits on a
+                    // line of its own or the binder or definition is laid out without its own
+                    // style, and attributes and options keep their value on the line of the
+                    // keyword
+                    let width = item.width + trailing_at(i);
+                    let first = i == 1
+                        && doc.layout == Layout::Plain
+                        && (self.fits(out, 1 + width) || column + self.indent + width > self.width);
+                    if first || items[i - 1].is_keyword() {
+                        out.push(" ");
+                    } else {
+                        out.newline(column + self.indent);
+                    }
+                    self.print(item, trailing_at(i), out);
+                }
+            }
+        }
+
+        out.push(")");
+    }
+
+    fn print_flat(&self, doc: &Doc, out: &mut Output) {
+        match &doc.kind {
+            DocKind::Atom(text) => out.push(text),
+            DocKind::List(items) => {
+                out.push("(");
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(" ");
+                    }
+                    self.print_flat(item, out);
+                }
+                out.push(")");
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    column: usize,
+}
+
+impl Output {
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+        match text.rfind('\n') {
+            // quoted symbols and strings can span lines
+            Some(i) => self.column = text[i + 1..].chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.text.push('\n');
+        self.text.extend(std::iter::repeat_n(' ', indent));
+        self.column = indent;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    Plain,
+    /// `let`, `forall`, `exists`, `lambda` and `par`.
+    Binder,
+    /// `define-fun` and `define-fun-rec`.
+    FunctionDef,
+}
+
+/// An s-expression with the width of its compact form, so that the printer can tell in constant
+/// time whether a list fits on the rest of the line.
+struct Doc {
+    kind: DocKind,
+    layout: Layout,
+    width: usize,
+}
+
+enum DocKind {
+    Atom(String),
+    List(Vec<Doc>),
+}
+
+impl Doc {
+    fn new(sexpr: &Sexpr) -> Doc {
+        match sexpr {
+            Sexpr::Sequence(seq) => {
+                let items: Vec<Doc> = seq.iter().map(Doc::new).collect();
+                let width = 2
+                    + items.iter().map(|doc| doc.width).sum::<usize>()
+                    + items.len().saturating_sub(1);
+                let layout = match seq.first() {
+                    Some(Sexpr::Reserved(
+                        Reserved::Let | Reserved::Forall | Reserved::Exists | Reserved::Par,
+                    )) => Layout::Binder,
+                    Some(Sexpr::Symbol(sym)) if sym.symbol() == "lambda" => Layout::Binder,
+                    Some(Sexpr::Reserved(Reserved::Command(
+                        CommandType::DefineFun | CommandType::DefineFunRec,
+                    ))) => Layout::FunctionDef,
+                    _ => Layout::Plain,
+                };
+
+                Doc {
+                    kind: DocKind::List(items),
+                    layout,
+                    width,
+                }
+            }
+            atom => {
+                let text = atom.to_string();
+                Doc {
+                    width: text.chars().count(),
+                    kind: DocKind::Atom(text),
+                    layout: Layout::Plain,
+                }
+            }
+        }
+    }
+
+    fn is_list(&self) -> bool {
+        matches!(self.kind, DocKind::List(_))
+    }
+
+    fn is_keyword(&self) -> bool {
+        matches!(&self.kind, DocKind::Atom(text) if text.starts_with(':'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_script, parse_sexprs, parse_term};
+
+    #[test]
+    fn breaks_long_lists() {
+        let input = "(set-logic QF_UF) (push 1) (check-sat) (pop 1) \
+            (define-fun f ((a Int) (b Int) (c Int)) Int (ite (> a b) (+ a c) (- b c))) \
+            (assert (forall ((x Int) (y Int)) (! (=> (and (p x) (q y)) (r (f x y 0))) :pattern ((f x y 0)))))";
+        let script = parse_script(input).unwrap();
+        let printer = PrettyPrinter {
+            width: 36,
+            ..PrettyPrinter::default()
+        };
+
+        let expected = "\
+(set-logic QF_UF)
+(push 1)
+(check-sat)
+(pop 1)
+(define-fun f ((a Int)
+               (b Int)
+               (c Int)) Int
+  (ite (> a b) (+ a c) (- b c)))
+(assert (forall ((x Int) (y Int))
+          (! (=> (and (p x) (q y))
+               (r (f x y 0)))
+            :pattern ((f x y 0)))))
+";
+        assert_eq!(printer.script(&script), expected);
+
+        let packed = PrettyPrinter {
+            one_command_per_line: false,
+            hang_binders: false,
+            align_fun_args: false,
+            ..printer
+        };
+        let expected = "\
+(set-logic QF_UF) (push 1)
+(check-sat) (pop 1)
+(define-fun
+  f
+  ((a Int) (b Int) (c Int))
+  Int
+  (ite (> a b) (+ a c) (- b c)))
+(assert (forall
+          ((x Int) (y Int))
+          (! (=> (and (p x) (q y))
+               (r (f x y 0)))
+            :pattern ((f x y 0)))))
+";
+        assert_eq!(packed.script(&script), expected);
+
+        for printer in [printer, packed] {
+            let reparsed = parse_sexprs(&printer.script(&script)).unwrap();
+            assert_eq!(reparsed, parse_sexprs(input).unwrap());
+        }
+    }
+
+    fn printer(width: usize) -> PrettyPrinter {
+        PrettyPrinter {
+            width,
+            ..PrettyPrinter::default()
+        }
+    }
+
+    #[test]
+    fn width() {
+        let term = parse_term("(g (f aaaa bbbb))").unwrap();
+        // the whole term is 17 columns wide
+        assert_eq!(printer(17).term(&term), "(g (f aaaa bbbb))");
+        // breaking before the argument lets it fit, closing parenthesis included
+        assert_eq!(printer(16).term(&term), "(g\n  (f aaaa bbbb))");
+        assert_eq!(printer(15).term(&term), "(g (f aaaa\n     bbbb))");
+
+        let script = parse_script("(define-fun f ((a Int) (b Int)) Int (+ a b))").unwrap();
+        assert_eq!(
+            printer(44).script(&script),
+            "(define-fun f ((a Int) (b Int)) Int (+ a b))\n"
+        );
+        assert_eq!(
+            printer(35).script(&script),
+            "(define-fun f ((a Int) (b Int)) Int\n  (+ a b))\n"
+        );
+        // the sort follows the arguments on their line
+        assert_eq!(
+            printer(34).script(&script),
+            "(define-fun f ((a Int)\n               (b Int)) Int\n  (+ a b))\n"
+        );
+    }
+
+    #[test]
+    fn indent() {
+        let term = parse_term("(and (p x) (q y) (r z))").unwrap();
+        assert_eq!(printer(12).term(&term), "(and (p x)\n  (q y)\n  (r z))");
+        let indented = PrettyPrinter {
+            indent: 4,
+            ..printer(12)
+        };
+        assert_eq!(indented.term(&term), "(and (p x)\n    (q y)\n    (r z))");
+    }
+
+    #[test]
+    fn one_command_per_line() {
+        let script = parse_script("(push 1) (pop 1) (check-sat)").unwrap();
+        assert_eq!(
+            printer(80).script(&script),
+            "(push 1)\n(pop 1)\n(check-sat)\n"
+        );
+
+        let packed = |width| PrettyPrinter {
+            one_command_per_line: false,
+            ..printer(width)
+        };
+        assert_eq!(
+            packed(16).script(&script),
+            "(push 1) (pop 1)\n(check-sat)\n"
+        );
+        assert_eq!(
+            packed(15).script(&script),
+            "(push 1)\n(pop 1)\n(check-sat)\n"
+        );
+        assert_eq!(packed(80).script(&Script(vec![])), "");
+    }
+
+    #[test]
+    fn hang_binders() {
+        let term = parse_term("(forall ((x Int) (y Int)) (=> (p x) (q y)))").unwrap();
+        assert_eq!(
+            printer(20).term(&term),
+            "(forall ((x Int)\n         (y Int))\n  (=> (p x) (q y)))"
+        );
+        let plain = PrettyPrinter {
+            hang_binders: false,
+            ..printer(20)
+        };
+        assert_eq!(
+            plain.term(&term),
+            "(forall\n  ((x Int) (y Int))\n  (=> (p x) (q y)))"
+        );
+    }
+
+    #[test]
+    fn align_fun_args() {
+        let script = parse_script("(define-fun f ((a Int) (b Int)) Int (+ a b))").unwrap();
+        let plain = PrettyPrinter {
+            align_fun_args: false,
+            ..printer(34)
+        };
+        assert_eq!(
+            plain.script(&script),
+            "(define-fun\n  f\n  ((a Int) (b Int))\n  Int\n  (+ a b))\n"
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Reading s-expressions and commands incrementally from a [`BufRead`].
+//!
+//! [`SexprReader`] and [`CommandReader`] only ever buffer the top-level form they are currently
+//! reading, so they can process inputs of any size, as well as solver output on a pipe that never
+//! ends. Each form is parsed the same way [`parse_script`](crate::parser::parse_script) parses a
+//! whole file, and spans refer to the position in the whole stream.
+//!
+//! If the underlying reader fails, e.g. with [`io::ErrorKind::WouldBlock`] on a non-blocking pipe,
+//! the error is returned and the partially read form is kept, so iteration can resume once more
+//! input is available.
+
+use std::io::{self, BufRead};
+
+use super::lexer::{LexErrorKind, Position, Span};
+use super::parser::{ParseError, ParseErrorKind, SexprParser};
+use super::scripts::Command;
+use super::sexprs::{FromSexprError, Sexpr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Normal,
+    String,
+    /// Just read a `"` in a string, which is either the end of the string or the first half of
+    /// an escaped `""`.
+    StringQuote,
+    QuotedSymbol,
+    Comment,
+}
+
+/// Splits a byte stream into top-level forms. All of the state lives in the struct, so reading
+/// can be interrupted at any byte and resumed later.
+struct Splitter<R> {
+    reader: R,
+    scan: Scan,
+}
+
+/// The state of the splitter, i.e. everything but the reader.
+struct Scan {
+    state: State,
+    depth: usize,
+    /// The bytes of the current form. Empty between forms.
+    form: Vec<u8>,
+    /// Where the current form starts.
+    start: Position,
+    /// The position of the next byte.
+    pos: Position,
+}
+
+enum Step {
+    /// Take the byte and look at the next one.
+    Consume,
+    /// The form is complete, including the byte.
+    ConsumeAndFinish,
+    /// The form is complete. The byte belongs to whatever comes next.
+    Finish,
+}
+
+impl Scan {
+    fn in_form(&self) -> bool {
+        !self.form.is_empty()
+    }
+
+    /// Whether we are in the middle of an atom that is not nested in a list.
+    fn in_toplevel_atom(&self) -> bool {
+        self.in_form() && self.depth == 0 && self.state == State::Normal
+    }
+
+    fn push(&mut self, b: u8) {
+        if !self.in_form() {
+            self.start = self.pos;
+        }
+        self.form.push(b);
+    }
+
+    fn step(&mut self, b: u8) -> Step {
+        match self.state {
+            State::Normal => match b {
+                b' ' | b'\t' | b'\r' | b'\n' => {
+                    if self.in_toplevel_atom() {
+                        return Step::Finish;
+                    }
+                    if self.in_form() {
+                        self.push(b);
+                    }
+                    Step::Consume
+                }
+                b';' | b'(' | b')' | b'"' | b'|' if self.in_toplevel_atom() => Step::Finish,
+                b';' => {
+                    if self.in_form() {
+                        self.push(b);
+                    }
+                    self.state = State::Comment;
+                    Step::Consume
+                }
+                b'(' => {
+                    self.push(b);
+                    self.depth += 1;
+                    Step::Consume
+                }
+                b')' => {
+                    self.push(b);
+                    // a `)` on its own is an error, which the parser will report
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        Step::ConsumeAndFinish
+                    } else {
+                        Step::Consume
+                    }
+                }
+                b'"' => {
+                    self.push(b);
+                    self.state = State::String;
+                    Step::Consume
+                }
+                b'|' => {
+                    self.push(b);
+                    self.state = State::QuotedSymbol;
+                    Step::Consume
+                }
+                _ => {
+                    self.push(b);
+                    Step::Consume
+                }
+            },
+            State::String => {
+                self.push(b);
+                if b == b'"' {
+                    self.state = State::StringQuote;
+                }
+                Step::Consume
+            }
+            State::StringQuote => {
+                if b == b'"' {
+                    self.push(b);
+                    self.state = State::String;
+                    Step::Consume
+                } else {
+                    // the string ended with the previous byte
+                    self.state = State::Normal;
+                    if self.depth == 0 {
+                        Step::Finish
+                    } else {
+                        self.step(b)
+                    }
+                }
+            }
+            State::QuotedSymbol => {
+                self.push(b);
+                if b == b'|' {
+                    self.state = State::Normal;
+                    if self.depth == 0 {
+                        return Step::ConsumeAndFinish;
+                    }
+                }
+                Step::Consume
+            }
+            State::Comment => {
+                if self.in_form() {
+                    self.push(b);
+                }
+                if b == b'\n' || b == b'\r' {
+                    self.state = State::Normal;
+                }
+                Step::Consume
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<(String, Position), ParseError> {
+        self.state = State::Normal;
+        self.depth = 0;
+        let form = std::mem::take(&mut self.form);
+        String::from_utf8(form)
+            .map(|text| (text, self.start))
+            .map_err(|_| {
+                ParseError::new(
+                    ParseErrorKind::Lex(LexErrorKind::InvalidUtf8),
+                    Span::new(self.start, self.pos),
+                )
+            })
+    }
+}
+
+impl<R: BufRead> Splitter<R> {
+    fn new(reader: R) -> Self {
+        Splitter {
+            reader,
+            scan: Scan {
+                state: State::Normal,
+                depth: 0,
+                form: vec![],
+                start: Position::start(),
+                pos: Position::start(),
+            },
+        }
+    }
+
+    /// Reads the next top-level form and returns its text and start position.
+    fn next_form(&mut self) -> Option<Result<(String, Position), ParseError>> {
+        let Splitter { reader, scan } = self;
+
+        loop {
+            let buf = match reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    let span = Span::new(scan.pos, scan.pos);
+                    return Some(Err(ParseError::new(
+                        ParseErrorKind::Lex(LexErrorKind::Io(err)),
+                        span,
+                    )));
+                }
+            };
+
+            if buf.is_empty() {
+                // end of input, hand out whatever we have
+                if !scan.in_form() {
+                    return None;
+                }
+                return Some(scan.finish());
+            }
+
+            let mut finished = None;
+            for (i, &b) in buf.iter().enumerate() {
+                match scan.step(b) {
+                    Step::Consume => scan.pos.advance_byte(b),
+                    Step::ConsumeAndFinish => {
+                        scan.pos.advance_byte(b);
+                        finished = Some(i + 1);
+                        break;
+                    }
+                    Step::Finish => {
+                        finished = Some(i);
+                        break;
+                    }
+                }
+            }
+
+            match finished {
+                Some(consumed) => {
+                    reader.consume(consumed);
+                    return Some(scan.finish());
+                }
+                None => {
+                    let len = buf.len();
+                    reader.consume(len);
+                }
+            }
+        }
+    }
+
+    /// Reads the next form and converts it.
+    fn next_converted<T>(
+        &mut self,
+        convert: impl FnOnce(Sexpr) -> Result<T, FromSexprError>,
+    ) -> Option<Result<T, ParseError>> {
+        let (text, start) = match self.next_form()? {
+            Ok(form) => form,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let mut parser = SexprParser::starting_at(&text, start);
+        let result = parser.expect_spanned(convert).and_then(|converted| {
+            parser.expect_eof()?;
+            Ok(converted)
+        });
+
+        Some(result)
+    }
+}
+
+/// Reads s-expressions from a [`BufRead`], one top-level form at a time.
+pub struct SexprReader<R> {
+    splitter: Splitter<R>,
+}
+
+impl<R: BufRead> SexprReader<R> {
+    pub fn new(reader: R) -> Self {
+        SexprReader {
+            splitter: Splitter::new(reader),
+        }
+    }
+
+    /// Returns the underlying reader. Input that was read but not parsed yet is lost.
+    pub fn into_inner(self) -> R {
+        self.splitter.reader
+    }
+}
+
+impl<R: BufRead> Iterator for SexprReader<R> {
+    type Item = Result<Sexpr, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.splitter.next_converted(Ok)
+    }
+}
+
+/// Reads commands from a [`BufRead`], one at a time. After an error, reading continues with the
+/// next top-level form.
+pub struct CommandReader<R> {
+    splitter: Splitter<R>,
+}
+
+impl<R: BufRead> CommandReader<R> {
+    pub fn new(reader: R) -> Self {
+        CommandReader {
+            splitter: Splitter::new(reader),
+        }
+    }
+
+    /// Returns the underlying reader. Input that was read but not parsed yet is lost.
+    pub fn into_inner(self) -> R {
+        self.splitter.reader
+    }
+}
+
+impl<R: BufRead> Iterator for CommandReader<R> {
+    type Item = Result<Command, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.splitter.next_converted(Command::try_from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_script;
+    use std::io::{BufReader, Read};
+
+    const SCRIPT: &str = r#"; a comment (with parens)
+(set-logic QF_LIA) (declare-const |x ; y| Int)
+(assert (> |x ; y| 0)) ; trailing
+(echo "a ""(quoted)"" ;string")
+(check-sat)"#;
+
+    /// Hands out its input in small pieces, failing with `WouldBlock` in between.
+    struct Trickle<'a> {
+        input: &'a [u8],
+        block: bool,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.block = !self.block;
+            if self.block && !self.input.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+
+            let n = self.input.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn same_as_whole_file() {
+        let expected: Vec<_> = parse_script(SCRIPT)
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|command| Sexpr::from(command).to_string())
+            .collect();
+
+        let read: Vec<_> = CommandReader::new(SCRIPT.as_bytes())
+            .map(|command| Sexpr::from(command.unwrap()).to_string())
+            .collect();
+
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn resumes_after_would_block() {
+        let trickle = Trickle {
+            input: SCRIPT.as_bytes(),
+            block: false,
+        };
+        let reader = CommandReader::new(BufReader::with_capacity(4, trickle));
+
+        let mut commands = vec![];
+        let mut blocked = 0;
+        for result in reader {
+            match result {
+                Ok(command) => commands.push(Sexpr::from(command).to_string()),
+                Err(ParseError {
+                    kind: ParseErrorKind::Lex(LexErrorKind::Io(err)),
+                    ..
+                }) if err.kind() == io::ErrorKind::WouldBlock => blocked += 1,
+                Err(err) => panic!("unexpected error: {err}"),
+            }
+        }
+
+        assert!(blocked > 0);
+        assert_eq!(commands.len(), 5);
+        assert_eq!(commands[3], r#"(echo "a ""(quoted)"" ;string")"#);
+    }
+
+    #[test]
+    fn toplevel_atoms_and_errors() {
+        let sexprs: Vec<_> = SexprReader::new("sat\nunknown(x)\"s\"".as_bytes())
+            .map(|sexpr| sexpr.unwrap().to_string())
+            .collect();
+        assert_eq!(sexprs, ["sat", "unknown", "(x)", "\"s\""]);
+
+        let results: Vec<_> =
+            CommandReader::new("(check-sat)\n(assert)\n(exit)".as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.span.start.line, 2);
+        assert!(results[2].is_ok());
+    }
+}
@@ -0,0 +1,589 @@
+//! From the spec:
+//! > 3.9.1 Command responses
+//! >
+//! > The command responses are defined by the following grammar rules.
+//! > ```text
+//! > 〈general_response〉 ::= success | 〈specific_success_response〉
+//! >                       | unsupported | ( error 〈string〉 )
+//! > ```
+//!
+//! Which specific response a solver sends depends on the command it answers, so the types here
+//! are converted from s-expressions one at a time, e.g. as
+//! `GeneralResponse<CheckSatResponse>`. Responses are printed by converting them into [`Sexpr`].
+
+use super::lexicon::{Keyword, Numeral, Reserved, StringConstant, Symbol};
+use super::scripts::{Command, CommandType, FunctionDec, FunctionDef};
+use super::sexprs::{Elements, FromSexprError, FromSexprErrorKind, Production, Sexpr};
+use super::terms::Term;
+
+fn symbol(name: &str) -> Sexpr {
+    Symbol::new(name.to_string())
+        .expect("response keywords are valid symbols")
+        .into()
+}
+
+/// Matches a symbol with the given name.
+fn is_symbol(sexpr: &Sexpr, name: &str) -> bool {
+    matches!(sexpr, Sexpr::Symbol(sym) if sym.symbol() == name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GeneralResponse<T> {
+    Success,
+    Unsupported,
+    Error(StringConstant),
+    Specific(T),
+}
+
+impl<T> TryFrom<Sexpr> for GeneralResponse<T>
+where
+    T: TryFrom<Sexpr, Error = FromSexprError>,
+{
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, FromSexprError> {
+        match sexpr {
+            sexpr if is_symbol(&sexpr, "success") => Ok(GeneralResponse::Success),
+            sexpr if is_symbol(&sexpr, "unsupported") => Ok(GeneralResponse::Unsupported),
+            Sexpr::Sequence(seq) if seq.len() == 2 && is_symbol(&seq[0], "error") => {
+                let mut elems = Elements::new(Production::Response, seq);
+                elems.next("`error`", |_| Ok(()))?;
+                let msg = elems.next("string", StringConstant::try_from)?;
+                Ok(GeneralResponse::Error(msg))
+            }
+            sexpr => T::try_from(sexpr).map(GeneralResponse::Specific),
+        }
+    }
+}
+
+impl<T: Into<Sexpr>> From<GeneralResponse<T>> for Sexpr {
+    fn from(value: GeneralResponse<T>) -> Self {
+        match value {
+            GeneralResponse::Success => symbol("success"),
+            GeneralResponse::Unsupported => symbol("unsupported"),
+            GeneralResponse::Error(msg) => Sexpr::Sequence(vec![symbol("error"), msg.into()]),
+            GeneralResponse::Specific(response) => response.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CheckSatResponse {
+    Sat,
+    Unsat,
+    Unknown,
+}
+
+impl TryFrom<Sexpr> for CheckSatResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        match &sexpr {
+            Sexpr::Symbol(sym) if sym.symbol() == "sat" => Ok(CheckSatResponse::Sat),
+            Sexpr::Symbol(sym) if sym.symbol() == "unsat" => Ok(CheckSatResponse::Unsat),
+            Sexpr::Symbol(sym) if sym.symbol() == "unknown" => Ok(CheckSatResponse::Unknown),
+            other => Err(FromSexprError::unexpected(
+                Production::CheckSatResponse,
+                other,
+            )),
+        }
+    }
+}
+
+impl From<CheckSatResponse> for Sexpr {
+    fn from(value: CheckSatResponse) -> Self {
+        match value {
+            CheckSatResponse::Sat => symbol("sat"),
+            CheckSatResponse::Unsat => symbol("unsat"),
+            CheckSatResponse::Unknown => symbol("unknown"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EchoResponse(pub StringConstant);
+
+impl TryFrom<Sexpr> for EchoResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        StringConstant::try_from(sexpr).map(EchoResponse)
+    }
+}
+
+impl From<EchoResponse> for Sexpr {
+    fn from(value: EchoResponse) -> Self {
+        value.0.into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetAssertionsResponse(pub Vec<Term>);
+
+impl TryFrom<Sexpr> for GetAssertionsResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        Elements::of(Production::Response, sexpr)?
+            .rest(Term::try_from)
+            .map(GetAssertionsResponse)
+    }
+}
+
+impl From<GetAssertionsResponse> for Sexpr {
+    fn from(value: GetAssertionsResponse) -> Self {
+        Sexpr::Sequence(value.0.into_iter().map(|term| term.into()).collect())
+    }
+}
+
+/// An entry of a model, i.e. a function definition using the syntax of the respective command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ModelResponse {
+    DefineFun(FunctionDef),
+    DefineFunRec(FunctionDef),
+    DefineFunsRec(Vec<(FunctionDec, Term)>),
+}
+
+impl TryFrom<Sexpr> for ModelResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        let head = match &sexpr {
+            Sexpr::Sequence(seq) => seq.first(),
+            _ => None,
+        };
+        let is_definition = match head {
+            Some(Sexpr::Reserved(Reserved::Command(command_type))) => matches!(
+                command_type,
+                CommandType::DefineFun | CommandType::DefineFunRec | CommandType::DefineFunsRec
+            ),
+            _ => false,
+        };
+
+        if !is_definition {
+            return Err(FromSexprError::unexpected(
+                Production::ModelResponse,
+                &sexpr,
+            ));
+        }
+
+        match Command::try_from(sexpr)? {
+            Command::DefineFun(def) => Ok(ModelResponse::DefineFun(def)),
+            Command::DefineFunRec(def) => Ok(ModelResponse::DefineFunRec(def)),
+            Command::DefineFunsRec(defs) => Ok(ModelResponse::DefineFunsRec(defs)),
+            _ => unreachable!("command type was checked above"),
+        }
+    }
+}
+
+impl From<ModelResponse> for Command {
+    fn from(value: ModelResponse) -> Self {
+        match value {
+            ModelResponse::DefineFun(def) => Command::DefineFun(def),
+            ModelResponse::DefineFunRec(def) => Command::DefineFunRec(def),
+            ModelResponse::DefineFunsRec(defs) => Command::DefineFunsRec(defs),
+        }
+    }
+}
+
+impl From<ModelResponse> for Sexpr {
+    fn from(value: ModelResponse) -> Self {
+        Command::from(value).into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetModelResponse(pub Vec<ModelResponse>);
+
+impl TryFrom<Sexpr> for GetModelResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::Response, sexpr)?;
+
+        // some solvers still use the SMT-LIB 2.5 form `(model (define-fun ...) ...)`
+        if matches!(elems.peek(), Some(sexpr) if is_symbol(sexpr, "model")) {
+            elems.next("`model`", |_| Ok(()))?;
+        }
+
+        elems.rest(ModelResponse::try_from).map(GetModelResponse)
+    }
+}
+
+impl From<GetModelResponse> for Sexpr {
+    fn from(value: GetModelResponse) -> Self {
+        Sexpr::Sequence(value.0.into_iter().map(|entry| entry.into()).collect())
+    }
+}
+
+/// A term along with its value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ValuationPair(pub Term, pub Term);
+
+impl TryFrom<Sexpr> for ValuationPair {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::ValuationPair, sexpr)?;
+        let term = elems.next("term", Term::try_from)?;
+        let value = elems.next("value", Term::try_from)?;
+        elems.finish()?;
+
+        Ok(ValuationPair(term, value))
+    }
+}
+
+impl From<ValuationPair> for Sexpr {
+    fn from(value: ValuationPair) -> Self {
+        let ValuationPair(term, value) = value;
+        Sexpr::Sequence(vec![term.into(), value.into()])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetValueResponse(pub Vec<ValuationPair>);
+
+impl TryFrom<Sexpr> for GetValueResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        Elements::of(Production::Response, sexpr)?
+            .rest_nonempty("valuation pair", ValuationPair::try_from)
+            .map(GetValueResponse)
+    }
+}
+
+impl From<GetValueResponse> for Sexpr {
+    fn from(value: GetValueResponse) -> Self {
+        Sexpr::Sequence(value.0.into_iter().map(|pair| pair.into()).collect())
+    }
+}
+
+/// A named formula along with its truth value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TValuationPair(pub Symbol, pub bool);
+
+impl TryFrom<Sexpr> for TValuationPair {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::ValuationPair, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let value = elems.next("`true` or `false`", |sexpr| match &sexpr {
+            Sexpr::Symbol(sym) if sym.symbol() == "true" => Ok(true),
+            Sexpr::Symbol(sym) if sym.symbol() == "false" => Ok(false),
+            other => Err(FromSexprError::unexpected(Production::ValuationPair, other)),
+        })?;
+        elems.finish()?;
+
+        Ok(TValuationPair(name, value))
+    }
+}
+
+impl From<TValuationPair> for Sexpr {
+    fn from(value: TValuationPair) -> Self {
+        let TValuationPair(name, value) = value;
+        let value = if value { "true" } else { "false" };
+        Sexpr::Sequence(vec![name.into(), symbol(value)])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetAssignmentResponse(pub Vec<TValuationPair>);
+
+impl TryFrom<Sexpr> for GetAssignmentResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        Elements::of(Production::Response, sexpr)?
+            .rest(TValuationPair::try_from)
+            .map(GetAssignmentResponse)
+    }
+}
+
+impl From<GetAssignmentResponse> for Sexpr {
+    fn from(value: GetAssignmentResponse) -> Self {
+        Sexpr::Sequence(value.0.into_iter().map(|pair| pair.into()).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetUnsatCoreResponse(pub Vec<Symbol>);
+
+impl TryFrom<Sexpr> for GetUnsatCoreResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        Elements::of(Production::Response, sexpr)?
+            .rest(Symbol::try_from)
+            .map(GetUnsatCoreResponse)
+    }
+}
+
+impl From<GetUnsatCoreResponse> for Sexpr {
+    fn from(value: GetUnsatCoreResponse) -> Self {
+        Sexpr::Sequence(value.0.into_iter().map(|sym| sym.into()).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetUnsatAssumptionsResponse(pub Vec<Symbol>);
+
+impl TryFrom<Sexpr> for GetUnsatAssumptionsResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        Elements::of(Production::Response, sexpr)?
+            .rest(Symbol::try_from)
+            .map(GetUnsatAssumptionsResponse)
+    }
+}
+
+impl From<GetUnsatAssumptionsResponse> for Sexpr {
+    fn from(value: GetUnsatAssumptionsResponse) -> Self {
+        Sexpr::Sequence(value.0.into_iter().map(|sym| sym.into()).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorBehavior {
+    ImmediateExit,
+    ContinuedExecution,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ReasonUnknown {
+    Memout,
+    Incomplete,
+    Other(Sexpr),
+}
+
+/// An entry of the response to `get-info`. Flags the spec does not define a response for are
+/// kept as keyword and value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InfoResponse {
+    AssertionStackLevels(Numeral),
+    Authors(StringConstant),
+    ErrorBehavior(ErrorBehavior),
+    Name(StringConstant),
+    ReasonUnknown(ReasonUnknown),
+    Version(StringConstant),
+    Other(Keyword, Option<Sexpr>),
+}
+
+impl InfoResponse {
+    fn from_keyword_value(keyword: Keyword, value: Sexpr) -> Result<Self, FromSexprError> {
+        let response = match keyword.keyword() {
+            "assertion-stack-levels" => {
+                InfoResponse::AssertionStackLevels(Numeral::try_from(value)?)
+            }
+            "authors" => InfoResponse::Authors(StringConstant::try_from(value)?),
+            "error-behavior" if is_symbol(&value, "immediate-exit") => {
+                InfoResponse::ErrorBehavior(ErrorBehavior::ImmediateExit)
+            }
+            "error-behavior" if is_symbol(&value, "continued-execution") => {
+                InfoResponse::ErrorBehavior(ErrorBehavior::ContinuedExecution)
+            }
+            "error-behavior" => {
+                return Err(FromSexprError::unexpected(Production::InfoResponse, &value))
+            }
+            "name" => InfoResponse::Name(StringConstant::try_from(value)?),
+            "reason-unknown" if is_symbol(&value, "memout") => {
+                InfoResponse::ReasonUnknown(ReasonUnknown::Memout)
+            }
+            "reason-unknown" if is_symbol(&value, "incomplete") => {
+                InfoResponse::ReasonUnknown(ReasonUnknown::Incomplete)
+            }
+            "reason-unknown" => InfoResponse::ReasonUnknown(ReasonUnknown::Other(value)),
+            "version" => InfoResponse::Version(StringConstant::try_from(value)?),
+            _ => InfoResponse::Other(keyword, Some(value)),
+        };
+
+        Ok(response)
+    }
+
+    fn into_sexprs(self) -> Vec<Sexpr> {
+        let keyword = |name: &str| -> Sexpr {
+            Keyword::new(name.to_string())
+                .expect("info flags are valid keywords")
+                .into()
+        };
+
+        match self {
+            InfoResponse::AssertionStackLevels(n) => {
+                vec![keyword("assertion-stack-levels"), n.into()]
+            }
+            InfoResponse::Authors(s) => vec![keyword("authors"), s.into()],
+            InfoResponse::ErrorBehavior(behavior) => {
+                let value = match behavior {
+                    ErrorBehavior::ImmediateExit => "immediate-exit",
+                    ErrorBehavior::ContinuedExecution => "continued-execution",
+                };
+                vec![keyword("error-behavior"), symbol(value)]
+            }
+            InfoResponse::Name(s) => vec![keyword("name"), s.into()],
+            InfoResponse::ReasonUnknown(reason) => {
+                let value = match reason {
+                    ReasonUnknown::Memout => symbol("memout"),
+                    ReasonUnknown::Incomplete => symbol("incomplete"),
+                    ReasonUnknown::Other(sexpr) => sexpr,
+                };
+                vec![keyword("reason-unknown"), value]
+            }
+            InfoResponse::Version(s) => vec![keyword("version"), s.into()],
+            InfoResponse::Other(kw, value) => std::iter::once(kw.into()).chain(value).collect(),
+        }
+    }
+}
+
+/// The response to `get-info`. Solvers print the entries as one flat list of keywords and values,
+/// e.g. `(:name "foo" :version "1.0")`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetInfoResponse(pub Vec<InfoResponse>);
+
+impl TryFrom<Sexpr> for GetInfoResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::Response, sexpr)?;
+        let mut responses = vec![];
+
+        while elems.peek().is_some() {
+            let keyword = elems.next("keyword", Keyword::try_from)?;
+            let value = match elems.peek() {
+                None | Some(Sexpr::Keyword(_)) => None,
+                Some(_) => Some(elems.next("value", Ok)?),
+            };
+
+            responses.push(match value {
+                Some(value) => InfoResponse::from_keyword_value(keyword, value)?,
+                None => InfoResponse::Other(keyword, None),
+            });
+        }
+
+        if responses.is_empty() {
+            return Err(FromSexprError::new(
+                Production::Response,
+                FromSexprErrorKind::Missing {
+                    expected: "info response",
+                },
+            ));
+        }
+
+        Ok(GetInfoResponse(responses))
+    }
+}
+
+impl From<GetInfoResponse> for Sexpr {
+    fn from(value: GetInfoResponse) -> Self {
+        Sexpr::Sequence(
+            value
+                .0
+                .into_iter()
+                .flat_map(InfoResponse::into_sexprs)
+                .collect(),
+        )
+    }
+}
+
+/// The response to `get-option`, i.e. the value of the option.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetOptionResponse(pub Sexpr);
+
+impl TryFrom<Sexpr> for GetOptionResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Keyword(_) | Sexpr::Reserved(_) => {
+                Err(FromSexprError::unexpected(Production::Response, &sexpr))
+            }
+            sexpr => Ok(GetOptionResponse(sexpr)),
+        }
+    }
+}
+
+impl From<GetOptionResponse> for Sexpr {
+    fn from(value: GetOptionResponse) -> Self {
+        value.0
+    }
+}
+
+/// The response to `get-proof`. The format of proofs is solver-specific.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GetProofResponse(pub Sexpr);
+
+impl TryFrom<Sexpr> for GetProofResponse {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr) -> Result<Self, Self::Error> {
+        Ok(GetProofResponse(sexpr))
+    }
+}
+
+impl From<GetProofResponse> for Sexpr {
+    fn from(value: GetProofResponse) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_response;
+
+    fn roundtrip<T>(input: &str) -> String
+    where
+        T: TryFrom<Sexpr, Error = FromSexprError> + Into<Sexpr>,
+    {
+        Sexpr::from(parse_response::<T>(input).unwrap()).to_string()
+    }
+
+    #[test]
+    fn general_responses() {
+        assert!(matches!(
+            parse_response::<CheckSatResponse>("unsat").unwrap(),
+            GeneralResponse::Specific(CheckSatResponse::Unsat)
+        ));
+        assert!(matches!(
+            parse_response::<GetModelResponse>("unsupported").unwrap(),
+            GeneralResponse::Unsupported
+        ));
+        assert_eq!(
+            roundtrip::<GetValueResponse>(r#"(error "no model available")"#),
+            r#"(error "no model available")"#
+        );
+        assert!(parse_response::<CheckSatResponse>("maybe").is_err());
+    }
+
+    #[test]
+    fn specific_responses() {
+        let model = "((define-fun x () Int 5) (define-fun f ((a Int)) Bool (> a x)))";
+        assert_eq!(roundtrip::<GetModelResponse>(model), model);
+        assert_eq!(
+            roundtrip::<GetModelResponse>("(model (define-fun x () Int 5))"),
+            "((define-fun x () Int 5))"
+        );
+
+        let values = "((x 5) ((f x) true))";
+        assert_eq!(roundtrip::<GetValueResponse>(values), values);
+
+        let assignment = "((a0 true) (a1 false))";
+        assert_eq!(roundtrip::<GetAssignmentResponse>(assignment), assignment);
+
+        assert_eq!(
+            roundtrip::<GetUnsatCoreResponse>("(a0 |a 1|)"),
+            "(a0 |a 1|)"
+        );
+
+        let info = r#"(:name "z3" :version "4.12.2" :reason-unknown incomplete :foo)"#;
+        assert_eq!(roundtrip::<GetInfoResponse>(info), info);
+        assert!(matches!(
+            parse_response::<GetInfoResponse>(r#"(:name "z3")"#).unwrap(),
+            GeneralResponse::Specific(GetInfoResponse(ref entries))
+                if matches!(entries[..], [InfoResponse::Name(_)])
+        ));
+
+        assert_eq!(roundtrip::<GetOptionResponse>("true"), "true");
+    }
+}
@@ -1,10 +1,37 @@
-use super::lexicon::{Keyword, Reserved, StringConstant, Symbol};
-use super::sexprs::Sexpr;
+use std::fmt::Display;
+
+use super::attributes::AttributeValue;
+use super::lexicon::{
+    Decimal, InvalidStringError, Keyword, Numeral, Reserved, StringConstant, Symbol, Text,
+};
+use super::sexprs::{
+    self, Elements, FromSexprError, FromSexprErrorKind, Node, Production, Sexpr, SpecialConstant,
+};
 use super::sorts::Sort;
 use super::terms::{SortedVar, Term};
+use super::writer::{self, FmtWriter, IoWriter};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SortDec<T = String>(pub Symbol<T>, pub Numeral);
 
-#[derive(Clone, Debug)]
-pub struct SortDec(pub Symbol, pub i64);
+impl<T: Text> SortDec<T> {
+    pub fn into_owned(self) -> SortDec {
+        SortDec(self.0.into_owned(), self.1)
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for SortDec<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::SortDec, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let arity = elems.next("numeral", Numeral::try_from)?;
+        elems.finish()?;
+
+        Ok(SortDec(name, arity))
+    }
+}
 
 impl From<SortDec> for Sexpr {
     fn from(value: SortDec) -> Self {
@@ -13,10 +40,38 @@ impl From<SortDec> for Sexpr {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct SelectorDec {
-    pub name: Symbol,
-    pub sort: Sort,
+impl<T: Text> Display for SortDec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SelectorDec<T = String> {
+    pub name: Symbol<T>,
+    pub sort: Sort<T>,
+}
+
+impl<T: Text> SelectorDec<T> {
+    pub fn into_owned(self) -> SelectorDec {
+        SelectorDec {
+            name: self.name.into_owned(),
+            sort: self.sort.into_owned(),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for SelectorDec<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::SelectorDec, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let sort = elems.next("sort", Sort::try_from)?;
+        elems.finish()?;
+
+        Ok(SelectorDec { name, sort })
+    }
 }
 
 impl From<SelectorDec> for Sexpr {
@@ -26,10 +81,37 @@ impl From<SelectorDec> for Sexpr {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct ConstructorDec {
-    pub name: Symbol,
-    pub selectors: Vec<SelectorDec>,
+impl<T: Text> Display for SelectorDec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConstructorDec<T = String> {
+    pub name: Symbol<T>,
+    pub selectors: Vec<SelectorDec<T>>,
+}
+
+impl<T: Text> ConstructorDec<T> {
+    pub fn into_owned(self) -> ConstructorDec {
+        ConstructorDec {
+            name: self.name.into_owned(),
+            selectors: sexprs::into_owned(self.selectors, SelectorDec::into_owned),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for ConstructorDec<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::ConstructorDec, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let selectors = elems.rest(SelectorDec::try_from)?;
+
+        Ok(ConstructorDec { name, selectors })
+    }
 }
 
 impl From<ConstructorDec> for Sexpr {
@@ -44,33 +126,253 @@ impl From<ConstructorDec> for Sexpr {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct DatatypeDec {
-    par: Vec<Symbol>,
-    constructors: Vec<ConstructorDec>,
+impl<T: Text> Display for ConstructorDec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+/// A parametric datatype was given a different number of sorts than it has parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterCountError {
+    expected: usize,
+    found: usize,
+}
+
+impl Display for ParameterCountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected {} sort parameters, found {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParameterCountError {}
+
+/// A datatype declaration without constructors, or a parametric one without parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidDatatypeDecError(&'static str);
+
+impl Display for InvalidDatatypeDecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a datatype declaration needs at least one {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDatatypeDecError {}
+
+/// The constructors of a datatype, and the sort parameters if it is parametric, as in
+/// `(par (T) ((nil) (cons (head T) (tail (List T)))))`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DatatypeDec<T = String> {
+    pub(crate) par: Vec<Symbol<T>>,
+    pub(crate) constructors: Vec<ConstructorDec<T>>,
+}
+
+impl<T: Text> DatatypeDec<T> {
+    /// The sort parameters, empty unless the datatype is parametric.
+    pub fn parameters(&self) -> &[Symbol<T>] {
+        &self.par
+    }
+
+    pub fn constructors(&self) -> &[ConstructorDec<T>] {
+        &self.constructors
+    }
+
+    pub fn into_owned(self) -> DatatypeDec {
+        DatatypeDec {
+            par: sexprs::into_owned(self.par, Symbol::into_owned),
+            constructors: sexprs::into_owned(self.constructors, ConstructorDec::into_owned),
+        }
+    }
+}
+
+impl DatatypeDec {
+    pub fn new(constructors: Vec<ConstructorDec>) -> Result<Self, InvalidDatatypeDecError> {
+        if constructors.is_empty() {
+            return Err(InvalidDatatypeDecError("constructor"));
+        }
+
+        Ok(DatatypeDec {
+            par: vec![],
+            constructors,
+        })
+    }
+
+    /// A datatype with the sort parameters `par`, which the sorts of the selectors can refer to.
+    pub fn parametric(
+        par: Vec<Symbol>,
+        constructors: Vec<ConstructorDec>,
+    ) -> Result<Self, InvalidDatatypeDecError> {
+        if par.is_empty() {
+            return Err(InvalidDatatypeDecError("sort parameter"));
+        }
+
+        let mut dec = DatatypeDec::new(constructors)?;
+        dec.par = par;
+        Ok(dec)
+    }
+
+    fn check_parameter_count(&self, args: &[Sort]) -> Result<(), ParameterCountError> {
+        if args.len() == self.par.len() {
+            Ok(())
+        } else {
+            Err(ParameterCountError {
+                expected: self.par.len(),
+                found: args.len(),
+            })
+        }
+    }
+
+    /// The sort of an instance of the datatype declared as `name`, e.g. `(List Int)`.
+    pub fn sort(&self, name: Symbol, args: Vec<Sort>) -> Result<Sort, ParameterCountError> {
+        self.check_parameter_count(&args)?;
+        Ok(Sort::parametric(name, args))
+    }
+
+    /// The constructors of an instance of the datatype, with the parameters in the sorts of the
+    /// selectors replaced by `args`.
+    pub fn instantiate(&self, args: &[Sort]) -> Result<Vec<ConstructorDec>, ParameterCountError> {
+        self.check_parameter_count(args)?;
+
+        let constructors = self
+            .constructors
+            .iter()
+            .map(|constructor| ConstructorDec {
+                name: constructor.name.clone(),
+                selectors: constructor
+                    .selectors
+                    .iter()
+                    .map(|selector| SelectorDec {
+                        name: selector.name.clone(),
+                        sort: selector.sort.substitute(&self.par, args),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(constructors)
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for DatatypeDec<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::DatatypeDec, sexpr)?;
+        if let Some(Sexpr::Reserved(Reserved::Par)) = elems.peek() {
+            elems.next("`par`", |_| Ok(()))?;
+            let par = elems.next_nonempty_list("sort parameter", Symbol::try_from)?;
+            let constructors = elems.next_nonempty_list("constructor", ConstructorDec::try_from)?;
+            elems.finish()?;
+
+            return Ok(DatatypeDec { par, constructors });
+        }
+
+        let constructors = elems.rest_nonempty("constructor", ConstructorDec::try_from)?;
+
+        Ok(DatatypeDec {
+            par: vec![],
+            constructors,
+        })
+    }
 }
 
 impl From<DatatypeDec> for Sexpr {
     fn from(value: DatatypeDec) -> Self {
         let DatatypeDec { par, constructors } = value;
+        let constructors = constructors
+            .into_iter()
+            .map(|sel_dec| sel_dec.into())
+            .collect();
         if par.is_empty() {
-            Sexpr::Sequence(
-                constructors
-                    .into_iter()
-                    .map(|sel_dec| sel_dec.into())
-                    .collect(),
-            )
+            Sexpr::Sequence(constructors)
         } else {
-            todo!()
+            Sexpr::Sequence(vec![
+                Reserved::Par.into(),
+                to_seq(par),
+                Sexpr::Sequence(constructors),
+            ])
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct FunctionDec {
-    name: Symbol,
-    args: Vec<SortedVar>,
-    sort: Sort,
+impl<T: Text> Display for DatatypeDec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+/// Checks that the sort declaration of `declare-datatypes` at `index` declares as many
+/// parameters as the datatype declaration has.
+pub(crate) fn check_arity(
+    index: usize,
+    arity: &Numeral,
+    parameters: usize,
+) -> Result<(), FromSexprError> {
+    if usize::try_from(arity).ok() == Some(parameters) {
+        return Ok(());
+    }
+
+    Err(FromSexprError::new(
+        Production::DatatypeDec,
+        FromSexprErrorKind::ArityMismatch {
+            arity: arity.clone(),
+            parameters,
+        },
+    )
+    .at(index)
+    .at(2))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FunctionDec<T = String> {
+    pub(crate) name: Symbol<T>,
+    pub(crate) args: Vec<SortedVar<T>>,
+    pub(crate) sort: Sort<T>,
+}
+
+impl<T: Text> FunctionDec<T> {
+    pub fn new(name: Symbol<T>, args: Vec<SortedVar<T>>, sort: Sort<T>) -> Self {
+        FunctionDec { name, args, sort }
+    }
+
+    pub fn name(&self) -> &Symbol<T> {
+        &self.name
+    }
+
+    pub fn args(&self) -> &[SortedVar<T>] {
+        &self.args
+    }
+
+    /// The sort of the result.
+    pub fn sort(&self) -> &Sort<T> {
+        &self.sort
+    }
+
+    pub fn into_owned(self) -> FunctionDec {
+        FunctionDec {
+            name: self.name.into_owned(),
+            args: sexprs::into_owned(self.args, SortedVar::into_owned),
+            sort: self.sort.into_owned(),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for FunctionDec<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::FunctionDec, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let args = elems.next_list("sorted variable", SortedVar::try_from)?;
+        let sort = elems.next("sort", Sort::try_from)?;
+        elems.finish()?;
+
+        Ok(FunctionDec { name, args, sort })
+    }
 }
 
 impl From<FunctionDec> for Sexpr {
@@ -85,12 +387,72 @@ impl From<FunctionDec> for Sexpr {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct FunctionDef {
-    name: Symbol,
-    args: Vec<SortedVar>,
-    sort: Sort,
-    body: Term,
+impl<T: Text> Display for FunctionDec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FunctionDef<T = String> {
+    pub(crate) name: Symbol<T>,
+    pub(crate) args: Vec<SortedVar<T>>,
+    pub(crate) sort: Sort<T>,
+    pub(crate) body: Term<T>,
+}
+
+impl<T: Text> FunctionDef<T> {
+    pub fn new(name: Symbol<T>, args: Vec<SortedVar<T>>, sort: Sort<T>, body: Term<T>) -> Self {
+        FunctionDef {
+            name,
+            args,
+            sort,
+            body,
+        }
+    }
+
+    pub fn name(&self) -> &Symbol<T> {
+        &self.name
+    }
+
+    pub fn args(&self) -> &[SortedVar<T>] {
+        &self.args
+    }
+
+    /// The sort of the result.
+    pub fn sort(&self) -> &Sort<T> {
+        &self.sort
+    }
+
+    pub fn body(&self) -> &Term<T> {
+        &self.body
+    }
+
+    pub fn into_owned(self) -> FunctionDef {
+        FunctionDef {
+            name: self.name.into_owned(),
+            args: sexprs::into_owned(self.args, SortedVar::into_owned),
+            sort: self.sort.into_owned(),
+            body: self.body.into_owned(),
+        }
+    }
+
+    /// Reads a function definition from the elements of a `define-fun` or `define-fun-rec`
+    /// command that follow the command name.
+    pub(crate) fn from_elements(mut elems: Elements<Sexpr<T>>) -> Result<Self, FromSexprError> {
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let args = elems.next_list("sorted variable", SortedVar::try_from)?;
+        let sort = elems.next("sort", Sort::try_from)?;
+        let body = elems.next("term", Term::try_from)?;
+        elems.finish()?;
+
+        Ok(FunctionDef {
+            name,
+            args,
+            sort,
+            body,
+        })
+    }
 }
 
 impl From<FunctionDef> for Vec<Sexpr> {
@@ -111,10 +473,78 @@ impl From<FunctionDef> for Vec<Sexpr> {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct PropLiteral {
-    negate: bool,
-    symbol: Symbol,
+/// Prints the elements of a `define-fun` or `define-fun-rec` command that follow the command name.
+impl<T: Text> Display for FunctionDef<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display_elements(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PropLiteral<T = String> {
+    pub(crate) negate: bool,
+    pub(crate) symbol: Symbol<T>,
+}
+
+impl<T: Text> PropLiteral<T> {
+    /// The literal `symbol`.
+    pub fn new(symbol: Symbol<T>) -> Self {
+        PropLiteral {
+            negate: false,
+            symbol,
+        }
+    }
+
+    /// The literal `(not symbol)`.
+    pub fn negated(symbol: Symbol<T>) -> Self {
+        PropLiteral {
+            negate: true,
+            symbol,
+        }
+    }
+
+    pub fn symbol(&self) -> &Symbol<T> {
+        &self.symbol
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negate
+    }
+
+    pub fn into_owned(self) -> PropLiteral {
+        PropLiteral {
+            negate: self.negate,
+            symbol: self.symbol.into_owned(),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for PropLiteral<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Symbol(symbol) => Ok(PropLiteral {
+                negate: false,
+                symbol,
+            }),
+            Sexpr::Sequence(seq) => {
+                let mut elems = Elements::new(Production::PropLiteral, seq);
+                elems.next("`not`", |sexpr| match sexpr {
+                    Sexpr::Symbol(sym) if sym.symbol() == "not" => Ok(()),
+                    other => Err(FromSexprError::unexpected(Production::PropLiteral, &other)),
+                })?;
+                let symbol = elems.next("symbol", Symbol::try_from)?;
+                elems.finish()?;
+
+                Ok(PropLiteral {
+                    negate: true,
+                    symbol,
+                })
+            }
+            other => Err(FromSexprError::unexpected(Production::PropLiteral, &other)),
+        }
+    }
 }
 
 impl From<PropLiteral> for Sexpr {
@@ -131,7 +561,443 @@ impl From<PropLiteral> for Sexpr {
     }
 }
 
-#[derive(Debug, Clone)]
+impl<T: Text> Display for PropLiteral<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+/// Reads a `true` or `false` symbol.
+pub(crate) fn b_value<S: Node>(sexpr: S) -> Result<bool, FromSexprError> {
+    match sexpr.as_symbol() {
+        Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        _ => Err(FromSexprError::unexpected(Production::BValue, &sexpr)),
+    }
+}
+
+/// An option of `set-option`. The spec defines the value of each standard option, every other
+/// option is kept as an attribute, e.g. solver specific ones like `:smt.mbqi`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SmtOption<T = String> {
+    DiagnosticOutputChannel(StringConstant<T>),
+    GlobalDeclarations(bool),
+    InteractiveMode(bool),
+    PrintSuccess(bool),
+    ProduceAssertions(bool),
+    ProduceAssignments(bool),
+    ProduceModels(bool),
+    ProduceProofs(bool),
+    ProduceUnsatAssumptions(bool),
+    ProduceUnsatCores(bool),
+    RandomSeed(Numeral),
+    RegularOutputChannel(StringConstant<T>),
+    ReproducibleResourceLimit(Numeral),
+    Verbosity(Numeral),
+    Other(Keyword<T>, Option<AttributeValue<T>>),
+}
+
+impl<T: Text> SmtOption<T> {
+    /// The keyword that names the option, e.g. to query it with `get-option`.
+    pub fn keyword(&self) -> Keyword {
+        match self {
+            SmtOption::Other(kw, _) => kw.clone().into_owned(),
+            _ => Keyword::new(self.name().to_string()).expect("option names are valid keywords"),
+        }
+    }
+
+    /// The keyword without the colon.
+    fn name(&self) -> &str {
+        match self {
+            SmtOption::DiagnosticOutputChannel(_) => "diagnostic-output-channel",
+            SmtOption::GlobalDeclarations(_) => "global-declarations",
+            SmtOption::InteractiveMode(_) => "interactive-mode",
+            SmtOption::PrintSuccess(_) => "print-success",
+            SmtOption::ProduceAssertions(_) => "produce-assertions",
+            SmtOption::ProduceAssignments(_) => "produce-assignments",
+            SmtOption::ProduceModels(_) => "produce-models",
+            SmtOption::ProduceProofs(_) => "produce-proofs",
+            SmtOption::ProduceUnsatAssumptions(_) => "produce-unsat-assumptions",
+            SmtOption::ProduceUnsatCores(_) => "produce-unsat-cores",
+            SmtOption::RandomSeed(_) => "random-seed",
+            SmtOption::RegularOutputChannel(_) => "regular-output-channel",
+            SmtOption::ReproducibleResourceLimit(_) => "reproducible-resource-limit",
+            SmtOption::Verbosity(_) => "verbosity",
+            SmtOption::Other(kw, _) => kw.keyword(),
+        }
+    }
+
+    pub fn into_owned(self) -> SmtOption {
+        match self {
+            SmtOption::DiagnosticOutputChannel(s) => {
+                SmtOption::DiagnosticOutputChannel(s.into_owned())
+            }
+            SmtOption::GlobalDeclarations(b) => SmtOption::GlobalDeclarations(b),
+            SmtOption::InteractiveMode(b) => SmtOption::InteractiveMode(b),
+            SmtOption::PrintSuccess(b) => SmtOption::PrintSuccess(b),
+            SmtOption::ProduceAssertions(b) => SmtOption::ProduceAssertions(b),
+            SmtOption::ProduceAssignments(b) => SmtOption::ProduceAssignments(b),
+            SmtOption::ProduceModels(b) => SmtOption::ProduceModels(b),
+            SmtOption::ProduceProofs(b) => SmtOption::ProduceProofs(b),
+            SmtOption::ProduceUnsatAssumptions(b) => SmtOption::ProduceUnsatAssumptions(b),
+            SmtOption::ProduceUnsatCores(b) => SmtOption::ProduceUnsatCores(b),
+            SmtOption::RandomSeed(n) => SmtOption::RandomSeed(n),
+            SmtOption::RegularOutputChannel(s) => SmtOption::RegularOutputChannel(s.into_owned()),
+            SmtOption::ReproducibleResourceLimit(n) => SmtOption::ReproducibleResourceLimit(n),
+            SmtOption::Verbosity(n) => SmtOption::Verbosity(n),
+            SmtOption::Other(kw, value) => {
+                SmtOption::Other(kw.into_owned(), value.map(AttributeValue::into_owned))
+            }
+        }
+    }
+
+    pub(crate) fn from_elements(mut elems: Elements<Sexpr<T>>) -> Result<Self, FromSexprError> {
+        let kw = elems.next("option", Keyword::try_from)?;
+        let option = match kw.keyword() {
+            "diagnostic-output-channel" => {
+                SmtOption::DiagnosticOutputChannel(elems.next("string", StringConstant::try_from)?)
+            }
+            "global-declarations" => SmtOption::GlobalDeclarations(elems.next("boolean", b_value)?),
+            "interactive-mode" => SmtOption::InteractiveMode(elems.next("boolean", b_value)?),
+            "print-success" => SmtOption::PrintSuccess(elems.next("boolean", b_value)?),
+            "produce-assertions" => SmtOption::ProduceAssertions(elems.next("boolean", b_value)?),
+            "produce-assignments" => SmtOption::ProduceAssignments(elems.next("boolean", b_value)?),
+            "produce-models" => SmtOption::ProduceModels(elems.next("boolean", b_value)?),
+            "produce-proofs" => SmtOption::ProduceProofs(elems.next("boolean", b_value)?),
+            "produce-unsat-assumptions" => {
+                SmtOption::ProduceUnsatAssumptions(elems.next("boolean", b_value)?)
+            }
+            "produce-unsat-cores" => SmtOption::ProduceUnsatCores(elems.next("boolean", b_value)?),
+            "random-seed" => SmtOption::RandomSeed(elems.next("numeral", Numeral::try_from)?),
+            "regular-output-channel" => {
+                SmtOption::RegularOutputChannel(elems.next("string", StringConstant::try_from)?)
+            }
+            "reproducible-resource-limit" => {
+                SmtOption::ReproducibleResourceLimit(elems.next("numeral", Numeral::try_from)?)
+            }
+            "verbosity" => SmtOption::Verbosity(elems.next("numeral", Numeral::try_from)?),
+            _ => match elems.peek() {
+                None => SmtOption::Other(kw, None),
+                Some(_) => SmtOption::Other(
+                    kw,
+                    Some(elems.next("option value", AttributeValue::try_from)?),
+                ),
+            },
+        };
+        elems.finish()?;
+
+        Ok(option)
+    }
+}
+
+impl From<SmtOption> for Vec<Sexpr> {
+    fn from(value: SmtOption) -> Self {
+        let kw = value.keyword().into();
+        let b_value = |b: bool| -> Sexpr {
+            Symbol::new(b.to_string())
+                .expect("`true` and `false` are valid symbols")
+                .into()
+        };
+
+        let value = match value {
+            SmtOption::DiagnosticOutputChannel(s) | SmtOption::RegularOutputChannel(s) => {
+                Some(s.into())
+            }
+            SmtOption::GlobalDeclarations(b)
+            | SmtOption::InteractiveMode(b)
+            | SmtOption::PrintSuccess(b)
+            | SmtOption::ProduceAssertions(b)
+            | SmtOption::ProduceAssignments(b)
+            | SmtOption::ProduceModels(b)
+            | SmtOption::ProduceProofs(b)
+            | SmtOption::ProduceUnsatAssumptions(b)
+            | SmtOption::ProduceUnsatCores(b) => Some(b_value(b)),
+            SmtOption::RandomSeed(n)
+            | SmtOption::ReproducibleResourceLimit(n)
+            | SmtOption::Verbosity(n) => Some(n.into()),
+            SmtOption::Other(_, value) => value.map(Sexpr::from),
+        };
+
+        std::iter::once(kw).chain(value).collect()
+    }
+}
+
+/// Prints the keyword and the value, as they follow `set-option`.
+impl<T: Text> Display for SmtOption<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display_elements(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InfoFlag<T = String> {
+    AllStatistics,
+    AssertionStackLevels,
+    Authors,
+    ErrorBehavior,
+    Name,
+    ReasonUnknown,
+    Version,
+    Other(Keyword<T>),
+}
+
+impl<T: Text> InfoFlag<T> {
+    pub fn keyword(&self) -> Keyword {
+        match self {
+            InfoFlag::Other(kw) => kw.clone().into_owned(),
+            _ => Keyword::new(self.name().to_string()).expect("info flags are valid keywords"),
+        }
+    }
+
+    /// The keyword without the colon.
+    fn name(&self) -> &str {
+        match self {
+            InfoFlag::AllStatistics => "all-statistics",
+            InfoFlag::AssertionStackLevels => "assertion-stack-levels",
+            InfoFlag::Authors => "authors",
+            InfoFlag::ErrorBehavior => "error-behavior",
+            InfoFlag::Name => "name",
+            InfoFlag::ReasonUnknown => "reason-unknown",
+            InfoFlag::Version => "version",
+            InfoFlag::Other(kw) => kw.keyword(),
+        }
+    }
+
+    pub fn into_owned(self) -> InfoFlag {
+        match self {
+            InfoFlag::AllStatistics => InfoFlag::AllStatistics,
+            InfoFlag::AssertionStackLevels => InfoFlag::AssertionStackLevels,
+            InfoFlag::Authors => InfoFlag::Authors,
+            InfoFlag::ErrorBehavior => InfoFlag::ErrorBehavior,
+            InfoFlag::Name => InfoFlag::Name,
+            InfoFlag::ReasonUnknown => InfoFlag::ReasonUnknown,
+            InfoFlag::Version => InfoFlag::Version,
+            InfoFlag::Other(kw) => InfoFlag::Other(kw.into_owned()),
+        }
+    }
+}
+
+impl<T: Text> From<Keyword<T>> for InfoFlag<T> {
+    fn from(value: Keyword<T>) -> Self {
+        match value.keyword() {
+            "all-statistics" => InfoFlag::AllStatistics,
+            "assertion-stack-levels" => InfoFlag::AssertionStackLevels,
+            "authors" => InfoFlag::Authors,
+            "error-behavior" => InfoFlag::ErrorBehavior,
+            "name" => InfoFlag::Name,
+            "reason-unknown" => InfoFlag::ReasonUnknown,
+            "version" => InfoFlag::Version,
+            _ => InfoFlag::Other(value),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for InfoFlag<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Keyword(kw) => Ok(InfoFlag::from(kw)),
+            other => Err(FromSexprError::unexpected(Production::InfoFlag, &other)),
+        }
+    }
+}
+
+impl From<InfoFlag> for Sexpr {
+    fn from(value: InfoFlag) -> Self {
+        value.keyword().into()
+    }
+}
+
+impl<T: Text> Display for InfoFlag<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+/// The expected result of a benchmark, given by `(set-info :status ...)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Status {
+    Sat,
+    Unsat,
+    Unknown,
+}
+
+impl Status {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Status::Sat => "sat",
+            Status::Unsat => "unsat",
+            Status::Unknown => "unknown",
+        }
+    }
+}
+
+/// Reads `sat`, `unsat` or `unknown`.
+pub(crate) fn status<S: Node>(sexpr: S) -> Result<Status, FromSexprError> {
+    match sexpr.as_symbol() {
+        Some("sat") => Ok(Status::Sat),
+        Some("unsat") => Ok(Status::Unsat),
+        Some("unknown") => Ok(Status::Unknown),
+        _ => Err(FromSexprError::unexpected(Production::Status, &sexpr)),
+    }
+}
+
+/// The origin of a benchmark, as given by `:source`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source<T = String> {
+    Symbol(Symbol<T>),
+    String(StringConstant<T>),
+}
+
+impl Source {
+    /// A quoted symbol, or a string literal if the text contains `|` or `\\`, which a quoted
+    /// symbol can not. Like both of them, the text can not contain unprintable characters other
+    /// than whitespace.
+    pub fn new(text: String) -> Result<Self, InvalidStringError> {
+        let string = StringConstant::new(text)?;
+        if string.string_constant().contains(['|', '\\']) {
+            return Ok(Source::String(string));
+        }
+        Ok(Symbol::new(string.string_constant().to_string())
+            .map(Source::Symbol)
+            .unwrap_or(Source::String(string)))
+    }
+}
+
+impl<T: Text> Source<T> {
+    /// The text of the source, whether it is written as a symbol or a string literal.
+    pub fn text(&self) -> &str {
+        match self {
+            Source::Symbol(sym) => sym.symbol(),
+            Source::String(s) => s.string_constant(),
+        }
+    }
+
+    pub fn into_owned(self) -> Source {
+        match self {
+            Source::Symbol(sym) => Source::Symbol(sym.into_owned()),
+            Source::String(s) => Source::String(s.into_owned()),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Source<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Symbol(sym) => Ok(Source::Symbol(sym)),
+            Sexpr::SpecialConstant(SpecialConstant::String(s)) => Ok(Source::String(s)),
+            other => Err(FromSexprError::unexpected(Production::Symbol, &other)),
+        }
+    }
+}
+
+impl From<Source> for Sexpr {
+    fn from(value: Source) -> Self {
+        match value {
+            Source::Symbol(sym) => sym.into(),
+            Source::String(s) => s.into(),
+        }
+    }
+}
+
+/// The attribute of `set-info`. The ones that make up the header of benchmarks in the SMT-LIB
+/// library are read into their own variants, all others are kept as keyword and value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SmtInfo<T = String> {
+    SmtLibVersion(Decimal),
+    /// The origin of the benchmark, usually written as a quoted symbol spanning several lines.
+    Source(Source<T>),
+    License(StringConstant<T>),
+    Category(StringConstant<T>),
+    Status(Status),
+    Other(Keyword<T>, Option<AttributeValue<T>>),
+}
+
+impl<T: Text> SmtInfo<T> {
+    pub fn keyword(&self) -> Keyword {
+        match self {
+            SmtInfo::Other(kw, _) => kw.clone().into_owned(),
+            _ => Keyword::new(self.name().to_string()).expect("info names are valid keywords"),
+        }
+    }
+
+    /// The keyword without the colon.
+    fn name(&self) -> &str {
+        match self {
+            SmtInfo::SmtLibVersion(_) => "smt-lib-version",
+            SmtInfo::Source(_) => "source",
+            SmtInfo::License(_) => "license",
+            SmtInfo::Category(_) => "category",
+            SmtInfo::Status(_) => "status",
+            SmtInfo::Other(kw, _) => kw.keyword(),
+        }
+    }
+
+    pub fn into_owned(self) -> SmtInfo {
+        match self {
+            SmtInfo::SmtLibVersion(version) => SmtInfo::SmtLibVersion(version),
+            SmtInfo::Source(source) => SmtInfo::Source(source.into_owned()),
+            SmtInfo::License(s) => SmtInfo::License(s.into_owned()),
+            SmtInfo::Category(s) => SmtInfo::Category(s.into_owned()),
+            SmtInfo::Status(status) => SmtInfo::Status(status),
+            SmtInfo::Other(kw, value) => {
+                SmtInfo::Other(kw.into_owned(), value.map(AttributeValue::into_owned))
+            }
+        }
+    }
+
+    pub(crate) fn from_elements(mut elems: Elements<Sexpr<T>>) -> Result<Self, FromSexprError> {
+        let kw = elems.next("attribute", Keyword::try_from)?;
+        let info = match kw.keyword() {
+            "smt-lib-version" => SmtInfo::SmtLibVersion(elems.next("decimal", Decimal::try_from)?),
+            "source" => SmtInfo::Source(elems.next("symbol", Source::try_from)?),
+            "license" => SmtInfo::License(elems.next("string", StringConstant::try_from)?),
+            "category" => SmtInfo::Category(elems.next("string", StringConstant::try_from)?),
+            "status" => SmtInfo::Status(elems.next("status", status)?),
+            _ => match elems.peek() {
+                None => SmtInfo::Other(kw, None),
+                Some(_) => SmtInfo::Other(
+                    kw,
+                    Some(elems.next("attribute value", AttributeValue::try_from)?),
+                ),
+            },
+        };
+        elems.finish()?;
+
+        Ok(info)
+    }
+}
+
+impl From<SmtInfo> for Vec<Sexpr> {
+    fn from(value: SmtInfo) -> Self {
+        let kw = value.keyword().into();
+        let value = match value {
+            SmtInfo::SmtLibVersion(version) => Some(version.into()),
+            SmtInfo::Source(source) => Some(source.into()),
+            SmtInfo::License(s) | SmtInfo::Category(s) => Some(s.into()),
+            SmtInfo::Status(status) => Some(
+                Symbol::new(status.name().to_string())
+                    .expect("statuses are valid symbols")
+                    .into(),
+            ),
+            SmtInfo::Other(_, value) => value.map(Sexpr::from),
+        };
+
+        std::iter::once(kw).chain(value).collect()
+    }
+}
+
+/// Prints the keyword and the value, as they follow `set-info`.
+impl<T: Text> Display for SmtInfo<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display_elements(self, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CommandType {
     Assert,
     CheckSat,
@@ -141,6 +1007,8 @@ pub enum CommandType {
     DeclareDatatypes,
     DeclareFun,
     DeclareSort,
+    DeclareSortParameter,
+    DefineConst,
     DefineFun,
     DefineFunRec,
     DefineFunsRec,
@@ -149,7 +1017,7 @@ pub enum CommandType {
     Exit,
     GetAssertions,
     GetAssignment,
-    //GetInfo
+    GetInfo,
     GetModel,
     GetOption,
     GetProof,
@@ -160,9 +1028,88 @@ pub enum CommandType {
     Push,
     Reset,
     ResetAssertions,
-    //SetInfo
+    SetInfo,
     SetLogic,
-    //SetOption /* not implemented */
+    SetOption,
+}
+
+impl CommandType {
+    pub(crate) const ALL: [CommandType; 32] = [
+        CommandType::Assert,
+        CommandType::CheckSat,
+        CommandType::CheckSatAssuming,
+        CommandType::DeclareConst,
+        CommandType::DeclareDatatype,
+        CommandType::DeclareDatatypes,
+        CommandType::DeclareFun,
+        CommandType::DeclareSort,
+        CommandType::DeclareSortParameter,
+        CommandType::DefineConst,
+        CommandType::DefineFun,
+        CommandType::DefineFunRec,
+        CommandType::DefineFunsRec,
+        CommandType::DefineSort,
+        CommandType::Echo,
+        CommandType::Exit,
+        CommandType::GetAssertions,
+        CommandType::GetAssignment,
+        CommandType::GetInfo,
+        CommandType::GetModel,
+        CommandType::GetOption,
+        CommandType::GetProof,
+        CommandType::GetUnsatAssumptions,
+        CommandType::GetUnsatCore,
+        CommandType::GetValue,
+        CommandType::Pop,
+        CommandType::Push,
+        CommandType::Reset,
+        CommandType::ResetAssertions,
+        CommandType::SetInfo,
+        CommandType::SetLogic,
+        CommandType::SetOption,
+    ];
+
+    /// The name of the command, as it appears in scripts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandType::Assert => "assert",
+            CommandType::CheckSat => "check-sat",
+            CommandType::CheckSatAssuming => "check-sat-assuming",
+            CommandType::DeclareConst => "declare-const",
+            CommandType::DeclareDatatype => "declare-datatype",
+            CommandType::DeclareDatatypes => "declare-datatypes",
+            CommandType::DeclareFun => "declare-fun",
+            CommandType::DeclareSort => "declare-sort",
+            CommandType::DeclareSortParameter => "declare-sort-parameter",
+            CommandType::DefineConst => "define-const",
+            CommandType::DefineFun => "define-fun",
+            CommandType::DefineFunRec => "define-fun-rec",
+            CommandType::DefineFunsRec => "define-funs-rec",
+            CommandType::DefineSort => "define-sort",
+            CommandType::Echo => "echo",
+            CommandType::Exit => "exit",
+            CommandType::GetAssertions => "get-assertions",
+            CommandType::GetAssignment => "get-assignment",
+            CommandType::GetInfo => "get-info",
+            CommandType::GetModel => "get-model",
+            CommandType::GetOption => "get-option",
+            CommandType::GetProof => "get-proof",
+            CommandType::GetUnsatAssumptions => "get-unsat-assumptions",
+            CommandType::GetUnsatCore => "get-unsat-core",
+            CommandType::GetValue => "get-value",
+            CommandType::Pop => "pop",
+            CommandType::Push => "push",
+            CommandType::Reset => "reset",
+            CommandType::ResetAssertions => "reset-assertions",
+            CommandType::SetInfo => "set-info",
+            CommandType::SetLogic => "set-logic",
+            CommandType::SetOption => "set-option",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CommandType> {
+        Self::ALL.into_iter().find(|ct| ct.name() == name)
+    }
 }
 
 impl From<CommandType> for Reserved {
@@ -178,44 +1125,63 @@ impl From<CommandType> for Sexpr {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Command {
-    Assert(Term),
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Command<T = String> {
+    Assert(Term<T>),
     CheckSat,
-    CheckSatAssuming(Vec<PropLiteral>),
-    DeclareConst(Symbol, Sort),
-    DeclareDatatype(Symbol, DatatypeDec),
-    DeclareDatatypes(Vec<(SortDec, DatatypeDec)>),
-    DeclareFun(Symbol, Vec<Sort>, Sort),
-    DeclareSort(Symbol, i64),
-    DefineFun(FunctionDef),
-    DefineFunRec(FunctionDef),
-    DefineFunsRec(Vec<(FunctionDec, Term)>),
-    DefineSort(Symbol, Vec<Symbol>, Sort),
-    Echo(StringConstant),
+    CheckSatAssuming(Vec<PropLiteral<T>>),
+    DeclareConst(Symbol<T>, Sort<T>),
+    DeclareDatatype(Symbol<T>, DatatypeDec<T>),
+    DeclareDatatypes(Vec<(SortDec<T>, DatatypeDec<T>)>),
+    DeclareFun(Symbol<T>, Vec<Sort<T>>, Sort<T>),
+    DeclareSort(Symbol<T>, Numeral),
+    /// Since SMT-LIB 2.7.
+    DeclareSortParameter(Symbol<T>),
+    /// Since SMT-LIB 2.7.
+    DefineConst(Symbol<T>, Sort<T>, Term<T>),
+    DefineFun(FunctionDef<T>),
+    DefineFunRec(FunctionDef<T>),
+    DefineFunsRec(Vec<(FunctionDec<T>, Term<T>)>),
+    DefineSort(Symbol<T>, Vec<Symbol<T>>, Sort<T>),
+    Echo(StringConstant<T>),
     Exit,
     GetAssertions,
     GetAssignment,
-    //GetInfo(InfoFlag /* not implementd */),
+    GetInfo(InfoFlag<T>),
     GetModel,
-    GetOption(Keyword),
+    GetOption(Keyword<T>),
     GetProof,
     GetUnsatAssumptions,
     GetUnsatCore,
-    GetValue(Vec<Term>),
-    Pop(i64),
-    Push(i64),
+    GetValue(Vec<Term<T>>),
+    Pop(Numeral),
+    Push(Numeral),
     Reset,
     ResetAssertions,
-    //SetInfo( /* not implemented */)
-    SetLogic(Symbol),
-    //SetOption(Option) /* not implemented */
+    SetInfo(SmtInfo<T>),
+    SetLogic(Symbol<T>),
+    SetOption(SmtOption<T>),
 }
 
 fn to_seq<S: Into<Sexpr>>(items: Vec<S>) -> Sexpr {
     Sexpr::Sequence(items.into_iter().map(|prop| prop.into()).collect())
 }
 
+/// Pairs up the declarations and definitions of `declare-datatypes` and `define-funs-rec`.
+pub(crate) fn zip_exact<A, B>(left: Vec<A>, right: Vec<B>) -> Result<Vec<(A, B)>, FromSexprError> {
+    if left.len() != right.len() {
+        return Err(FromSexprError::new(
+            Production::Command,
+            FromSexprErrorKind::LengthMismatch {
+                left: left.len(),
+                right: right.len(),
+            },
+        ));
+    }
+
+    Ok(left.into_iter().zip(right).collect())
+}
+
 impl From<Command> for Sexpr {
     fn from(value: Command) -> Self {
         let command_type = value.command_type();
@@ -264,6 +1230,10 @@ impl From<Command> for Sexpr {
             Command::DeclareSort(name, num) => {
                 vec![command_type.into(), name.into(), num.into()]
             }
+            Command::DeclareSortParameter(name) => vec![command_type.into(), name.into()],
+            Command::DefineConst(name, sort, term) => {
+                vec![command_type.into(), name.into(), sort.into(), term.into()]
+            }
             Command::DefineFun(fun_def) | Command::DefineFunRec(fun_def) => {
                 let fun_def_sexprs: Vec<Sexpr> = fun_def.into();
                 vec![command_type.into()]
@@ -291,18 +1261,101 @@ impl From<Command> for Sexpr {
                 vec![command_type.into(), name.into(), to_seq(syms), sort.into()]
             }
             Command::Echo(string) => vec![command_type.into(), string.into()],
+            Command::GetInfo(flag) => vec![command_type.into(), flag.into()],
             Command::GetOption(kw) => vec![command_type.into(), kw.into()],
             Command::GetValue(terms) => vec![command_type.into(), to_seq(terms)],
             Command::Pop(num) | Command::Push(num) => vec![command_type.into(), num.into()],
             Command::SetLogic(sym) => vec![command_type.into(), sym.into()],
+            Command::SetInfo(info) => {
+                let info_sexprs: Vec<Sexpr> = info.into();
+                std::iter::once(command_type.into())
+                    .chain(info_sexprs)
+                    .collect()
+            }
+            Command::SetOption(option) => {
+                let option_sexprs: Vec<Sexpr> = option.into();
+                std::iter::once(command_type.into())
+                    .chain(option_sexprs)
+                    .collect()
+            }
         };
 
         Sexpr::Sequence(inner)
     }
 }
 
-impl Command {
-    fn command_type(&self) -> CommandType {
+/// Prints the command as its s-expression would be printed, without building it.
+impl<T: Text> Display for Command<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+impl<T: Text> Command<T> {
+    pub fn into_owned(self) -> Command {
+        match self {
+            Command::Assert(term) => Command::Assert(term.into_owned()),
+            Command::CheckSat => Command::CheckSat,
+            Command::CheckSatAssuming(literals) => {
+                Command::CheckSatAssuming(sexprs::into_owned(literals, PropLiteral::into_owned))
+            }
+            Command::DeclareConst(name, sort) => {
+                Command::DeclareConst(name.into_owned(), sort.into_owned())
+            }
+            Command::DeclareDatatype(name, dec) => {
+                Command::DeclareDatatype(name.into_owned(), dec.into_owned())
+            }
+            Command::DeclareDatatypes(decs) => {
+                Command::DeclareDatatypes(sexprs::into_owned(decs, |(sort_dec, dec)| {
+                    (sort_dec.into_owned(), dec.into_owned())
+                }))
+            }
+            Command::DeclareFun(name, args, sort) => Command::DeclareFun(
+                name.into_owned(),
+                sexprs::into_owned(args, Sort::into_owned),
+                sort.into_owned(),
+            ),
+            Command::DeclareSort(name, arity) => Command::DeclareSort(name.into_owned(), arity),
+            Command::DeclareSortParameter(name) => Command::DeclareSortParameter(name.into_owned()),
+            Command::DefineConst(name, sort, term) => {
+                Command::DefineConst(name.into_owned(), sort.into_owned(), term.into_owned())
+            }
+            Command::DefineFun(def) => Command::DefineFun(def.into_owned()),
+            Command::DefineFunRec(def) => Command::DefineFunRec(def.into_owned()),
+            Command::DefineFunsRec(defs) => {
+                Command::DefineFunsRec(sexprs::into_owned(defs, |(dec, body)| {
+                    (dec.into_owned(), body.into_owned())
+                }))
+            }
+            Command::DefineSort(name, params, sort) => Command::DefineSort(
+                name.into_owned(),
+                sexprs::into_owned(params, Symbol::into_owned),
+                sort.into_owned(),
+            ),
+            Command::Echo(s) => Command::Echo(s.into_owned()),
+            Command::Exit => Command::Exit,
+            Command::GetAssertions => Command::GetAssertions,
+            Command::GetAssignment => Command::GetAssignment,
+            Command::GetInfo(flag) => Command::GetInfo(flag.into_owned()),
+            Command::GetModel => Command::GetModel,
+            Command::GetOption(kw) => Command::GetOption(kw.into_owned()),
+            Command::GetProof => Command::GetProof,
+            Command::GetUnsatAssumptions => Command::GetUnsatAssumptions,
+            Command::GetUnsatCore => Command::GetUnsatCore,
+            Command::GetValue(terms) => {
+                Command::GetValue(sexprs::into_owned(terms, Term::into_owned))
+            }
+            Command::Pop(n) => Command::Pop(n),
+            Command::Push(n) => Command::Push(n),
+            Command::Reset => Command::Reset,
+            Command::ResetAssertions => Command::ResetAssertions,
+            Command::SetInfo(info) => Command::SetInfo(info.into_owned()),
+            Command::SetLogic(logic) => Command::SetLogic(logic.into_owned()),
+            Command::SetOption(option) => Command::SetOption(option.into_owned()),
+        }
+    }
+
+    pub(crate) fn command_type(&self) -> CommandType {
         match self {
             Command::Assert(_) => CommandType::Assert,
             Command::CheckSat => CommandType::CheckSat,
@@ -312,6 +1365,8 @@ impl Command {
             Command::DeclareDatatypes(_) => CommandType::DeclareDatatypes,
             Command::DeclareFun(_, _, _) => CommandType::DeclareFun,
             Command::DeclareSort(_, _) => CommandType::DeclareSort,
+            Command::DeclareSortParameter(_) => CommandType::DeclareSortParameter,
+            Command::DefineConst(_, _, _) => CommandType::DefineConst,
             Command::DefineFun(_) => CommandType::DefineFun,
             Command::DefineFunRec(_) => CommandType::DefineFunRec,
             Command::DefineFunsRec(_) => CommandType::DefineFunsRec,
@@ -320,6 +1375,7 @@ impl Command {
             Command::Exit => CommandType::Exit,
             Command::GetAssertions => CommandType::GetAssertions,
             Command::GetAssignment => CommandType::GetAssignment,
+            Command::GetInfo(_) => CommandType::GetInfo,
             Command::GetModel => CommandType::GetModel,
             Command::GetOption(_) => CommandType::GetOption,
             Command::GetProof => CommandType::GetProof,
@@ -330,9 +1386,328 @@ impl Command {
             Command::Push(_) => CommandType::Push,
             Command::Reset => CommandType::Reset,
             Command::ResetAssertions => CommandType::ResetAssertions,
+            Command::SetInfo(_) => CommandType::SetInfo,
             Command::SetLogic(_) => CommandType::SetLogic,
+            Command::SetOption(_) => CommandType::SetOption,
         }
     }
 }
 
-pub struct Script(pub Vec<Command>);
+impl Command {
+    /// Writes the command as [`Display`] prints it, e.g. straight to a file. The output is
+    /// buffered, so the writer does not need to be.
+    pub fn write_to(&self, writer: impl std::io::Write) -> std::io::Result<()> {
+        writer::write_io(self, writer)
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Command<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::Command, sexpr)?;
+        let command_type = elems.next("command name", |sexpr| match sexpr {
+            Sexpr::Reserved(Reserved::Command(command_type)) => Ok(command_type),
+            Sexpr::Symbol(sym) => CommandType::from_name(sym.symbol()).ok_or_else(|| {
+                FromSexprError::new(
+                    Production::Command,
+                    FromSexprErrorKind::UnknownCommand(sym.symbol().to_string()),
+                )
+            }),
+            other => Err(FromSexprError::unexpected(Production::Command, &other)),
+        })?;
+
+        let command = match command_type {
+            CommandType::Assert => Command::Assert(elems.next("term", Term::try_from)?),
+            CommandType::CheckSat => Command::CheckSat,
+            CommandType::CheckSatAssuming => Command::CheckSatAssuming(
+                elems.next_list("propositional literal", PropLiteral::try_from)?,
+            ),
+            CommandType::DeclareConst => Command::DeclareConst(
+                elems.next("symbol", Symbol::try_from)?,
+                elems.next("sort", Sort::try_from)?,
+            ),
+            CommandType::DeclareDatatype => Command::DeclareDatatype(
+                elems.next("symbol", Symbol::try_from)?,
+                elems.next("datatype declaration", DatatypeDec::try_from)?,
+            ),
+            CommandType::DeclareDatatypes => {
+                let sort_decs = elems.next_nonempty_list("sort declaration", SortDec::try_from)?;
+                let datatype_decs =
+                    elems.next_nonempty_list("datatype declaration", DatatypeDec::try_from)?;
+                let entries = zip_exact(sort_decs, datatype_decs)?;
+                for (i, (SortDec(_, arity), datatype_dec)) in entries.iter().enumerate() {
+                    check_arity(i, arity, datatype_dec.par.len())?;
+                }
+                Command::DeclareDatatypes(entries)
+            }
+            CommandType::DeclareFun => Command::DeclareFun(
+                elems.next("symbol", Symbol::try_from)?,
+                elems.next_list("sort", Sort::try_from)?,
+                elems.next("sort", Sort::try_from)?,
+            ),
+            CommandType::DeclareSort => Command::DeclareSort(
+                elems.next("symbol", Symbol::try_from)?,
+                elems.next("numeral", Numeral::try_from)?,
+            ),
+            CommandType::DeclareSortParameter => {
+                Command::DeclareSortParameter(elems.next("symbol", Symbol::try_from)?)
+            }
+            CommandType::DefineConst => Command::DefineConst(
+                elems.next("symbol", Symbol::try_from)?,
+                elems.next("sort", Sort::try_from)?,
+                elems.next("term", Term::try_from)?,
+            ),
+            CommandType::DefineFun => {
+                return FunctionDef::from_elements(elems).map(Command::DefineFun);
+            }
+            CommandType::DefineFunRec => {
+                return FunctionDef::from_elements(elems).map(Command::DefineFunRec);
+            }
+            CommandType::DefineFunsRec => {
+                let fun_decs =
+                    elems.next_nonempty_list("function declaration", FunctionDec::try_from)?;
+                let bodies = elems.next_nonempty_list("term", Term::try_from)?;
+                Command::DefineFunsRec(zip_exact(fun_decs, bodies)?)
+            }
+            CommandType::DefineSort => Command::DefineSort(
+                elems.next("symbol", Symbol::try_from)?,
+                elems.next_list("symbol", Symbol::try_from)?,
+                elems.next("sort", Sort::try_from)?,
+            ),
+            CommandType::Echo => Command::Echo(elems.next("string", StringConstant::try_from)?),
+            CommandType::Exit => Command::Exit,
+            CommandType::GetAssertions => Command::GetAssertions,
+            CommandType::GetAssignment => Command::GetAssignment,
+            CommandType::GetInfo => Command::GetInfo(elems.next("info flag", InfoFlag::try_from)?),
+            CommandType::GetModel => Command::GetModel,
+            CommandType::GetOption => Command::GetOption(elems.next("keyword", Keyword::try_from)?),
+            CommandType::GetProof => Command::GetProof,
+            CommandType::GetUnsatAssumptions => Command::GetUnsatAssumptions,
+            CommandType::GetUnsatCore => Command::GetUnsatCore,
+            CommandType::GetValue => {
+                Command::GetValue(elems.next_nonempty_list("term", Term::try_from)?)
+            }
+            CommandType::Pop => Command::Pop(elems.next("numeral", Numeral::try_from)?),
+            CommandType::Push => Command::Push(elems.next("numeral", Numeral::try_from)?),
+            CommandType::Reset => Command::Reset,
+            CommandType::ResetAssertions => Command::ResetAssertions,
+            CommandType::SetInfo => {
+                return SmtInfo::from_elements(elems).map(Command::SetInfo);
+            }
+            CommandType::SetLogic => Command::SetLogic(elems.next("symbol", Symbol::try_from)?),
+            CommandType::SetOption => {
+                return SmtOption::from_elements(elems).map(Command::SetOption);
+            }
+        };
+
+        elems.finish()?;
+        Ok(command)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Script<T = String>(pub Vec<Command<T>>);
+
+impl<T: Text> Script<T> {
+    pub fn into_owned(self) -> Script {
+        Script(sexprs::into_owned(self.0, Command::into_owned))
+    }
+}
+
+/// Prints each command on a line of its own.
+impl<T: Text> Display for Script<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::write_script(FmtWriter::new(f), self).map(drop)
+    }
+}
+
+impl Script {
+    /// Writes the script as [`Display`] prints it, one command after the other, so that large
+    /// scripts can be written to a file without holding their text in memory. The output is
+    /// buffered, so the writer does not need to be.
+    pub fn write_to(&self, writer: impl std::io::Write) -> std::io::Result<()> {
+        use std::io::Write;
+
+        writer::write_script(IoWriter::new(std::io::BufWriter::new(writer)), self)?
+            .finish()
+            .flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identifiers::{Identifier, Index};
+    use crate::parser::{parse_command, parse_script, parse_sort};
+    use crate::terms::QualifiedIdentifier;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol::new_str(name).unwrap()
+    }
+
+    #[test]
+    fn build_commands() {
+        let int = Sort::simple("Int").unwrap();
+        let bv32 = Sort::new(Identifier::indexed(
+            sym("BitVec"),
+            vec![Index::Numeral(32u8.into())],
+        ));
+        let array = Sort::app(sym("Array").into(), vec![int.clone(), bv32.clone()]);
+        let plus = QualifiedIdentifier::new(sym("+").into());
+        let var = |name| Term::QualifiedIdentifier(QualifiedIdentifier::new(sym(name).into()));
+
+        let script = Script(vec![
+            Command::DeclareConst(sym("a"), array),
+            Command::DefineFun(FunctionDef::new(
+                sym("inc"),
+                vec![SortedVar::new(sym("n"), int.clone())],
+                int.clone(),
+                Term::Application(plus, vec![var("n"), Term::from(1)]),
+            )),
+            Command::DeclareDatatype(
+                sym("Box"),
+                DatatypeDec::parametric(
+                    vec![sym("T")],
+                    vec![ConstructorDec {
+                        name: sym("box"),
+                        selectors: vec![SelectorDec {
+                            name: sym("unbox"),
+                            sort: Sort::simple("T").unwrap(),
+                        }],
+                    }],
+                )
+                .unwrap(),
+            ),
+            Command::CheckSatAssuming(vec![
+                PropLiteral::new(sym("p")),
+                PropLiteral::negated(sym("q")),
+            ]),
+        ]);
+
+        let printed: Vec<_> = script
+            .0
+            .into_iter()
+            .map(|command| Sexpr::from(command).to_string())
+            .collect();
+        assert_eq!(
+            printed,
+            [
+                "(declare-const a (Array Int (_ BitVec 32)))",
+                "(define-fun inc ((n Int)) Int (+ n 1))",
+                "(declare-datatype Box (par (T) ((box (unbox T)))))",
+                "(check-sat-assuming (p (not q)))",
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_errors() {
+        let errors: Vec<Box<dyn std::error::Error>> = vec![
+            Box::new(Sort::simple("a|b").unwrap_err()),
+            Box::new(Keyword::new_str("").unwrap_err()),
+            Box::new(DatatypeDec::new(vec![]).unwrap_err()),
+            Box::new(DatatypeDec::parametric(vec![], vec![]).unwrap_err()),
+        ];
+        let messages: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                r#""a|b" is not a valid symbol"#,
+                r#""" is not a valid keyword"#,
+                "a datatype declaration needs at least one constructor",
+                "a datatype declaration needs at least one sort parameter",
+            ]
+        );
+    }
+
+    #[test]
+    fn typed_options() {
+        let command = parse_command("(set-option :produce-unsat-cores true)").unwrap();
+        assert!(matches!(
+            command,
+            Command::SetOption(SmtOption::ProduceUnsatCores(true))
+        ));
+
+        let command = parse_command("(set-option :print-success false)").unwrap();
+        let Command::SetOption(option) = command else {
+            panic!("expected set-option")
+        };
+        assert_eq!(option.keyword().to_string(), ":print-success");
+
+        let err = parse_command("(set-option :produce-models 1)").unwrap_err();
+        assert_eq!(err.to_string(), "1:29: expected boolean, found numeral `1`");
+    }
+
+    #[test]
+    fn parametric_datatypes() {
+        let command = parse_command(
+            "(declare-datatype List (par (T) ((nil) (cons (head T) (tail (List T))))))",
+        )
+        .unwrap();
+        let Command::DeclareDatatype(name, dec) = command else {
+            panic!("expected declare-datatype")
+        };
+        let int = parse_sort("Int").unwrap();
+        let sort = dec.sort(name, vec![int.clone()]).unwrap();
+        assert_eq!(sort.to_string(), "(List Int)");
+
+        let constructors = dec.instantiate(&[int]).unwrap();
+        let cons = Sexpr::from(constructors[1].clone());
+        assert_eq!(cons.to_string(), "(cons (head Int) (tail (List Int)))");
+        assert!(dec.instantiate(&[]).is_err());
+
+        let input = "(declare-datatypes ((List 1) (Nat 1)) ((par (T) ((nil))) ((zero))))";
+        let err = parse_command(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:58: datatype declaration has 0 parameters, but the sort is declared with arity 1"
+        );
+        let err = crate::borrowed::parse_command(input).unwrap_err();
+        assert_eq!(err.span.start.column, 58);
+    }
+
+    #[test]
+    fn benchmark_headers() {
+        let script = parse_script(
+            "(set-info :status sat) (set-info :source \"a|b\") (get-info :all-statistics)",
+        )
+        .unwrap();
+        assert!(matches!(
+            script.0[0],
+            Command::SetInfo(SmtInfo::Status(Status::Sat))
+        ));
+        assert!(matches!(&script.0[1], Command::SetInfo(SmtInfo::Source(s)) if s.text() == "a|b"));
+        assert!(matches!(
+            script.0[2],
+            Command::GetInfo(InfoFlag::AllStatistics)
+        ));
+
+        assert_eq!(
+            Sexpr::from(script.0[1].clone()).to_string(),
+            r#"(set-info :source "a|b")"#
+        );
+
+        // a source that is not a valid quoted symbol is written as a string literal
+        let source = |text: &str| {
+            let info = SmtInfo::Source(Source::new(text.to_string()).unwrap());
+            let command = Command::SetInfo(info);
+            assert_eq!(
+                Sexpr::from(command.clone()).to_string(),
+                command.to_string()
+            );
+            command.to_string()
+        };
+        assert_eq!(source("a|b"), r#"(set-info :source "a|b")"#);
+        assert_eq!(source("a\\b"), r#"(set-info :source "a\b")"#);
+        assert_eq!(source("a\nb"), "(set-info :source |a\nb|)");
+        assert_eq!(source("ab"), "(set-info :source ab)");
+        assert!(Source::new("a\u{7}b".to_string()).is_err());
+
+        let err = parse_command("(set-info :status valid)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:19: expected status, found symbol `valid`"
+        );
+    }
+}
@@ -1,23 +1,29 @@
 use std::fmt::Display;
 
-use super::lexicon::{Keyword, Reserved, StringConstant, Symbol};
+use super::lexicon::{
+    Binary, Decimal, Hexadecimal, Keyword, Numeral, Reserved, StringConstant, Symbol, Text,
+};
+use super::version::VersionError;
+use super::writer;
 
-#[derive(Clone, Debug)]
-pub enum SpecialConstant {
-    // technically the spec only supports unsigned, but there may be solvers that
-    // support signed as well, so let's just make it signed.
-    Numeral(i64),
-    String(StringConstant),
-    /* the following items are in the spec, but not implemented yet:
-    Hexadecimal(_),
-    Binary(_),
-    Decimal(_),
-    */
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SpecialConstant<T = String> {
+    Numeral(Numeral),
+    Decimal(Decimal),
+    Hexadecimal(Hexadecimal),
+    Binary(Binary),
+    String(StringConstant<T>),
 }
 
-impl From<i64> for SpecialConstant {
-    fn from(value: i64) -> Self {
-        Self::Numeral(value)
+impl<T: Text> SpecialConstant<T> {
+    pub fn into_owned(self) -> SpecialConstant {
+        match self {
+            SpecialConstant::Numeral(num) => SpecialConstant::Numeral(num),
+            SpecialConstant::Decimal(dec) => SpecialConstant::Decimal(dec),
+            SpecialConstant::Hexadecimal(hex) => SpecialConstant::Hexadecimal(hex),
+            SpecialConstant::Binary(bin) => SpecialConstant::Binary(bin),
+            SpecialConstant::String(s) => SpecialConstant::String(s.into_owned()),
+        }
     }
 }
 
@@ -27,43 +33,440 @@ impl From<SpecialConstant> for Sexpr {
     }
 }
 
-impl Display for SpecialConstant {
+impl<T: Text> Display for SpecialConstant<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SpecialConstant::Numeral(num) => num.fmt(f),
+            SpecialConstant::Decimal(dec) => dec.fmt(f),
+            SpecialConstant::Hexadecimal(hex) => hex.fmt(f),
+            SpecialConstant::Binary(bin) => bin.fmt(f),
             SpecialConstant::String(s) => s.fmt(f),
         }
     }
 }
 
-pub enum Sexpr {
-    SpecialConstant(SpecialConstant),
-    Symbol(Symbol),
-    Keyword(Keyword),
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Sexpr<T = String> {
+    SpecialConstant(SpecialConstant<T>),
+    Symbol(Symbol<T>),
+    Keyword(Keyword<T>),
     Reserved(Reserved),
-    Sequence(Vec<Sexpr>),
+    Sequence(Vec<Sexpr<T>>),
 }
 
-impl Display for Sexpr {
+impl<T: Text> Display for Sexpr<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+impl<T: Text> Sexpr<T> {
+    pub fn into_owned(self) -> Sexpr {
         match self {
-            Sexpr::SpecialConstant(sc) => sc.fmt(f),
-            Sexpr::Symbol(sym) => sym.fmt(f),
-            Sexpr::Keyword(kw) => kw.fmt(f),
-            Sexpr::Reserved(r) => r.fmt(f),
-            Sexpr::Sequence(seq) => {
-                write!(f, "(")?;
-
-                for (i, e) in seq.iter().enumerate() {
-                    if i == 0 {
-                        write!(f, "{e}")?;
-                    } else {
-                        write!(f, " {e}")?;
-                    }
-                }
-
-                write!(f, ")")
+            Sexpr::SpecialConstant(sc) => Sexpr::SpecialConstant(sc.into_owned()),
+            Sexpr::Symbol(sym) => Sexpr::Symbol(sym.into_owned()),
+            Sexpr::Keyword(kw) => Sexpr::Keyword(kw.into_owned()),
+            Sexpr::Reserved(r) => Sexpr::Reserved(r),
+            Sexpr::Sequence(seq) => Sexpr::Sequence(into_owned(seq, Sexpr::into_owned)),
+        }
+    }
+
+    /// A short description of the s-expression, for error messages.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Sexpr::SpecialConstant(SpecialConstant::Numeral(num)) => format!("numeral `{num}`"),
+            Sexpr::SpecialConstant(SpecialConstant::Decimal(dec)) => format!("decimal `{dec}`"),
+            Sexpr::SpecialConstant(SpecialConstant::Hexadecimal(hex)) => {
+                format!("hexadecimal `{hex}`")
             }
+            Sexpr::SpecialConstant(SpecialConstant::Binary(bin)) => format!("binary `{bin}`"),
+            Sexpr::SpecialConstant(SpecialConstant::String(_)) => "string literal".to_string(),
+            Sexpr::Symbol(sym) => format!("symbol `{sym}`"),
+            Sexpr::Keyword(kw) => format!("keyword `{kw}`"),
+            Sexpr::Reserved(r) => format!("reserved word `{r}`"),
+            Sexpr::Sequence(seq) if seq.is_empty() => "`()`".to_string(),
+            Sexpr::Sequence(seq) => format!("list of {} elements", seq.len()),
+        }
+    }
+}
+
+/// The grammar productions of the spec that s-expressions are converted into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Production {
+    Numeral,
+    Decimal,
+    Hexadecimal,
+    Binary,
+    StringConstant,
+    Symbol,
+    Keyword,
+    Index,
+    Identifier,
+    AttributeValue,
+    Attribute,
+    Sort,
+    QualifiedIdentifier,
+    VarBinding,
+    SortedVar,
+    Pattern,
+    MatchCase,
+    Term,
+    SortDec,
+    SelectorDec,
+    ConstructorDec,
+    DatatypeDec,
+    FunctionDec,
+    FunctionDef,
+    PropLiteral,
+    BValue,
+    InfoFlag,
+    Status,
+    Command,
+    Response,
+    CheckSatResponse,
+    ModelResponse,
+    ValuationPair,
+    InfoResponse,
+}
+
+impl Display for Production {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Production::Numeral => "numeral",
+            Production::Decimal => "decimal",
+            Production::Hexadecimal => "hexadecimal",
+            Production::Binary => "binary",
+            Production::StringConstant => "string literal",
+            Production::Symbol => "symbol",
+            Production::Keyword => "keyword",
+            Production::Index => "index",
+            Production::Identifier => "identifier",
+            Production::AttributeValue => "attribute value",
+            Production::Attribute => "attribute",
+            Production::Sort => "sort",
+            Production::QualifiedIdentifier => "qualified identifier",
+            Production::VarBinding => "variable binding",
+            Production::SortedVar => "sorted variable",
+            Production::Pattern => "pattern",
+            Production::MatchCase => "match case",
+            Production::Term => "term",
+            Production::SortDec => "sort declaration",
+            Production::SelectorDec => "selector declaration",
+            Production::ConstructorDec => "constructor declaration",
+            Production::DatatypeDec => "datatype declaration",
+            Production::FunctionDec => "function declaration",
+            Production::FunctionDef => "function definition",
+            Production::PropLiteral => "propositional literal",
+            Production::BValue => "boolean",
+            Production::InfoFlag => "info flag",
+            Production::Status => "status",
+            Production::Command => "command",
+            Production::Response => "response",
+            Production::CheckSatResponse => "check-sat response",
+            Production::ModelResponse => "model response",
+            Production::ValuationPair => "valuation pair",
+            Production::InfoResponse => "info response",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FromSexprErrorKind {
+    /// The s-expression does not have the shape of the production.
+    Unexpected {
+        found: String,
+    },
+    /// A list ended before all elements of the production were read.
+    Missing {
+        expected: &'static str,
+    },
+    /// A list has more elements than the production allows.
+    TrailingElement {
+        found: String,
+    },
+    /// The lists of a `declare-datatypes` or `define-funs-rec` differ in length.
+    LengthMismatch {
+        left: usize,
+        right: usize,
+    },
+    /// The arity of a sort declaration differs from the number of parameters of its datatype.
+    ArityMismatch {
+        arity: Numeral,
+        parameters: usize,
+    },
+    UnknownCommand(String),
+    /// A symbol that is bound twice by the same pattern.
+    DuplicateBinder(String),
+    /// A construct that the version of SMT-LIB being read does not have.
+    Version(VersionError),
+}
+
+/// Describes why an s-expression could not be converted into a syntax type, by one of the
+/// `TryFrom<Sexpr>` impls. It names the production that failed, and the path to the offending
+/// s-expression within the one that was converted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FromSexprError {
+    production: Production,
+    kind: FromSexprErrorKind,
+    path: Vec<usize>,
+}
+
+impl FromSexprError {
+    pub(crate) fn new(production: Production, kind: FromSexprErrorKind) -> Self {
+        FromSexprError {
+            production,
+            kind,
+            path: vec![],
         }
     }
+
+    pub(crate) fn unexpected(production: Production, found: &impl Node) -> Self {
+        Self::new(
+            production,
+            FromSexprErrorKind::Unexpected {
+                found: found.describe(),
+            },
+        )
+    }
+
+    /// Marks the error as having occurred in the element with the given index.
+    pub(crate) fn at(mut self, index: usize) -> Self {
+        self.path.insert(0, index);
+        self
+    }
+
+    /// The production that could not be read.
+    pub fn production(&self) -> Production {
+        self.production
+    }
+
+    pub fn kind(&self) -> &FromSexprErrorKind {
+        &self.kind
+    }
+
+    /// The location of the offending s-expression: the indexes of the list elements to follow,
+    /// starting from the root.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+}
+
+impl Display for FromSexprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let production = self.production;
+        match &self.kind {
+            FromSexprErrorKind::Unexpected { found } => {
+                write!(f, "expected {production}, found {found}")
+            }
+            FromSexprErrorKind::Missing { expected } => {
+                write!(f, "incomplete {production}: missing {expected}")
+            }
+            FromSexprErrorKind::TrailingElement { found } => {
+                write!(f, "unexpected {found} at the end of {production}")
+            }
+            FromSexprErrorKind::LengthMismatch { left, right } => write!(
+                f,
+                "{production} has {left} declarations, but {right} definitions"
+            ),
+            FromSexprErrorKind::ArityMismatch { arity, parameters } => write!(
+                f,
+                "{production} has {parameters} parameters, but the sort is declared with arity {arity}"
+            ),
+            FromSexprErrorKind::UnknownCommand(name) => write!(f, "unknown command `{name}`"),
+            FromSexprErrorKind::DuplicateBinder(name) => {
+                write!(f, "`{name}` is bound more than once in {production}")
+            }
+            FromSexprErrorKind::Version(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FromSexprError {}
+
+/// Converts the items of a list into their owned counterparts, for the `into_owned` methods of
+/// the syntax types.
+pub(crate) fn into_owned<T, U>(items: Vec<T>, f: impl FnMut(T) -> U) -> Vec<U> {
+    items.into_iter().map(f).collect()
+}
+
+/// The s-expression trees that can be converted into the syntax types: [`Sexpr`], whether it
+/// owns its text or borrows it like [`borrowed::Sexpr`](crate::borrowed::Sexpr).
+pub(crate) trait Node: Sized {
+    /// A short description of the s-expression, for error messages.
+    fn describe(&self) -> String;
+
+    /// Returns the elements of a list, or the s-expression itself if it is not a list.
+    fn into_list(self) -> Result<Vec<Self>, Self>;
+
+    /// The name of a symbol.
+    fn as_symbol(&self) -> Option<&str>;
+
+    fn as_reserved(&self) -> Option<&Reserved>;
+
+    fn as_list(&self) -> Option<&[Self]>;
+}
+
+impl<T: Text> Node for Sexpr<T> {
+    fn describe(&self) -> String {
+        Sexpr::describe(self)
+    }
+
+    fn into_list(self) -> Result<Vec<Self>, Self> {
+        match self {
+            Sexpr::Sequence(seq) => Ok(seq),
+            other => Err(other),
+        }
+    }
+
+    fn as_symbol(&self) -> Option<&str> {
+        match self {
+            Sexpr::Symbol(sym) => Some(sym.symbol()),
+            _ => None,
+        }
+    }
+
+    fn as_reserved(&self) -> Option<&Reserved> {
+        match self {
+            Sexpr::Reserved(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> Option<&[Self]> {
+        match self {
+            Sexpr::Sequence(seq) => Some(seq),
+            _ => None,
+        }
+    }
+}
+
+/// Walks the elements of a list while converting it into a production, keeping track of the
+/// index for error reporting.
+pub(crate) struct Elements<S = Sexpr> {
+    production: Production,
+    iter: std::iter::Peekable<std::iter::Enumerate<std::vec::IntoIter<S>>>,
+}
+
+impl<S: Node> Elements<S> {
+    /// Expects `sexpr` to be a list and returns its elements.
+    pub(crate) fn of(production: Production, sexpr: S) -> Result<Self, FromSexprError> {
+        match sexpr.into_list() {
+            Ok(seq) => Ok(Self::new(production, seq)),
+            Err(other) => Err(FromSexprError::unexpected(production, &other)),
+        }
+    }
+
+    pub(crate) fn new(production: Production, seq: Vec<S>) -> Self {
+        Elements {
+            production,
+            iter: seq.into_iter().enumerate().peekable(),
+        }
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<&S> {
+        self.iter.peek().map(|(_, sexpr)| sexpr)
+    }
+
+    /// Converts the next element, or fails with a "missing `expected`" error.
+    pub(crate) fn next<T>(
+        &mut self,
+        expected: &'static str,
+        convert: impl FnOnce(S) -> Result<T, FromSexprError>,
+    ) -> Result<T, FromSexprError> {
+        match self.iter.next() {
+            Some((i, sexpr)) => convert(sexpr).map_err(|err| err.at(i)),
+            None => Err(FromSexprError::new(
+                self.production,
+                FromSexprErrorKind::Missing { expected },
+            )),
+        }
+    }
+
+    /// Converts the next element, which has to be a list, element by element.
+    pub(crate) fn next_list<T>(
+        &mut self,
+        expected: &'static str,
+        convert: impl FnMut(S) -> Result<T, FromSexprError>,
+    ) -> Result<Vec<T>, FromSexprError> {
+        let production = self.production;
+        self.next(expected, |sexpr| {
+            Elements::of(production, sexpr)?.rest(convert)
+        })
+    }
+
+    /// Like `next_list`, but the list must not be empty.
+    pub(crate) fn next_nonempty_list<T>(
+        &mut self,
+        expected: &'static str,
+        convert: impl FnMut(S) -> Result<T, FromSexprError>,
+    ) -> Result<Vec<T>, FromSexprError> {
+        let production = self.production;
+        self.next(expected, |sexpr| {
+            Elements::of(production, sexpr)?.rest_nonempty(expected, convert)
+        })
+    }
+
+    /// Converts all remaining elements.
+    pub(crate) fn rest<T>(
+        self,
+        mut convert: impl FnMut(S) -> Result<T, FromSexprError>,
+    ) -> Result<Vec<T>, FromSexprError> {
+        self.iter
+            .map(|(i, sexpr)| convert(sexpr).map_err(|err| err.at(i)))
+            .collect()
+    }
+
+    /// Like `rest`, but requires at least one element.
+    pub(crate) fn rest_nonempty<T>(
+        mut self,
+        expected: &'static str,
+        mut convert: impl FnMut(S) -> Result<T, FromSexprError>,
+    ) -> Result<Vec<T>, FromSexprError> {
+        let first = self.next(expected, &mut convert)?;
+        let mut items = vec![first];
+        items.extend(self.rest(convert)?);
+        Ok(items)
+    }
+
+    /// Fails if there are elements left.
+    pub(crate) fn finish(mut self) -> Result<(), FromSexprError> {
+        match self.iter.next() {
+            None => Ok(()),
+            Some((i, sexpr)) => Err(FromSexprError::new(
+                self.production,
+                FromSexprErrorKind::TrailingElement {
+                    found: sexpr.describe(),
+                },
+            )
+            .at(i)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sexpr;
+    use crate::scripts::Command;
+    use crate::terms::Term;
+
+    #[test]
+    fn try_from_reports_path() {
+        let sexpr = parse_sexpr("(assert (forall ((x Int) (y 5)) (p x y)))").unwrap();
+        let err = Command::try_from(sexpr).unwrap_err();
+        assert_eq!(err.production(), Production::Sort);
+        assert_eq!(err.path(), &[1, 1, 1, 1]);
+        assert_eq!(err.to_string(), "expected sort, found numeral `5`");
+
+        let sexpr = parse_sexpr("(let ((a 1)) a b)").unwrap();
+        let err = Term::try_from(sexpr).unwrap_err();
+        assert_eq!(err.production(), Production::Term);
+        assert_eq!(err.path(), &[3]);
+        assert!(matches!(
+            err.kind(),
+            FromSexprErrorKind::TrailingElement { .. }
+        ));
+    }
 }
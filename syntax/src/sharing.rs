@@ -0,0 +1,664 @@
+//! Printing terms that repeat subterms without writing the subterms out more than once.
+//!
+//! Generated terms often contain the same subterm many times over, e.g. when every step of an
+//! unrolled loop refers to the previous one twice, and printing such a term as a tree makes the
+//! text grow exponentially. [`Sharing`] finds the subterms that occur more than once by
+//! comparing them structurally, binds each of them to a fresh variable with `let` and refers to
+//! the variable instead. At script level, the shared subterms can become nullary `define-fun`s
+//! instead, see [`Sharing::script`].
+//!
+//! Subterms that mention variables bound by a `let`, `forall`, `exists`, `lambda` or `match`
+//! are only shared within the body of that binder, where the `let` is put, so no variable ends up
+//! outside of its scope. Terms annotated with `:named` are left as they are, as they must stay
+//! closed, and the terms of `:pattern` and `:no-pattern` annotations are not rewritten.
+//!
+//! ```
+//! use smtlib_syntax::parser::parse_term;
+//! use smtlib_syntax::sharing::Sharing;
+//!
+//! let term = parse_term("(f (g (h x y) (h x y)) (g (h x y) (h x y)))").unwrap();
+//! assert_eq!(
+//!     Sharing::default().term(&term).to_string(),
+//!     "(let ((_let_1 (h x y))) (let ((_let_2 (g _let_1 _let_1))) (f _let_2 _let_2)))"
+//! );
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use super::attributes::Attribute;
+use super::identifiers::Identifier;
+use super::lexicon::Symbol;
+use super::scripts::{Command, FunctionDef, Script};
+use super::sexprs::Sexpr;
+use super::sorts::Sort;
+use super::terms::{MatchCase, QualifiedIdentifier, Term, VarBinding};
+
+/// The options for sharing subterms.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sharing {
+    /// The number of nodes a subterm needs to have to be shared, counting every constant,
+    /// identifier, application, binder, `match` and annotation, e.g. 3 for `(f x y)`. Constants
+    /// and identifiers on their own are never shared.
+    pub min_size: usize,
+    /// The start of the names of the shared subterms, which are followed by a number. Numbers
+    /// that would give a name that already occurs in the term or script are skipped.
+    pub prefix: Symbol,
+}
+
+impl Default for Sharing {
+    fn default() -> Self {
+        Sharing {
+            min_size: 3,
+            prefix: Symbol::new_str("_let_").expect("`_let_` is a valid symbol"),
+        }
+    }
+}
+
+impl Sharing {
+    /// Binds the subterms that occur more than once with `let`, as close to the root as their
+    /// variables allow. The bindings are nested so that every shared subterm is bound before the
+    /// subterms that contain it.
+    pub fn term(&self, term: &Term) -> Term {
+        let mut names = Names::new(&self.prefix);
+        names.reserve(&term.clone().into());
+        self.scope(term, &mut names)
+    }
+
+    /// Shares the subterms of the terms asserted and defined in the script. The subterms that
+    /// `sort_of` gives a sort for, and that mention none of the variables of a binder, become
+    /// nullary `define-fun`s that are defined right before the first command using them. The
+    /// others are bound with `let` within the commands, as with [`Sharing::term`].
+    ///
+    /// Subterms are not shared across `push`, `pop`, `reset` and `reset-assertions`, as the
+    /// definitions could be undone by them. The terms of `get-value` are left as they are, as
+    /// they are repeated in the response of the solver.
+    pub fn script<F>(&self, script: &Script, mut sort_of: F) -> Script
+    where
+        F: FnMut(&Term) -> Option<Sort>,
+    {
+        let mut names = Names::new(&self.prefix);
+        for command in &script.0 {
+            names.reserve(&command.clone().into());
+        }
+
+        let mut commands = vec![];
+        let segments = script.0.split_inclusive(|command| {
+            matches!(
+                command,
+                Command::Push(_) | Command::Pop(_) | Command::Reset | Command::ResetAssertions
+            )
+        });
+        for segment in segments {
+            self.segment(segment, &mut sort_of, &mut names, &mut commands);
+        }
+
+        Script(commands)
+    }
+
+    fn segment<F>(
+        &self,
+        segment: &[Command],
+        sort_of: &mut F,
+        names: &mut Names,
+        commands: &mut Vec<Command>,
+    ) where
+        F: FnMut(&Term) -> Option<Sort>,
+    {
+        let mut analysis = Analysis::default();
+        let mut counts = HashMap::new();
+        for (root, vars) in segment.iter().filter_map(root) {
+            analysis.bound = vec![vars];
+            analysis.visit(root);
+            self.count(root, &analysis.info, &mut counts);
+        }
+
+        let shared: Vec<(&Term, Sort)> = self
+            .shared(counts, &analysis.info)
+            .into_iter()
+            .filter_map(|term| Some((term, sort_of(term)?)))
+            .collect();
+        let helpers: HashMap<&Term, Symbol> = shared
+            .iter()
+            .map(|(term, _)| (*term, names.fresh()))
+            .collect();
+        let indexes: HashMap<&Symbol, usize> = shared
+            .iter()
+            .enumerate()
+            .map(|(i, (term, _))| (&helpers[term], i))
+            .collect();
+        let rewrite = Rewrite {
+            info: &analysis.info,
+            shared: &helpers,
+        };
+
+        let definitions: Vec<Term> = shared
+            .iter()
+            .map(|(term, _)| rewrite.children(term))
+            .collect();
+        let dependencies: Vec<Vec<usize>> = definitions
+            .iter()
+            .map(|definition| references(definition, &indexes))
+            .collect();
+
+        let mut defined = vec![false; shared.len()];
+        for command in segment {
+            let command = match command {
+                Command::Assert(term) => Command::Assert(rewrite.term(term)),
+                Command::DefineConst(name, sort, term) => {
+                    Command::DefineConst(name.clone(), sort.clone(), rewrite.term(term))
+                }
+                Command::DefineFun(def) => Command::DefineFun(FunctionDef {
+                    name: def.name.clone(),
+                    args: def.args.clone(),
+                    sort: def.sort.clone(),
+                    body: rewrite.term(&def.body),
+                }),
+                command => command.clone(),
+            };
+
+            // the helpers that the command needs, including those that other helpers need, are
+            // defined smallest first, as a helper can only contain smaller ones
+            let mut needed = match root(&command) {
+                Some((term, _)) => references(term, &indexes),
+                None => vec![],
+            };
+            let mut new = vec![];
+            while let Some(i) = needed.pop() {
+                if !defined[i] {
+                    defined[i] = true;
+                    new.push(i);
+                    needed.extend(&dependencies[i]);
+                }
+            }
+            new.sort_unstable();
+            for i in new {
+                let (term, sort) = &shared[i];
+                commands.push(Command::DefineFun(FunctionDef {
+                    name: helpers[term].clone(),
+                    args: vec![],
+                    sort: sort.clone(),
+                    body: self.scope(&definitions[i], names),
+                }));
+            }
+
+            commands.push(match command {
+                Command::Assert(term) => Command::Assert(self.scope(&term, names)),
+                Command::DefineConst(name, sort, term) => {
+                    Command::DefineConst(name, sort, self.scope(&term, names))
+                }
+                Command::DefineFun(def) => Command::DefineFun(FunctionDef {
+                    body: self.scope(&def.body, names),
+                    ..def
+                }),
+                command => command,
+            });
+        }
+    }
+
+    /// Shares the subterms of a term that mention no variables bound within it, and then the
+    /// subterms of the bodies of its binders, which may mention their variables.
+    fn scope(&self, term: &Term, names: &mut Names) -> Term {
+        let mut analysis = Analysis::default();
+        analysis.visit(term);
+        let mut counts = HashMap::new();
+        self.count(term, &analysis.info, &mut counts);
+
+        let shared = self.shared(counts, &analysis.info);
+        let variables: HashMap<&Term, Symbol> =
+            shared.iter().map(|term| (*term, names.fresh())).collect();
+        let indexes: HashMap<&Symbol, usize> = shared
+            .iter()
+            .enumerate()
+            .map(|(i, term)| (&variables[term], i))
+            .collect();
+        let rewrite = Rewrite {
+            info: &analysis.info,
+            shared: &variables,
+        };
+
+        // a binding goes into the first `let` after those of the subterms it contains
+        let mut levels: Vec<usize> = vec![];
+        let mut lets: Vec<Vec<VarBinding>> = vec![];
+        for term in &shared {
+            let definition = rewrite.children(term);
+            let level = references(&definition, &indexes)
+                .into_iter()
+                .map(|i| levels[i] + 1)
+                .max()
+                .unwrap_or(0);
+            levels.push(level);
+            if lets.len() == level {
+                lets.push(vec![]);
+            }
+            let definition = self.descend(&definition, names);
+            lets[level].push(VarBinding(variables[term].clone(), definition));
+        }
+
+        let body = self.descend(&rewrite.term(term), names);
+        wrap(body, lets)
+    }
+
+    /// Applies [`Sharing::scope`] to the bodies of the outermost binders within the term.
+    fn descend(&self, term: &Term, names: &mut Names) -> Term {
+        map_children(term, &mut |child, is_body| {
+            if is_body {
+                self.scope(child, names)
+            } else {
+                self.descend(child, names)
+            }
+        })
+    }
+
+    /// Counts how often each candidate for sharing occurs. The subterms of a candidate are only
+    /// counted where it occurs first, as the other occurrences become a variable.
+    fn count<'t>(
+        &self,
+        term: &'t Term,
+        info: &HashMap<*const Term, Info>,
+        counts: &mut HashMap<&'t Term, usize>,
+    ) {
+        if self.is_candidate(term, info[&ptr(term)]) {
+            let count = counts.entry(term).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                return;
+            }
+        }
+        for child in children(term) {
+            self.count(child, info, counts);
+        }
+    }
+
+    /// The candidates that occur more than once, smallest first and otherwise in the order of
+    /// terms, so that the names do not depend on the order of the hash map.
+    fn shared<'t>(
+        &self,
+        counts: HashMap<&'t Term, usize>,
+        info: &HashMap<*const Term, Info>,
+    ) -> Vec<&'t Term> {
+        let mut shared: Vec<&Term> = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(term, _)| term)
+            .collect();
+        shared.sort_by(|a, b| {
+            let size = |term: &Term| info[&ptr(term)].size;
+            size(a).cmp(&size(b)).then_with(|| a.cmp(b))
+        });
+        shared
+    }
+
+    fn is_candidate(&self, term: &Term, info: Info) -> bool {
+        let compound = !matches!(
+            term,
+            Term::SpecialConstant(_) | Term::QualifiedIdentifier(_) | Term::Annotated(_, _)
+        );
+        compound && info.hoistable && info.size >= self.min_size
+    }
+}
+
+/// The size of a subterm, and whether it can be bound at the root of the term being shared,
+/// i.e. mentions no variable that is bound within that term.
+#[derive(Clone, Copy, Debug)]
+struct Info {
+    size: usize,
+    hoistable: bool,
+}
+
+/// The subterms are told apart by their address, as equal subterms can differ in what their
+/// variables are bound to.
+fn ptr(term: &Term) -> *const Term {
+    term
+}
+
+#[derive(Default)]
+struct Analysis<'t> {
+    info: HashMap<*const Term, Info>,
+    /// The variables of the binders around the current subterm, innermost last.
+    bound: Vec<Vec<&'t Symbol>>,
+}
+
+impl<'t> Analysis<'t> {
+    /// Records the info of the term and its subterms, and returns the size of the term and the
+    /// outermost binder whose variables occur free in it.
+    fn visit(&mut self, term: &'t Term) -> (usize, Option<usize>) {
+        let parts: Vec<(usize, Option<usize>)> = match term {
+            Term::SpecialConstant(_) => vec![],
+            Term::QualifiedIdentifier(qi) => vec![(0, self.binder(qi))],
+            Term::Application(qi, args) => {
+                let head = (0, self.binder(qi));
+                std::iter::once(head)
+                    .chain(args.iter().map(|arg| self.visit(arg)))
+                    .collect()
+            }
+            Term::Let(bindings, body) => {
+                let mut parts: Vec<_> = bindings.iter().map(|b| self.visit(&b.1)).collect();
+                parts.push(self.visit_binder(bindings.iter().map(|b| &b.0).collect(), body));
+                parts
+            }
+            Term::Forall(vars, body) | Term::Exists(vars, body) | Term::Lambda(vars, body) => {
+                vec![self.visit_binder(vars.iter().map(|var| &var.0).collect(), body)]
+            }
+            Term::Match(term, cases) => {
+                let head = self.visit(term);
+                std::iter::once(head)
+                    .chain(cases.iter().map(|MatchCase(pattern, term)| {
+                        // a pattern without binders is a variable, unless it names a constructor
+                        let vars = match pattern.binders() {
+                            [] => vec![pattern.head()],
+                            binders => binders.iter().collect(),
+                        };
+                        self.visit_binder(vars, term)
+                    }))
+                    .collect()
+            }
+            Term::Annotated(term, attributes) => {
+                let head = self.visit(term);
+                let mut parts = vec![head];
+                for attribute in attributes {
+                    let terms = match attribute {
+                        Attribute::Pattern(terms) => terms.terms(),
+                        Attribute::NoPattern(term) => std::slice::from_ref(term),
+                        Attribute::Named(_) | Attribute::Other(_, _) => &[],
+                    };
+                    parts.extend(terms.iter().map(|term| (0, self.visit(term).1)));
+                }
+                parts
+            }
+            Term::Apply(fun, args) => std::iter::once(&**fun)
+                .chain(args)
+                .map(|term| self.visit(term))
+                .collect(),
+        };
+
+        let size = 1 + parts.iter().map(|(size, _)| size).sum::<usize>();
+        let binder = parts.iter().filter_map(|(_, binder)| *binder).min();
+        let info = Info {
+            size,
+            hoistable: binder.is_none(),
+        };
+        self.info.insert(ptr(term), info);
+        (size, binder)
+    }
+
+    fn visit_binder(&mut self, vars: Vec<&'t Symbol>, body: &'t Term) -> (usize, Option<usize>) {
+        let level = self.bound.len();
+        self.bound.push(vars);
+        let (size, binder) = self.visit(body);
+        self.bound.pop();
+        // the variables of this binder are not free in it
+        (size, binder.filter(|binder| *binder < level))
+    }
+
+    fn binder(&self, qi: &QualifiedIdentifier) -> Option<usize> {
+        let id = qi.identifier();
+        if !id.indexes().is_empty() {
+            return None;
+        }
+        self.bound
+            .iter()
+            .rposition(|vars| vars.contains(&id.symbol()))
+    }
+}
+
+struct Rewrite<'a, 't> {
+    info: &'a HashMap<*const Term, Info>,
+    shared: &'a HashMap<&'t Term, Symbol>,
+}
+
+impl Rewrite<'_, '_> {
+    /// Replaces the term by its variable if it is shared, and its subterms otherwise.
+    fn term(&self, term: &Term) -> Term {
+        // only the subterms that could have been counted are looked up, which saves hashing
+        // the others
+        let info = self.info[&ptr(term)];
+        if info.hoistable {
+            if let Some(name) = self.shared.get(term) {
+                return Term::QualifiedIdentifier(QualifiedIdentifier::new(Identifier::new(
+                    name.clone(),
+                )));
+            }
+        }
+        self.children(term)
+    }
+
+    fn children(&self, term: &Term) -> Term {
+        map_children(term, &mut |child, _| self.term(child))
+    }
+}
+
+/// The subterms that sharing looks into: all but those of attributes, and none of a term
+/// annotated with `:named`.
+fn children(term: &Term) -> Vec<&Term> {
+    match term {
+        Term::SpecialConstant(_) | Term::QualifiedIdentifier(_) => vec![],
+        Term::Application(_, args) => args.iter().collect(),
+        Term::Let(bindings, body) => bindings
+            .iter()
+            .map(|binding| &binding.1)
+            .chain(std::iter::once(&**body))
+            .collect(),
+        Term::Forall(_, body) | Term::Exists(_, body) | Term::Lambda(_, body) => vec![body],
+        Term::Match(term, cases) => std::iter::once(&**term)
+            .chain(cases.iter().map(|case| &case.1))
+            .collect(),
+        Term::Annotated(_, attributes) if is_named(attributes) => vec![],
+        Term::Annotated(term, _) => vec![term],
+        Term::Apply(fun, args) => std::iter::once(&**fun).chain(args).collect(),
+    }
+}
+
+/// Rebuilds the term from its [`children`] mapped with `f`, which is told whether the child is
+/// the body of a binder.
+fn map_children(term: &Term, f: &mut impl FnMut(&Term, bool) -> Term) -> Term {
+    match term {
+        Term::SpecialConstant(_) | Term::QualifiedIdentifier(_) => term.clone(),
+        Term::Application(qi, args) => {
+            Term::Application(qi.clone(), args.iter().map(|arg| f(arg, false)).collect())
+        }
+        Term::Let(bindings, body) => Term::Let(
+            bindings
+                .iter()
+                .map(|VarBinding(name, term)| VarBinding(name.clone(), f(term, false)))
+                .collect(),
+            Box::new(f(body, true)),
+        ),
+        Term::Forall(vars, body) => Term::Forall(vars.clone(), Box::new(f(body, true))),
+        Term::Exists(vars, body) => Term::Exists(vars.clone(), Box::new(f(body, true))),
+        Term::Lambda(vars, body) => Term::Lambda(vars.clone(), Box::new(f(body, true))),
+        Term::Match(term, cases) => Term::Match(
+            Box::new(f(term, false)),
+            cases
+                .iter()
+                .map(|MatchCase(pattern, term)| MatchCase(pattern.clone(), f(term, true)))
+                .collect(),
+        ),
+        Term::Annotated(_, attributes) if is_named(attributes) => term.clone(),
+        Term::Annotated(term, attributes) => {
+            Term::Annotated(Box::new(f(term, false)), attributes.clone())
+        }
+        Term::Apply(fun, args) => Term::Apply(
+            Box::new(f(fun, false)),
+            args.iter().map(|arg| f(arg, false)).collect(),
+        ),
+    }
+}
+
+fn is_named(attributes: &[Attribute]) -> bool {
+    attributes
+        .iter()
+        .any(|attribute| matches!(attribute, Attribute::Named(_)))
+}
+
+/// The indexes of the shared subterms whose names occur in the term.
+fn references(term: &Term, indexes: &HashMap<&Symbol, usize>) -> Vec<usize> {
+    let mut found = vec![];
+    let mut stack = vec![term];
+    while let Some(term) = stack.pop() {
+        if let Term::QualifiedIdentifier(qi) = term {
+            if let Some(i) = indexes.get(qi.identifier().symbol()) {
+                found.push(*i);
+            }
+        }
+        stack.extend(children(term));
+    }
+    found.sort_unstable();
+    found.dedup();
+    found
+}
+
+/// Puts the nested `let`s around the body, or around the annotated term if the body is
+/// annotated, so that annotations such as `:pattern` stay right below their quantifier.
+fn wrap(body: Term, lets: Vec<Vec<VarBinding>>) -> Term {
+    match body {
+        Term::Annotated(term, attributes) if !lets.is_empty() => {
+            Term::Annotated(Box::new(wrap(*term, lets)), attributes)
+        }
+        body => lets
+            .into_iter()
+            .rev()
+            .fold(body, |body, bindings| Term::Let(bindings, Box::new(body))),
+    }
+}
+
+/// The term of a command that sharing applies to, with the variables it is in the scope of.
+fn root(command: &Command) -> Option<(&Term, Vec<&Symbol>)> {
+    match command {
+        Command::Assert(term) | Command::DefineConst(_, _, term) => Some((term, vec![])),
+        Command::DefineFun(def) => Some((&def.body, def.args.iter().map(|var| &var.0).collect())),
+        _ => None,
+    }
+}
+
+/// Fresh names for the shared subterms.
+struct Names {
+    prefix: String,
+    used: HashSet<String>,
+    next: usize,
+}
+
+impl Names {
+    fn new(prefix: &Symbol) -> Self {
+        Names {
+            prefix: prefix.symbol().to_string(),
+            used: HashSet::new(),
+            next: 1,
+        }
+    }
+
+    /// Marks the symbols of the s-expression as taken.
+    fn reserve(&mut self, sexpr: &Sexpr) {
+        match sexpr {
+            Sexpr::Symbol(sym) => {
+                self.used.insert(sym.symbol().to_string());
+            }
+            Sexpr::Sequence(seq) => seq.iter().for_each(|sexpr| self.reserve(sexpr)),
+            Sexpr::SpecialConstant(_) | Sexpr::Keyword(_) | Sexpr::Reserved(_) => {}
+        }
+    }
+
+    fn fresh(&mut self) -> Symbol {
+        loop {
+            let name = format!("{}{}", self.prefix, self.next);
+            self.next += 1;
+            if self.used.insert(name.clone()) {
+                return Symbol::new(name).expect("a valid symbol followed by digits is valid");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_script, parse_term};
+
+    fn share(input: &str) -> String {
+        Sharing::default()
+            .term(&parse_term(input).unwrap())
+            .to_string()
+    }
+
+    #[test]
+    fn shares_within_scopes() {
+        let mut term = parse_term("x").unwrap();
+        for _ in 0..16 {
+            term = Term::Application(
+                QualifiedIdentifier::new(Identifier::new(Symbol::new_str("f").unwrap())),
+                vec![term.clone(), term],
+            );
+        }
+        let mut expected = "(let ((_let_1 (f x x))) ".to_string();
+        for i in 2..16 {
+            expected += &format!("(let ((_let_{i} (f _let_{} _let_{}))) ", i - 1, i - 1);
+        }
+        expected += "(f _let_15 _let_15)";
+        expected.extend(std::iter::repeat_n(')', 15));
+        assert_eq!(Sharing::default().term(&term).to_string(), expected);
+
+        // subterms mentioning a bound variable stay within its binder
+        assert_eq!(
+            share("(and (forall ((x Int)) (and (p (g x y)) (q (g x y)) (r (h y z)))) (s (h y z)))"),
+            "(let ((_let_1 (h y z))) (and (forall ((x Int)) (let ((_let_2 (g x y))) \
+             (and (p _let_2) (q _let_2) (r _let_1)))) (s _let_1)))"
+        );
+        assert_eq!(
+            share("(and (p (g x y)) (forall ((x Int)) (p (g x y))) (let ((y 0)) (g x y)))"),
+            "(and (p (g x y)) (forall ((x Int)) (p (g x y))) (let ((y 0)) (g x y)))"
+        );
+        assert_eq!(
+            share("(match l (((cons h t) (+ (g h 1) (g h 1))) (nil (+ (g h 1) (g h 1)))))"),
+            "(let ((_let_1 (g h 1))) (match l (((cons h t) (let ((_let_2 (g h 1))) \
+             (+ _let_2 _let_2))) (nil (+ _let_1 _let_1)))))"
+        );
+
+        // annotations stay in place, and names that are taken are skipped
+        assert_eq!(
+            share("(forall ((x Int)) (! (= (g x _let_1) (g x _let_1)) :pattern ((g x _let_1))))"),
+            "(forall ((x Int)) (! (let ((_let_2 (g x _let_1))) (= _let_2 _let_2)) \
+             :pattern ((g x _let_1))))"
+        );
+        assert_eq!(
+            share("(and (! (g x y) :named a) (g x y))"),
+            "(and (! (g x y) :named a) (g x y))"
+        );
+
+        let sharing = Sharing {
+            min_size: 4,
+            ..Sharing::default()
+        };
+        let term = parse_term("(+ (g x y) (g x y))").unwrap();
+        assert_eq!(sharing.term(&term), term);
+    }
+
+    #[test]
+    fn defines_helpers() {
+        let input = "(declare-fun x () Int) (declare-fun f (Int Int) Int) \
+            (assert (> (f (f x x) (f x x)) 0)) \
+            (assert (or (p (g x x)) (q (g x x)))) \
+            (push 1) \
+            (define-fun h ((y Int)) Int (+ (f y y) (f y y) (f x x) (f x x))) \
+            (pop 1)";
+        let script = parse_script(input).unwrap();
+        let sort_of = |term: &Term| match term {
+            Term::Application(qi, _) if qi.identifier().symbol().symbol() == "f" => {
+                Some(Sort::new(Identifier::new(Symbol::new_str("Int").unwrap())))
+            }
+            _ => None,
+        };
+
+        let expected = "\
+(declare-fun x () Int)
+(declare-fun f (Int Int) Int)
+(define-fun _let_1 () Int (f x x))
+(assert (> (f _let_1 _let_1) 0))
+(assert (let ((_let_2 (g x x))) (or (p _let_2) (q _let_2))))
+(push 1)
+(define-fun _let_3 () Int (f x x))
+(define-fun h ((y Int)) Int (let ((_let_4 (f y y))) (+ _let_4 _let_4 _let_3 _let_3)))
+(pop 1)
+";
+        let shared = Sharing::default().script(&script, sort_of);
+        assert_eq!(shared.to_string(), expected);
+    }
+}
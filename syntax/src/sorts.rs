@@ -6,18 +6,143 @@
 //! > term is a sort symbol, a sort parameter, or a sort symbol applied to a sequence of sort terms.
 //! > Syntactically, a sort symbol can be either the distinguished symbol Bool or any 〈identier 〉.
 //! > A sort parameter can be any 〈symbol 〉 (which in turn, is an 〈identier 〉).
-//! > ```
+//! > ```text
 //! > 〈sort〉 ::= 〈identier 〉 | ( 〈identier 〉 〈sort〉+ )
 //! > ```
 
 use std::fmt::Display;
 
-use super::{identifiers::Identifier, sexprs::Sexpr};
+use super::{
+    identifiers::Identifier,
+    lexicon::{InvalidSymbolError, Symbol, Text},
+    sexprs::{self, Elements, FromSexprError, FromSexprErrorKind, Production, Sexpr},
+    writer,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sort<T = String> {
+    pub(crate) identifier: Identifier<T>,
+    pub(crate) parameters: Vec<Sort<T>>,
+}
+
+impl<T: Text> Sort<T> {
+    /// A sort without parameters, e.g. `(_ BitVec 32)`.
+    pub fn new(identifier: Identifier<T>) -> Self {
+        Sort {
+            identifier,
+            parameters: vec![],
+        }
+    }
+
+    /// The application of a sort constructor to parameters, e.g. `(Array Int Bool)`. Without
+    /// parameters, this is the same as [`Sort::new`].
+    pub fn app(identifier: Identifier<T>, parameters: Vec<Sort<T>>) -> Self {
+        Sort {
+            identifier,
+            parameters,
+        }
+    }
+
+    pub fn identifier(&self) -> &Identifier<T> {
+        &self.identifier
+    }
+
+    pub fn parameters(&self) -> &[Sort<T>] {
+        &self.parameters
+    }
 
-#[derive(Debug, Clone)]
-pub struct Sort {
-    identifier: Identifier,
-    parameters: Vec<Sort>,
+    pub fn into_owned(self) -> Sort {
+        Sort {
+            identifier: self.identifier.into_owned(),
+            parameters: sexprs::into_owned(self.parameters, Sort::into_owned),
+        }
+    }
+
+    /// The sort `name`, or `(name parameters+)`.
+    pub(crate) fn parametric(name: Symbol<T>, parameters: Vec<Sort<T>>) -> Self {
+        Sort::app(name.into(), parameters)
+    }
+
+    /// Whether this is a function sort `(-> ...)`.
+    pub fn is_function(&self) -> bool {
+        self.identifier.indexes.is_empty()
+            && self.identifier.symbol.symbol() == "->"
+            && !self.parameters.is_empty()
+    }
+}
+
+impl Sort {
+    /// A sort that is just a symbol, e.g. `Int`.
+    pub fn simple(name: &str) -> Result<Self, InvalidSymbolError> {
+        Symbol::new_str(name).map(|symbol| Sort::new(symbol.into()))
+    }
+
+    /// The sort `(-> args+ result)` of functions, which SMT-LIB 2.7 added to make functions
+    /// values. Without arguments, this is just `result`.
+    pub fn function(args: Vec<Sort>, result: Sort) -> Self {
+        if args.is_empty() {
+            return result;
+        }
+
+        let arrow = Symbol::new("->".to_string()).expect("`->` is a valid symbol");
+        let mut parameters = args;
+        parameters.push(result);
+        Sort::parametric(arrow, parameters)
+    }
+
+    /// Replaces the sort parameters `params` by the sorts `args`.
+    pub(crate) fn substitute(&self, params: &[Symbol], args: &[Sort]) -> Sort {
+        let Sort {
+            identifier,
+            parameters,
+        } = self;
+
+        if parameters.is_empty() && identifier.indexes.is_empty() {
+            if let Some(i) = params.iter().position(|p| *p == identifier.symbol) {
+                return args[i].clone();
+            }
+        }
+
+        Sort {
+            identifier: identifier.clone(),
+            parameters: parameters
+                .iter()
+                .map(|param| param.substitute(params, args))
+                .collect(),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Sort<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Sequence(seq) if !Identifier::is_indexed(&seq) => {
+                let mut elems = Elements::new(Production::Sort, seq);
+                let identifier = elems.next("identifier", Identifier::try_from)?;
+                let parameters = elems.rest_nonempty("sort", Sort::try_from)?;
+
+                Ok(Sort {
+                    identifier,
+                    parameters,
+                })
+            }
+            sexpr => Identifier::try_from(sexpr)
+                .map(|identifier| Sort {
+                    identifier,
+                    parameters: vec![],
+                })
+                .map_err(|err| match (err.path(), err.kind()) {
+                    // report things that are not sorts at all as such, rather than as malformed
+                    // identifiers
+                    ([], FromSexprErrorKind::Unexpected { .. }) => {
+                        FromSexprError::new(Production::Sort, err.kind().clone())
+                    }
+                    _ => err,
+                }),
+        }
+    }
 }
 
 impl From<Sort> for Sexpr {
@@ -36,18 +161,8 @@ impl From<Sort> for Sexpr {
     }
 }
 
-impl Display for Sort {
+impl<T: Text> Display for Sort<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.parameters.is_empty() {
-            self.identifier.fmt(f)
-        } else {
-            write!(f, "({id}", id = self.identifier)?;
-
-            for param in &self.parameters {
-                write!(f, " {param}")?;
-            }
-
-            write!(f, ")")
-        }
+        writer::display(self, f)
     }
 }
@@ -0,0 +1,473 @@
+//! Making sure that printed scripts conform to the grammar of the spec.
+//!
+//! Most of the grammar is upheld by the syntax types themselves: numerals can not be negative and
+//! [`Term::from`] writes negative integers as `(- 5)`, symbols are quoted when they are not simple
+//! symbols or clash with a reserved word such as `assert`, and the constructors of string
+//! literals, keywords, `set-info` sources and the like reject text that could not be read back.
+//!
+//! What the types can still express are the lists that the grammar requires to be non-empty,
+//! e.g. `(f)` or `(get-value ())`, binders that bind the same symbol twice, and datatype
+//! declarations whose sort parameters disagree with the arity of their sort. Many solvers accept
+//! some of these, so they are printed as they are, but [`Script::strict`] rewrites them into their
+//! equivalent when there is one, e.g. `(f)` into `f`, and fails on the rest.
+//!
+//! ```
+//! use smtlib_syntax::scripts::Command;
+//! use smtlib_syntax::terms::Term;
+//!
+//! let term = Term::Let(vec![], Box::new(Term::from(-5)));
+//! let command = Command::Assert(term).strict().unwrap();
+//! assert_eq!(command.to_string(), "(assert (- 5))");
+//!
+//! assert!(Command::GetValue(vec![]).strict().is_err());
+//! ```
+
+use std::fmt::Display;
+
+use super::attributes::{Attribute, MultiPattern};
+use super::lexicon::Symbol;
+use super::scripts::{Command, FunctionDef, Script, SortDec};
+use super::terms::{first_duplicate, MatchCase, SortedVar, Term, VarBinding};
+
+/// A construct that the grammar of the spec does not allow, and that has no equivalent it does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComplianceError {
+    construct: &'static str,
+}
+
+impl Display for ComplianceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not valid SMT-LIB", self.construct)
+    }
+}
+
+impl std::error::Error for ComplianceError {}
+
+fn require(condition: bool, construct: &'static str) -> Result<(), ComplianceError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(ComplianceError { construct })
+    }
+}
+
+fn require_distinct<'a>(
+    symbols: impl IntoIterator<Item = &'a Symbol>,
+    construct: &'static str,
+) -> Result<(), ComplianceError> {
+    let symbols: Vec<_> = symbols.into_iter().collect();
+    require(first_duplicate(&symbols).is_none(), construct)
+}
+
+fn require_distinct_vars(vars: &[SortedVar]) -> Result<(), ComplianceError> {
+    require_distinct(
+        vars.iter().map(|SortedVar(symbol, _)| symbol),
+        "a variable bound twice by the same binder",
+    )
+}
+
+fn strict_all(terms: Vec<Term>) -> Result<Vec<Term>, ComplianceError> {
+    terms.into_iter().map(Term::strict).collect()
+}
+
+fn strict_attribute(attribute: Attribute) -> Result<Attribute, ComplianceError> {
+    let attribute = match attribute {
+        Attribute::Pattern(terms) => Attribute::Pattern(MultiPattern(strict_all(terms.0)?)),
+        Attribute::NoPattern(term) => Attribute::NoPattern(term.strict()?),
+        attribute @ (Attribute::Named(_) | Attribute::Other(_, _)) => attribute,
+    };
+
+    Ok(attribute)
+}
+
+fn strict_function_def(def: FunctionDef) -> Result<FunctionDef, ComplianceError> {
+    require_distinct_vars(&def.args)?;
+    Ok(FunctionDef {
+        body: def.body.strict()?,
+        ..def
+    })
+}
+
+impl Term {
+    /// Rewrites the lists that the grammar requires to be non-empty but that are empty, e.g. an
+    /// application without arguments into the function symbol and a `let` without bindings into
+    /// its body. A `lambda` without variables, a `match` without cases and a binder that binds
+    /// the same symbol twice are an error.
+    pub fn strict(self) -> Result<Term, ComplianceError> {
+        let term = match self {
+            Term::SpecialConstant(_) | Term::QualifiedIdentifier(_) => self,
+            Term::Application(qi, args) if args.is_empty() => Term::QualifiedIdentifier(qi),
+            Term::Application(qi, args) => Term::Application(qi, strict_all(args)?),
+            Term::Let(bindings, body) if bindings.is_empty() => body.strict()?,
+            Term::Let(bindings, body) => {
+                require_distinct(
+                    bindings.iter().map(|VarBinding(symbol, _)| symbol),
+                    "a variable bound twice by the same binder",
+                )?;
+                Term::Let(
+                    bindings
+                        .into_iter()
+                        .map(|VarBinding(name, term)| Ok(VarBinding(name, term.strict()?)))
+                        .collect::<Result<_, _>>()?,
+                    Box::new(body.strict()?),
+                )
+            }
+            Term::Forall(vars, body) | Term::Exists(vars, body) if vars.is_empty() => {
+                body.strict()?
+            }
+            Term::Forall(vars, body) => {
+                require_distinct_vars(&vars)?;
+                Term::Forall(vars, Box::new(body.strict()?))
+            }
+            Term::Exists(vars, body) => {
+                require_distinct_vars(&vars)?;
+                Term::Exists(vars, Box::new(body.strict()?))
+            }
+            Term::Match(term, cases) => {
+                require(!cases.is_empty(), "`match` without cases")?;
+                Term::Match(
+                    Box::new(term.strict()?),
+                    cases
+                        .into_iter()
+                        .map(|MatchCase(pattern, term)| Ok(MatchCase(pattern, term.strict()?)))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            Term::Annotated(term, attributes) if attributes.is_empty() => term.strict()?,
+            Term::Annotated(term, attributes) => Term::Annotated(
+                Box::new(term.strict()?),
+                attributes
+                    .into_iter()
+                    .map(strict_attribute)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Term::Lambda(vars, body) => {
+                require(!vars.is_empty(), "`lambda` without variables")?;
+                require_distinct_vars(&vars)?;
+                Term::Lambda(vars, Box::new(body.strict()?))
+            }
+            Term::Apply(fun, args) if args.is_empty() => fun.strict()?,
+            Term::Apply(fun, args) => Term::Apply(Box::new(fun.strict()?), strict_all(args)?),
+        };
+
+        Ok(term)
+    }
+}
+
+impl Command {
+    /// Rewrites the terms of the command with [`Term::strict`]. Commands that need at least one
+    /// of something but have none, e.g. `(get-value ())`, functions whose arguments bind the same
+    /// symbol twice, and datatypes whose sort parameters disagree with the arity of their sort
+    /// are an error.
+    pub fn strict(self) -> Result<Command, ComplianceError> {
+        let command = match self {
+            Command::Assert(term) => Command::Assert(term.strict()?),
+            Command::DeclareDatatypes(entries) => {
+                require(!entries.is_empty(), "`declare-datatypes` without datatypes")?;
+                for (SortDec(_, arity), dec) in &entries {
+                    require(
+                        usize::try_from(arity).ok() == Some(dec.parameters().len()),
+                        "a datatype whose sort parameters disagree with the arity of its sort",
+                    )?;
+                }
+                Command::DeclareDatatypes(entries)
+            }
+            Command::DefineConst(name, sort, term) => {
+                Command::DefineConst(name, sort, term.strict()?)
+            }
+            Command::DefineFun(def) => Command::DefineFun(strict_function_def(def)?),
+            Command::DefineFunRec(def) => Command::DefineFunRec(strict_function_def(def)?),
+            Command::DefineFunsRec(entries) => {
+                require(!entries.is_empty(), "`define-funs-rec` without functions")?;
+                Command::DefineFunsRec(
+                    entries
+                        .into_iter()
+                        .map(|(dec, body)| {
+                            require_distinct_vars(&dec.args)?;
+                            Ok((dec, body.strict()?))
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            Command::GetValue(terms) => {
+                require(!terms.is_empty(), "`get-value` without terms")?;
+                Command::GetValue(strict_all(terms)?)
+            }
+            command @ (Command::CheckSat
+            | Command::CheckSatAssuming(_)
+            | Command::DeclareConst(_, _)
+            | Command::DeclareDatatype(_, _)
+            | Command::DeclareFun(_, _, _)
+            | Command::DeclareSort(_, _)
+            | Command::DeclareSortParameter(_)
+            | Command::DefineSort(_, _, _)
+            | Command::Echo(_)
+            | Command::Exit
+            | Command::GetAssertions
+            | Command::GetAssignment
+            | Command::GetInfo(_)
+            | Command::GetModel
+            | Command::GetOption(_)
+            | Command::GetProof
+            | Command::GetUnsatAssumptions
+            | Command::GetUnsatCore
+            | Command::Pop(_)
+            | Command::Push(_)
+            | Command::Reset
+            | Command::ResetAssertions
+            | Command::SetInfo(_)
+            | Command::SetLogic(_)
+            | Command::SetOption(_)) => command,
+        };
+
+        Ok(command)
+    }
+}
+
+impl Script {
+    /// Makes every command conform to the grammar, see [`Command::strict`].
+    pub fn strict(self) -> Result<Script, ComplianceError> {
+        self.0
+            .into_iter()
+            .map(Command::strict)
+            .collect::<Result<_, _>>()
+            .map(Script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::identifiers::{Identifier, Index};
+    use crate::lexicon::{Keyword, Numeral, StringConstant, Symbol};
+    use crate::parser::parse_command;
+    use crate::scripts::{
+        ConstructorDec, DatatypeDec, FunctionDec, InfoFlag, PropLiteral, SelectorDec, SmtInfo,
+        SmtOption, SortDec, Source, Status,
+    };
+    use crate::sorts::Sort;
+    use crate::terms::{Pattern, QualifiedIdentifier, SortedVar};
+
+    fn sym(name: &str) -> Symbol {
+        Symbol::new_str(name).unwrap()
+    }
+
+    fn var(name: &str) -> Term {
+        Term::QualifiedIdentifier(QualifiedIdentifier::new(sym(name).into()))
+    }
+
+    fn app(name: &str, args: Vec<Term>) -> Term {
+        Term::Application(QualifiedIdentifier::new(sym(name).into()), args)
+    }
+
+    /// One command of every kind, with the symbols, numerals and empty lists that are easy to get
+    /// wrong when printing.
+    fn every_command() -> Vec<Command> {
+        let int = Sort::simple("Int").unwrap();
+        let list = DatatypeDec::parametric(
+            vec![sym("T")],
+            vec![
+                ConstructorDec {
+                    name: sym("nil"),
+                    selectors: vec![],
+                },
+                ConstructorDec {
+                    name: sym("cons"),
+                    selectors: vec![SelectorDec {
+                        name: sym("head"),
+                        sort: Sort::simple("T").unwrap(),
+                    }],
+                },
+            ],
+        )
+        .unwrap();
+        let inc = FunctionDef::new(
+            sym("inc"),
+            vec![SortedVar::new(sym("assert"), int.clone())],
+            int.clone(),
+            app("+", vec![var("assert"), Term::from(-1)]),
+        );
+        let body = Term::Match(
+            Box::new(Term::Let(vec![], Box::new(var("l")))),
+            vec![
+                MatchCase::new(Pattern::symbol(sym("nil")), Term::from(0)),
+                MatchCase::new(
+                    Pattern::constructor(sym("cons"), vec![sym("h"), sym("t")]).unwrap(),
+                    app("f", vec![]),
+                ),
+            ],
+        );
+
+        vec![
+            Command::Assert(
+                Term::Forall(
+                    vec![SortedVar::new(sym("x"), int.clone())],
+                    Box::new(app(">", vec![var("x"), Term::from(i64::MIN)])),
+                )
+                .annotate(Attribute::Pattern(
+                    MultiPattern::new(vec![app("g", vec![var("x")])]).unwrap(),
+                ))
+                .named(sym("par")),
+            ),
+            Command::CheckSat,
+            Command::CheckSatAssuming(vec![
+                PropLiteral::new(sym("p")),
+                PropLiteral::negated(sym("check-sat")),
+            ]),
+            Command::DeclareConst(
+                sym("x y"),
+                Sort::new(Identifier::indexed(
+                    sym("BitVec"),
+                    vec![Index::Numeral(Numeral::from(32u8))],
+                )),
+            ),
+            Command::DeclareDatatype(sym("List"), list.clone()),
+            Command::DeclareDatatypes(vec![(SortDec(sym("List"), Numeral::from(1u8)), list)]),
+            Command::DeclareFun(sym("f"), vec![], int.clone()),
+            Command::DeclareSort(sym("U"), Numeral::from(0u8)),
+            Command::DeclareSortParameter(sym("X")),
+            Command::DefineConst(
+                sym("k"),
+                Sort::function(vec![int.clone()], int.clone()),
+                Term::Lambda(
+                    vec![SortedVar::new(sym("n"), int.clone())],
+                    Box::new(Term::Apply(Box::new(var("n")), vec![])),
+                ),
+            ),
+            Command::DefineFun(inc.clone()),
+            Command::DefineFunRec(inc),
+            Command::DefineFunsRec(vec![(
+                FunctionDec::new(sym("len"), vec![SortedVar::new(sym("l"), int.clone())], int),
+                body,
+            )]),
+            Command::DefineSort(
+                sym("Arr"),
+                vec![sym("_")],
+                Sort::app(sym("Array").into(), vec![Sort::simple("_").unwrap()]),
+            ),
+            Command::Echo(StringConstant::new_str("say \"hi\"").unwrap()),
+            Command::Exit,
+            Command::GetAssertions,
+            Command::GetAssignment,
+            Command::GetInfo(InfoFlag::ReasonUnknown),
+            Command::GetModel,
+            Command::GetOption(Keyword::new_str("produce-models").unwrap()),
+            Command::GetProof,
+            Command::GetUnsatAssumptions,
+            Command::GetUnsatCore,
+            Command::GetValue(vec![
+                app("f", vec![]),
+                Term::Annotated(Box::new(var("x")), vec![]),
+            ]),
+            Command::Pop(Numeral::from(1u8)),
+            Command::Push(Numeral::from(1u8)),
+            Command::Reset,
+            Command::ResetAssertions,
+            Command::SetInfo(SmtInfo::Source(Source::new("a|b".to_string()).unwrap())),
+            Command::SetInfo(SmtInfo::Source(
+                Source::new("Generated\n by hand".to_string()).unwrap(),
+            )),
+            Command::SetLogic(sym("QF_UF")),
+            Command::SetOption(SmtOption::RandomSeed(Numeral::from(7u8))),
+            Command::SetInfo(SmtInfo::Status(Status::Unsat)),
+        ]
+    }
+
+    #[test]
+    fn every_command_conforms() {
+        let commands = every_command();
+        let kinds: BTreeSet<_> = commands.iter().map(Command::command_type).collect();
+        assert_eq!(kinds.len(), crate::scripts::CommandType::ALL.len());
+
+        for command in commands {
+            let strict = command.strict().unwrap();
+            let text = strict.to_string();
+            // the parser implements the grammar of the spec, so reading the command back fails
+            // or yields a different one if anything was printed wrongly
+            assert_eq!(parse_command(&text).unwrap(), strict, "{text}");
+        }
+    }
+
+    #[test]
+    fn rewrites_and_errors() {
+        let printed: Vec<_> = every_command()
+            .into_iter()
+            .map(|command| command.strict().unwrap().to_string())
+            .collect();
+        assert!(printed
+            .contains(&"(define-fun inc ((|assert| Int)) Int (+ |assert| (- 1)))".to_string()));
+        assert!(printed.contains(&"(check-sat-assuming (p (not |check-sat|)))".to_string()));
+        assert!(printed.contains(&"(define-sort Arr (|_|) (Array |_|))".to_string()));
+        assert!(printed.contains(&r#"(set-info :source "a|b")"#.to_string()));
+        assert!(printed.contains(&"(set-info :source |Generated\n by hand|)".to_string()));
+        assert!(printed.contains(&"(get-value (f x))".to_string()));
+
+        let err = Command::GetValue(vec![]).strict().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`get-value` without terms is not valid SMT-LIB"
+        );
+        let lambda = Term::Lambda(vec![], Box::new(Term::from(1)));
+        assert!(Command::Assert(lambda).strict().is_err());
+    }
+
+    #[test]
+    fn duplicate_binders() {
+        let int = Sort::simple("Int").unwrap();
+        let x = || SortedVar::new(sym("x"), int.clone());
+        let forall = Term::Forall(vec![x(), x()], Box::new(var("x")));
+        let err = Command::Assert(forall).strict().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a variable bound twice by the same binder is not valid SMT-LIB"
+        );
+        let lambda = Term::Lambda(vec![x(), x()], Box::new(var("x")));
+        assert!(lambda.strict().is_err());
+        let bindings = vec![
+            VarBinding::new(sym("y"), Term::from(1)),
+            VarBinding::new(sym("y"), Term::from(2)),
+        ];
+        assert!(Term::Let(bindings, Box::new(var("y"))).strict().is_err());
+        // binders that are nested may shadow each other
+        let nested = Term::Exists(
+            vec![x()],
+            Box::new(Term::Exists(vec![x()], Box::new(var("x")))),
+        );
+        assert!(nested.strict().is_ok());
+
+        let def = FunctionDef::new(sym("f"), vec![x(), x()], int.clone(), var("x"));
+        assert!(Command::DefineFun(def).strict().is_err());
+        let dec = FunctionDec::new(sym("g"), vec![x(), x()], int);
+        assert!(Command::DefineFunsRec(vec![(dec, var("x"))])
+            .strict()
+            .is_err());
+    }
+
+    #[test]
+    fn datatype_arity() {
+        let constructors = || {
+            vec![ConstructorDec {
+                name: sym("nil"),
+                selectors: vec![],
+            }]
+        };
+        let plain = DatatypeDec::new(constructors()).unwrap();
+        let parametric = DatatypeDec::parametric(vec![sym("T")], constructors()).unwrap();
+        let declare = |arity: u8, dec: &DatatypeDec| {
+            Command::DeclareDatatypes(vec![(SortDec(sym("L"), Numeral::from(arity)), dec.clone())])
+                .strict()
+        };
+
+        assert!(declare(0, &plain).is_ok());
+        assert!(declare(1, &parametric).is_ok());
+        let err = declare(1, &plain).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a datatype whose sort parameters disagree with the arity of its sort is not valid \
+             SMT-LIB"
+        );
+        assert!(declare(2, &parametric).is_err());
+    }
+}
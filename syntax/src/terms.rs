@@ -1,12 +1,47 @@
+use std::collections::HashSet;
 use std::fmt::Display;
 
+use super::attributes::{read_attributes, Attribute};
 use super::identifiers::Identifier;
-use super::lexicon::{Reserved, Symbol};
-use super::sexprs::{Sexpr, SpecialConstant};
+use super::lexicon::{Numeral, Reserved, Symbol, Text};
+use super::sexprs::{
+    self, Elements, FromSexprError, FromSexprErrorKind, Node, Production, Sexpr, SpecialConstant,
+};
 use super::sorts::Sort;
+use super::writer;
 
-#[derive(Debug, Clone)]
-pub struct QualifiedIdentifier(Identifier, Option<Sort>);
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QualifiedIdentifier<T = String>(pub(crate) Identifier<T>, pub(crate) Option<Sort<T>>);
+
+impl<T: Text> QualifiedIdentifier<T> {
+    pub fn new(identifier: Identifier<T>) -> Self {
+        QualifiedIdentifier(identifier, None)
+    }
+
+    /// The identifier with its sort given explicitly, i.e. `(as identifier sort)`.
+    pub fn with_sort(identifier: Identifier<T>, sort: Sort<T>) -> Self {
+        QualifiedIdentifier(identifier, Some(sort))
+    }
+
+    pub fn identifier(&self) -> &Identifier<T> {
+        &self.0
+    }
+
+    pub fn sort(&self) -> Option<&Sort<T>> {
+        self.1.as_ref()
+    }
+
+    pub fn into_owned(self) -> QualifiedIdentifier {
+        let QualifiedIdentifier(id, sort) = self;
+        QualifiedIdentifier(id.into_owned(), sort.map(Sort::into_owned))
+    }
+}
+
+impl<T: Text> From<Identifier<T>> for QualifiedIdentifier<T> {
+    fn from(value: Identifier<T>) -> Self {
+        QualifiedIdentifier::new(value)
+    }
+}
 impl From<QualifiedIdentifier> for super::sexprs::Sexpr {
     fn from(value: QualifiedIdentifier) -> Self {
         let QualifiedIdentifier(id, sort) = value;
@@ -19,19 +54,71 @@ impl From<QualifiedIdentifier> for super::sexprs::Sexpr {
         }
     }
 }
-impl Display for QualifiedIdentifier {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let QualifiedIdentifier(id, sort) = self;
 
-        match sort {
-            Some(sort) => write!(f, "(as {id} {sort})"),
-            None => write!(f, "{id}"),
+impl<T: Text> TryFrom<Sexpr<T>> for QualifiedIdentifier<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Sequence(seq) if matches!(seq.first(), Some(Sexpr::Reserved(Reserved::As))) => {
+                let mut elems = Elements::new(Production::QualifiedIdentifier, seq);
+                elems.next("`as`", |_| Ok(()))?;
+                let id = elems.next("identifier", Identifier::try_from)?;
+                let sort = elems.next("sort", Sort::try_from)?;
+                elems.finish()?;
+
+                Ok(QualifiedIdentifier(id, Some(sort)))
+            }
+            sexpr @ (Sexpr::Symbol(_) | Sexpr::Sequence(_)) => {
+                Identifier::try_from(sexpr).map(|id| QualifiedIdentifier(id, None))
+            }
+            other => Err(FromSexprError::unexpected(
+                Production::QualifiedIdentifier,
+                &other,
+            )),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct VarBinding(Symbol, Term);
+impl<T: Text> Display for QualifiedIdentifier<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VarBinding<T = String>(pub(crate) Symbol<T>, pub(crate) Term<T>);
+
+impl<T: Text> VarBinding<T> {
+    pub fn new(symbol: Symbol<T>, term: Term<T>) -> Self {
+        VarBinding(symbol, term)
+    }
+
+    pub fn symbol(&self) -> &Symbol<T> {
+        &self.0
+    }
+
+    pub fn term(&self) -> &Term<T> {
+        &self.1
+    }
+
+    pub fn into_owned(self) -> VarBinding {
+        VarBinding(self.0.into_owned(), self.1.into_owned())
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for VarBinding<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::VarBinding, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let term = elems.next("term", Term::try_from)?;
+        elems.finish()?;
+
+        Ok(VarBinding(name, term))
+    }
+}
 
 impl From<VarBinding> for Sexpr {
     fn from(value: VarBinding) -> Self {
@@ -41,8 +128,45 @@ impl From<VarBinding> for Sexpr {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct SortedVar(Symbol, Sort);
+impl<T: Text> Display for VarBinding<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SortedVar<T = String>(pub(crate) Symbol<T>, pub(crate) Sort<T>);
+
+impl<T: Text> SortedVar<T> {
+    pub fn new(symbol: Symbol<T>, sort: Sort<T>) -> Self {
+        SortedVar(symbol, sort)
+    }
+
+    pub fn symbol(&self) -> &Symbol<T> {
+        &self.0
+    }
+
+    pub fn sort(&self) -> &Sort<T> {
+        &self.1
+    }
+
+    pub fn into_owned(self) -> SortedVar {
+        SortedVar(self.0.into_owned(), self.1.into_owned())
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for SortedVar<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::SortedVar, sexpr)?;
+        let name = elems.next("symbol", Symbol::try_from)?;
+        let sort = elems.next("sort", Sort::try_from)?;
+        elems.finish()?;
+
+        Ok(SortedVar(name, sort))
+    }
+}
 
 impl From<SortedVar> for Sexpr {
     fn from(value: SortedVar) -> Self {
@@ -52,17 +176,418 @@ impl From<SortedVar> for Sexpr {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Term {
-    SpecialConstant(SpecialConstant),
-    QualifiedIdentifier(QualifiedIdentifier),
-    Application(QualifiedIdentifier, Vec<Term>),
-    Let(Vec<VarBinding>, Box<Term>),
-    Forall(Vec<SortedVar>, Box<Term>),
-    Exists(Vec<SortedVar>, Box<Term>),
-    // TODO:
-    // - match
-    // - ! (term attributes)
+impl<T: Text> Display for SortedVar<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateBinderError(Symbol);
+
+impl Display for DuplicateBinderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` is bound more than once", self.0)
+    }
+}
+
+impl std::error::Error for DuplicateBinderError {}
+
+/// The index of the first item that equals an earlier one.
+pub(crate) fn first_duplicate<T: PartialEq>(items: &[T]) -> Option<usize> {
+    (1..items.len()).find(|&i| items[..i].contains(&items[i]))
+}
+
+/// The pattern of a `match` case.
+///
+/// A pattern is either a single symbol, or a constructor applied to variables that are bound to
+/// its fields. A single symbol is a nullary constructor if the matched datatype has one by that
+/// name, and a variable that matches anything otherwise.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pattern<T = String> {
+    symbol: Symbol<T>,
+    binders: Vec<Symbol<T>>,
+}
+
+impl<T: Text> Pattern<T> {
+    /// A pattern that is a single symbol.
+    pub fn symbol(symbol: Symbol<T>) -> Self {
+        Pattern {
+            symbol,
+            binders: vec![],
+        }
+    }
+
+    /// A constructor applied to variables, which have to be distinct. Without binders, this is the
+    /// same as [`Pattern::symbol`].
+    pub fn constructor(
+        constructor: Symbol<T>,
+        binders: Vec<Symbol<T>>,
+    ) -> Result<Self, DuplicateBinderError> {
+        if let Some(i) = first_duplicate(&binders) {
+            return Err(DuplicateBinderError(binders[i].clone().into_owned()));
+        }
+
+        Ok(Pattern {
+            symbol: constructor,
+            binders,
+        })
+    }
+
+    /// The single symbol, or the constructor.
+    pub fn head(&self) -> &Symbol<T> {
+        &self.symbol
+    }
+
+    /// The variables bound by a constructor pattern.
+    pub fn binders(&self) -> &[Symbol<T>] {
+        &self.binders
+    }
+
+    pub fn into_owned(self) -> Pattern {
+        Pattern {
+            symbol: self.symbol.into_owned(),
+            binders: sexprs::into_owned(self.binders, Symbol::into_owned),
+        }
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Pattern<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        match sexpr {
+            Sexpr::Symbol(symbol) => Ok(Pattern::symbol(symbol)),
+            Sexpr::Sequence(seq) => {
+                let mut elems = Elements::new(Production::Pattern, seq);
+                let constructor = elems.next("constructor", Symbol::try_from)?;
+                let binders = elems.rest_nonempty("variable", Symbol::try_from)?;
+
+                if let Some(i) = first_duplicate(&binders) {
+                    let name = binders[i].symbol().to_string();
+                    return Err(FromSexprError::new(
+                        Production::Pattern,
+                        FromSexprErrorKind::DuplicateBinder(name),
+                    )
+                    .at(i + 1));
+                }
+
+                Ok(Pattern {
+                    symbol: constructor,
+                    binders,
+                })
+            }
+            other => Err(FromSexprError::unexpected(Production::Pattern, &other)),
+        }
+    }
+}
+
+impl From<Pattern> for Sexpr {
+    fn from(value: Pattern) -> Self {
+        let Pattern { symbol, binders } = value;
+        if binders.is_empty() {
+            symbol.into()
+        } else {
+            let mut elems = vec![symbol.into()];
+            elems.extend(binders.into_iter().map(Sexpr::from));
+            Sexpr::Sequence(elems)
+        }
+    }
+}
+
+impl<T: Text> Display for Pattern<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MatchCase<T = String>(pub(crate) Pattern<T>, pub(crate) Term<T>);
+
+impl<T: Text> MatchCase<T> {
+    pub fn new(pattern: Pattern<T>, term: Term<T>) -> Self {
+        MatchCase(pattern, term)
+    }
+
+    pub fn pattern(&self) -> &Pattern<T> {
+        &self.0
+    }
+
+    pub fn term(&self) -> &Term<T> {
+        &self.1
+    }
+
+    pub fn into_owned(self) -> MatchCase {
+        MatchCase(self.0.into_owned(), self.1.into_owned())
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for MatchCase<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let mut elems = Elements::of(Production::MatchCase, sexpr)?;
+        let pattern = elems.next("pattern", Pattern::try_from)?;
+        let term = elems.next("term", Term::try_from)?;
+        elems.finish()?;
+
+        Ok(MatchCase(pattern, term))
+    }
+}
+
+impl From<MatchCase> for Sexpr {
+    fn from(value: MatchCase) -> Self {
+        let MatchCase(pattern, term) = value;
+
+        Sexpr::Sequence(vec![pattern.into(), term.into()])
+    }
+}
+
+impl<T: Text> Display for MatchCase<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Term<T = String> {
+    SpecialConstant(SpecialConstant<T>),
+    QualifiedIdentifier(QualifiedIdentifier<T>),
+    Application(QualifiedIdentifier<T>, Vec<Term<T>>),
+    Let(Vec<VarBinding<T>>, Box<Term<T>>),
+    Forall(Vec<SortedVar<T>>, Box<Term<T>>),
+    Exists(Vec<SortedVar<T>>, Box<Term<T>>),
+    Match(Box<Term<T>>, Vec<MatchCase<T>>),
+    Annotated(Box<Term<T>>, Vec<Attribute<T>>),
+    /// `(lambda (vars+) body)`, since SMT-LIB 2.7.
+    Lambda(Vec<SortedVar<T>>, Box<Term<T>>),
+    /// The application of a term that is not a function symbol, e.g. of a lambda, since SMT-LIB
+    /// 2.7. Applications of function symbols are [`Term::Application`], whether they are partial
+    /// or not.
+    Apply(Box<Term<T>>, Vec<Term<T>>),
+}
+
+impl<T: Text> Term<T> {
+    pub fn into_owned(self) -> Term {
+        let terms = |terms| sexprs::into_owned(terms, Term::into_owned);
+        let vars = |vars| sexprs::into_owned(vars, SortedVar::into_owned);
+        let body = |body: Box<Term<T>>| Box::new(body.into_owned());
+        match self {
+            Term::SpecialConstant(sc) => Term::SpecialConstant(sc.into_owned()),
+            Term::QualifiedIdentifier(qi) => Term::QualifiedIdentifier(qi.into_owned()),
+            Term::Application(name, args) => Term::Application(name.into_owned(), terms(args)),
+            Term::Let(bindings, term) => Term::Let(
+                sexprs::into_owned(bindings, VarBinding::into_owned),
+                body(term),
+            ),
+            Term::Forall(quants, term) => Term::Forall(vars(quants), body(term)),
+            Term::Exists(quants, term) => Term::Exists(vars(quants), body(term)),
+            Term::Match(term, cases) => {
+                Term::Match(body(term), sexprs::into_owned(cases, MatchCase::into_owned))
+            }
+            Term::Annotated(term, attributes) => Term::Annotated(
+                body(term),
+                sexprs::into_owned(attributes, Attribute::into_owned),
+            ),
+            Term::Lambda(quants, term) => Term::Lambda(vars(quants), body(term)),
+            Term::Apply(fun, args) => Term::Apply(body(fun), terms(args)),
+        }
+    }
+}
+
+impl Term {
+    /// Annotates the term with `:named`, or adds the name to its annotations.
+    pub fn named(self, name: Symbol) -> Term {
+        self.annotate(Attribute::Named(name))
+    }
+
+    /// Adds an attribute to the annotations of the term, or annotates it with `!`.
+    pub fn annotate(self, attribute: Attribute) -> Term {
+        match self {
+            Term::Annotated(term, mut attributes) => {
+                attributes.push(attribute);
+                Term::Annotated(term, attributes)
+            }
+            term => Term::Annotated(Box::new(term), vec![attribute]),
+        }
+    }
+
+    /// The name given to the term by a `:named` annotation.
+    pub fn name(&self) -> Option<&Symbol> {
+        match self {
+            Term::Annotated(_, attributes) => attributes.iter().find_map(|attr| match attr {
+                Attribute::Named(name) => Some(name),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the terms are equal up to the names of bound variables, e.g.
+    /// `(forall ((x Int)) (p x))` and `(forall ((y Int)) (p y))`. Variables are bound by `let`,
+    /// the quantifiers, `lambda`, and the binders of constructor patterns in `match`. A `match`
+    /// pattern that is a single symbol can be a nullary constructor or a variable, so such
+    /// patterns only match if they are the same symbol; use
+    /// [`Term::alpha_eq_with_constructors`] to rename them too.
+    pub fn alpha_eq(&self, other: &Term) -> bool {
+        AlphaEq::default().terms(self, other)
+    }
+
+    /// Like [`Term::alpha_eq`], but with the constructors of the datatypes in scope, so that a
+    /// `match` pattern that is a single symbol other than a constructor is known to be a variable,
+    /// e.g. `(match l ((x (f x))))` and `(match l ((y (f y))))` are equal.
+    pub fn alpha_eq_with_constructors(&self, other: &Term, constructors: &HashSet<Symbol>) -> bool {
+        AlphaEq {
+            constructors: Some(constructors),
+            ..AlphaEq::default()
+        }
+        .terms(self, other)
+    }
+
+    /// Writes the term as [`Display`] prints it, e.g. straight to a file. The output is buffered,
+    /// so the writer does not need to be.
+    pub fn write_to(&self, writer: impl std::io::Write) -> std::io::Result<()> {
+        writer::write_io(self, writer)
+    }
+}
+
+/// The variables in scope on either side of an alpha-equivalence check, innermost last.
+#[derive(Default)]
+struct AlphaEq<'a> {
+    left: Vec<&'a Symbol>,
+    right: Vec<&'a Symbol>,
+    /// The constructors of the datatypes, if known.
+    constructors: Option<&'a HashSet<Symbol>>,
+}
+
+impl<'a> AlphaEq<'a> {
+    /// Compares the terms with the given variables bound in addition.
+    fn bound(
+        &mut self,
+        left: impl IntoIterator<Item = &'a Symbol>,
+        right: impl IntoIterator<Item = &'a Symbol>,
+        compare: impl FnOnce(&mut Self) -> bool,
+    ) -> bool {
+        let (left_len, right_len) = (self.left.len(), self.right.len());
+        self.left.extend(left);
+        self.right.extend(right);
+        let equal = self.left.len() - left_len == self.right.len() - right_len && compare(self);
+        self.left.truncate(left_len);
+        self.right.truncate(right_len);
+        equal
+    }
+
+    fn qualified_identifiers(&self, a: &QualifiedIdentifier, b: &QualifiedIdentifier) -> bool {
+        let (QualifiedIdentifier(a_id, a_sort), QualifiedIdentifier(b_id, b_sort)) = (a, b);
+        if a_sort != b_sort {
+            return false;
+        }
+        if !a_id.indexes.is_empty() || !b_id.indexes.is_empty() {
+            return a_id == b_id;
+        }
+
+        let a_var = self.left.iter().rposition(|v| **v == a_id.symbol);
+        let b_var = self.right.iter().rposition(|v| **v == b_id.symbol);
+        match (a_var, b_var) {
+            (None, None) => a_id.symbol == b_id.symbol,
+            (a_var, b_var) => a_var == b_var,
+        }
+    }
+
+    fn all(&mut self, a: &'a [Term], b: &'a [Term]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.terms(a, b))
+    }
+
+    fn sorted_vars(
+        &mut self,
+        a: &'a [SortedVar],
+        b: &'a [SortedVar],
+        a_body: &'a Term,
+        b_body: &'a Term,
+    ) -> bool {
+        a.iter().map(|v| &v.1).eq(b.iter().map(|v| &v.1))
+            && self.bound(a.iter().map(|v| &v.0), b.iter().map(|v| &v.0), |this| {
+                this.terms(a_body, b_body)
+            })
+    }
+
+    fn terms(&mut self, a: &'a Term, b: &'a Term) -> bool {
+        match (a, b) {
+            (Term::SpecialConstant(a), Term::SpecialConstant(b)) => a == b,
+            (Term::QualifiedIdentifier(a), Term::QualifiedIdentifier(b)) => {
+                self.qualified_identifiers(a, b)
+            }
+            (Term::Application(a_fun, a_args), Term::Application(b_fun, b_args)) => {
+                self.qualified_identifiers(a_fun, b_fun) && self.all(a_args, b_args)
+            }
+            (Term::Let(a_bindings, a_body), Term::Let(b_bindings, b_body)) => {
+                a_bindings.len() == b_bindings.len()
+                    && a_bindings
+                        .iter()
+                        .zip(b_bindings)
+                        .all(|(a, b)| self.terms(&a.1, &b.1))
+                    && self.bound(
+                        a_bindings.iter().map(|b| &b.0),
+                        b_bindings.iter().map(|b| &b.0),
+                        |this| this.terms(a_body, b_body),
+                    )
+            }
+            (Term::Forall(a_vars, a_body), Term::Forall(b_vars, b_body))
+            | (Term::Exists(a_vars, a_body), Term::Exists(b_vars, b_body))
+            | (Term::Lambda(a_vars, a_body), Term::Lambda(b_vars, b_body)) => {
+                self.sorted_vars(a_vars, b_vars, a_body, b_body)
+            }
+            (Term::Match(a_term, a_cases), Term::Match(b_term, b_cases)) => {
+                self.terms(a_term, b_term)
+                    && a_cases.len() == b_cases.len()
+                    && a_cases.iter().zip(b_cases).all(|(a, b)| {
+                        let (MatchCase(a_pattern, a_body), MatchCase(b_pattern, b_body)) = (a, b);
+                        let (a_symbol, b_symbol) = (&a_pattern.symbol, &b_pattern.symbol);
+                        match (a_pattern.binders.as_slice(), b_pattern.binders.as_slice()) {
+                            // a variable is bound like the binders of a constructor pattern, and
+                            // binding the same symbol on both sides is right for a constructor too
+                            ([], []) => {
+                                let variables = match self.constructors {
+                                    Some(constructors) => {
+                                        !constructors.contains(a_symbol)
+                                            && !constructors.contains(b_symbol)
+                                    }
+                                    None => false,
+                                };
+                                (variables || a_symbol == b_symbol)
+                                    && self.bound([a_symbol], [b_symbol], |this| {
+                                        this.terms(a_body, b_body)
+                                    })
+                            }
+                            (a_binders, b_binders) => {
+                                a_symbol == b_symbol
+                                    && self.bound(a_binders, b_binders, |this| {
+                                        this.terms(a_body, b_body)
+                                    })
+                            }
+                        }
+                    })
+            }
+            (Term::Annotated(a_term, a_attributes), Term::Annotated(b_term, b_attributes)) => {
+                self.terms(a_term, b_term)
+                    && a_attributes.len() == b_attributes.len()
+                    && a_attributes
+                        .iter()
+                        .zip(b_attributes)
+                        .all(|pair| match pair {
+                            (Attribute::Pattern(a), Attribute::Pattern(b)) => {
+                                self.all(a.terms(), b.terms())
+                            }
+                            (Attribute::NoPattern(a), Attribute::NoPattern(b)) => self.terms(a, b),
+                            (a, b) => a == b,
+                        })
+            }
+            (Term::Apply(a_fun, a_args), Term::Apply(b_fun, b_args)) => {
+                self.terms(a_fun, b_fun) && self.all(a_args, b_args)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl From<Term> for Sexpr {
@@ -91,6 +616,281 @@ impl From<Term> for Sexpr {
                 Sexpr::Sequence(quants.into_iter().map(|e| e.into()).collect()),
                 (*body).into(),
             ]),
+            Term::Match(term, cases) => Sexpr::Sequence(vec![
+                Reserved::Match.into(),
+                (*term).into(),
+                Sexpr::Sequence(cases.into_iter().map(|e| e.into()).collect()),
+            ]),
+            Term::Lambda(vars, body) => Sexpr::Sequence(vec![
+                lambda().into(),
+                Sexpr::Sequence(vars.into_iter().map(|e| e.into()).collect()),
+                (*body).into(),
+            ]),
+            Term::Apply(fun, args) => Sexpr::Sequence(
+                std::iter::once((*fun).into())
+                    .chain(args.into_iter().map(|e| e.into()))
+                    .collect(),
+            ),
+            Term::Annotated(term, attributes) => Sexpr::Sequence(
+                [Reserved::Bang.into(), (*term).into()]
+                    .into_iter()
+                    .chain(attributes.into_iter().flat_map(Attribute::into_sexprs))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Prints the term as its s-expression would be printed, without building it.
+impl<T: Text> Display for Term<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writer::display(self, f)
+    }
+}
+
+/// `lambda` is only reserved since SMT-LIB 2.7, so it is read as a symbol and recognized by
+/// position.
+pub(crate) fn lambda() -> Symbol {
+    Symbol::new("lambda".to_string()).expect("`lambda` is a valid symbol")
+}
+
+/// Whether the elements of a list start like a lambda term, i.e. with `lambda` and a list of
+/// lists. Anything else is an application of a function named `lambda`, as in SMT-LIB 2.6, e.g.
+/// `(lambda (f x))`.
+pub(crate) fn is_lambda<S: Node>(seq: &[S]) -> bool {
+    match seq {
+        [head, vars, ..] if head.as_symbol() == Some("lambda") => vars
+            .as_list()
+            .is_some_and(|vars| vars.iter().all(|var| var.as_list().is_some())),
+        _ => false,
+    }
+}
+
+/// Whether a term starting with `head` applies a term rather than a function symbol, i.e. `head`
+/// is a list but not an indexed or qualified identifier.
+pub(crate) fn applies_term<S: Node>(head: &S) -> bool {
+    match head.as_list() {
+        Some(seq) => !matches!(
+            seq.first().and_then(Node::as_reserved),
+            Some(Reserved::Underscore | Reserved::As)
+        ),
+        None => false,
+    }
+}
+
+/// Negative values become an application of `-`, e.g. `(- 5)`, since numerals are natural numbers.
+impl From<i64> for Term {
+    fn from(value: i64) -> Self {
+        let numeral = Term::SpecialConstant(Numeral::from(value.unsigned_abs()).into());
+        if value < 0 {
+            let minus = Symbol::new("-".to_string()).expect("`-` is a valid symbol");
+            let minus = QualifiedIdentifier(
+                Identifier {
+                    symbol: minus,
+                    indexes: vec![],
+                },
+                None,
+            );
+            Term::Application(minus, vec![numeral])
+        } else {
+            numeral
         }
     }
 }
+
+impl From<i64> for Sexpr {
+    fn from(value: i64) -> Self {
+        Term::from(value).into()
+    }
+}
+
+impl<T: Text> TryFrom<Sexpr<T>> for Term<T> {
+    type Error = FromSexprError;
+
+    fn try_from(sexpr: Sexpr<T>) -> Result<Self, Self::Error> {
+        let seq = match sexpr {
+            Sexpr::SpecialConstant(sc) => return Ok(Term::SpecialConstant(sc)),
+            sexpr @ Sexpr::Symbol(_) => {
+                return QualifiedIdentifier::try_from(sexpr).map(Term::QualifiedIdentifier)
+            }
+            Sexpr::Sequence(seq) if !seq.is_empty() => seq,
+            other => return Err(FromSexprError::unexpected(Production::Term, &other)),
+        };
+
+        let is_lambda = is_lambda(&seq);
+        let mut elems = Elements::new(Production::Term, seq);
+        let term = match elems.peek() {
+            Some(Sexpr::Reserved(Reserved::Underscore | Reserved::As)) => {
+                let seq = elems.rest(Ok)?;
+                QualifiedIdentifier::try_from(Sexpr::Sequence(seq))
+                    .map(Term::QualifiedIdentifier)?
+            }
+            Some(Sexpr::Reserved(Reserved::Let)) => {
+                elems.next("`let`", |_| Ok(()))?;
+                let bindings =
+                    elems.next_nonempty_list("variable binding", VarBinding::try_from)?;
+                let body = elems.next("term", Term::try_from)?;
+                elems.finish()?;
+                Term::Let(bindings, Box::new(body))
+            }
+            Some(Sexpr::Reserved(quantifier @ (Reserved::Forall | Reserved::Exists))) => {
+                let is_forall = matches!(quantifier, Reserved::Forall);
+                elems.next("quantifier", |_| Ok(()))?;
+                let vars = elems.next_nonempty_list("sorted variable", SortedVar::try_from)?;
+                let body = Box::new(elems.next("term", Term::try_from)?);
+                elems.finish()?;
+                if is_forall {
+                    Term::Forall(vars, body)
+                } else {
+                    Term::Exists(vars, body)
+                }
+            }
+            Some(Sexpr::Reserved(Reserved::Match)) => {
+                elems.next("`match`", |_| Ok(()))?;
+                let term = elems.next("term", Term::try_from)?;
+                let cases = elems.next_nonempty_list("match case", MatchCase::try_from)?;
+                elems.finish()?;
+                Term::Match(Box::new(term), cases)
+            }
+            _ if is_lambda => {
+                elems.next("`lambda`", |_| Ok(()))?;
+                let vars = elems.next_nonempty_list("sorted variable", SortedVar::try_from)?;
+                let body = elems.next("term", Term::try_from)?;
+                elems.finish()?;
+                Term::Lambda(vars, Box::new(body))
+            }
+            Some(head) if applies_term(head) => {
+                let fun = elems.next("function", Term::try_from)?;
+                let args = elems.rest_nonempty("argument", Term::try_from)?;
+                Term::Apply(Box::new(fun), args)
+            }
+            Some(Sexpr::Reserved(Reserved::Bang)) => {
+                elems.next("`!`", |_| Ok(()))?;
+                let term = elems.next("term", Term::try_from)?;
+                let attributes = read_attributes(elems)?;
+                Term::Annotated(Box::new(term), attributes)
+            }
+            _ => {
+                let name = elems.next("function", QualifiedIdentifier::try_from)?;
+                let args = elems.rest_nonempty("argument", Term::try_from)?;
+                Term::Application(name, args)
+            }
+        };
+
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_term;
+
+    #[test]
+    fn match_terms() {
+        let input = "(match l ((nil 0) ((cons h t) h) (x 1)))";
+        let term = parse_term(input).unwrap();
+        assert!(matches!(&term, Term::Match(_, cases) if cases.len() == 3));
+        assert_eq!(term.to_string(), input);
+        assert_eq!(Sexpr::from(term).to_string(), input);
+
+        let borrowed = crate::borrowed::parse_term(input).unwrap();
+        assert_eq!(Sexpr::from(borrowed.into_owned()).to_string(), input);
+
+        let err = parse_term("(match l (((cons h h) h)))").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:20: `h` is bound more than once in pattern"
+        );
+
+        let cons = Symbol::new("cons".to_string()).unwrap();
+        let h = Symbol::new("h".to_string()).unwrap();
+        assert!(Pattern::constructor(cons, vec![h.clone(), h]).is_err());
+    }
+
+    #[test]
+    fn term_equality() {
+        let term = |input| parse_term(input).unwrap();
+        let alpha_eq = |a, b| term(a).alpha_eq(&term(b));
+
+        assert!(alpha_eq(
+            "(forall ((x Int)) (p x))",
+            "(forall ((y Int)) (p y))"
+        ));
+        assert!(!alpha_eq(
+            "(forall ((x Int)) (p x))",
+            "(forall ((y Real)) (p y))"
+        ));
+        assert!(!alpha_eq(
+            "(forall ((x Int)) (p z))",
+            "(forall ((y Int)) (p w))"
+        ));
+        assert!(!alpha_eq(
+            "(forall ((x Int)) (p x))",
+            "(forall ((y Int)) (p x))"
+        ));
+        assert!(alpha_eq(
+            "(let ((x 1) (y x)) (+ x y))",
+            "(let ((a 1) (b x)) (+ a b))"
+        ));
+        assert!(!alpha_eq(
+            "(let ((x 1)) (let ((y 2)) x))",
+            "(let ((y 1)) (let ((x 2)) x))"
+        ));
+        assert!(alpha_eq(
+            "(match l (((cons h t) h) (nil 0)))",
+            "(match l (((cons a b) a) (nil 0)))"
+        ));
+        // single symbols may be constructors, which can not be renamed
+        assert!(!alpha_eq("(match l ((x (f x))))", "(match l ((y (f y))))"));
+        assert!(!alpha_eq(
+            "(match c ((red 1) (green 2)))",
+            "(match c ((green 1) (red 2)))"
+        ));
+        assert!(alpha_eq(
+            "(match c ((red 1) (green 2)))",
+            "(match c ((red 1) (green 2)))"
+        ));
+        let constructors: HashSet<Symbol> = ["red", "green", "nil", "cons"]
+            .into_iter()
+            .map(|name| Symbol::new_str(name).unwrap())
+            .collect();
+        let alpha_eq_with = |a, b| term(a).alpha_eq_with_constructors(&term(b), &constructors);
+        assert!(alpha_eq_with(
+            "(match l ((x (f x))))",
+            "(match l ((y (f y))))"
+        ));
+        assert!(!alpha_eq_with(
+            "(match l ((x (f x))))",
+            "(match l ((y (f x))))"
+        ));
+        assert!(!alpha_eq_with(
+            "(match c ((red 1) (green 2)))",
+            "(match c ((green 1) (red 2)))"
+        ));
+        assert!(!alpha_eq_with("(match l ((nil 0)))", "(match l ((x 0)))"));
+        assert!(alpha_eq_with(
+            "(match l (((cons h t) t) (nil l)))",
+            "(match l (((cons a b) b) (nil l)))"
+        ));
+        assert!(alpha_eq(
+            "(forall ((x Int)) (match l ((x x))))",
+            "(forall ((y Int)) (match l ((x x))))"
+        ));
+        assert!(alpha_eq(
+            "(! (exists ((x Int)) (! (f x) :pattern ((g x)))) :named a)",
+            "(! (exists ((y Int)) (! (f y) :pattern ((g y)))) :named a)"
+        ));
+        assert!(alpha_eq(
+            "((lambda ((x Int)) x) 1)",
+            "((lambda ((y Int)) y) 1)"
+        ));
+
+        let a = term("(forall ((x Int)) (p x))");
+        let b = term("(forall ((y Int)) (p y))");
+        assert_ne!(a, b);
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Less);
+        let set: std::collections::HashSet<_> = [a.clone(), a, b].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+}
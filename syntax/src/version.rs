@@ -0,0 +1,376 @@
+//! Versions of the SMT-LIB standard.
+//!
+//! The syntax types cover SMT-LIB 2.7, which adds `define-const`, `declare-sort-parameter`, the
+//! `->` sort, `lambda` and the application of arbitrary terms to 2.6. Solvers that only know 2.6
+//! reject these, so scripts meant for them should be passed through [`Script::for_version`]
+//! before printing. It rewrites what has an equivalent in the older version and fails on the
+//! rest.
+//!
+//! The parser reads 2.7 unless told otherwise:
+//! [`parse_script_with_version`](crate::parser::parse_script_with_version) and
+//! [`parse_command_with_version`](crate::parser::parse_command_with_version) reject what the
+//! given version does not have, including `define-const`.
+//!
+//! ```
+//! use smtlib_syntax::parser::parse_script;
+//! use smtlib_syntax::sexprs::Sexpr;
+//! use smtlib_syntax::version::Version;
+//!
+//! let script = parse_script("(define-const x Int 5)").unwrap();
+//! let script = script.for_version(Version::V2_6).unwrap();
+//! assert_eq!(Sexpr::from(script.0[0].clone()).to_string(), "(define-fun x () Int 5)");
+//!
+//! let script = parse_script("(assert ((lambda ((x Int)) (> x 0)) 1))").unwrap();
+//! assert!(script.for_version(Version::V2_6).is_err());
+//! ```
+
+use std::fmt::Display;
+
+use super::attributes::Attribute;
+use super::scripts::{Command, DatatypeDec, FunctionDef, Script};
+use super::sorts::Sort;
+use super::terms::{QualifiedIdentifier, Term};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    V2_6,
+    V2_7,
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Version::V2_6 => write!(f, "2.6"),
+            Version::V2_7 => write!(f, "2.7"),
+        }
+    }
+}
+
+/// A construct that the targeted version does not have, and that can not be rewritten into one
+/// it has.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionError {
+    construct: &'static str,
+    version: Version,
+}
+
+impl Display for VersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is not part of SMT-LIB {}",
+            self.construct, self.version
+        )
+    }
+}
+
+impl std::error::Error for VersionError {}
+
+fn require(version: Version, since: Version, construct: &'static str) -> Result<(), VersionError> {
+    if version >= since {
+        Ok(())
+    } else {
+        Err(VersionError { construct, version })
+    }
+}
+
+fn check_sort(sort: &Sort, version: Version) -> Result<(), VersionError> {
+    if sort.is_function() {
+        require(version, Version::V2_7, "the `->` sort")?;
+    }
+
+    check_all(&sort.parameters, version, check_sort)
+}
+
+fn check_all<T>(
+    items: &[T],
+    version: Version,
+    check: impl Fn(&T, Version) -> Result<(), VersionError>,
+) -> Result<(), VersionError> {
+    items.iter().try_for_each(|item| check(item, version))
+}
+
+fn check_qualified_identifier(
+    qi: &QualifiedIdentifier,
+    version: Version,
+) -> Result<(), VersionError> {
+    match &qi.1 {
+        Some(sort) => check_sort(sort, version),
+        None => Ok(()),
+    }
+}
+
+fn check_term(term: &Term, version: Version) -> Result<(), VersionError> {
+    match term {
+        Term::SpecialConstant(_) => Ok(()),
+        Term::QualifiedIdentifier(qi) => check_qualified_identifier(qi, version),
+        Term::Application(qi, args) => {
+            check_qualified_identifier(qi, version)?;
+            check_all(args, version, check_term)
+        }
+        Term::Let(bindings, body) => {
+            check_all(bindings, version, |binding, version| {
+                check_term(&binding.1, version)
+            })?;
+            check_term(body, version)
+        }
+        Term::Forall(vars, body) | Term::Exists(vars, body) => {
+            check_all(vars, version, |var, version| check_sort(&var.1, version))?;
+            check_term(body, version)
+        }
+        Term::Match(term, cases) => {
+            check_term(term, version)?;
+            check_all(cases, version, |case, version| check_term(&case.1, version))
+        }
+        Term::Annotated(term, attributes) => {
+            check_term(term, version)?;
+            check_all(attributes, version, |attribute, version| match attribute {
+                Attribute::Pattern(terms) => check_all(terms.terms(), version, check_term),
+                Attribute::NoPattern(term) => check_term(term, version),
+                Attribute::Named(_) | Attribute::Other(_, _) => Ok(()),
+            })
+        }
+        Term::Lambda(_, _) => require(version, Version::V2_7, "`lambda`"),
+        Term::Apply(_, _) => require(version, Version::V2_7, "the application of a term"),
+    }
+}
+
+fn check_function_def(def: &FunctionDef, version: Version) -> Result<(), VersionError> {
+    check_all(&def.args, version, |var, version| {
+        check_sort(&var.1, version)
+    })?;
+    check_sort(&def.sort, version)?;
+    check_term(&def.body, version)
+}
+
+fn check_datatype_dec(dec: &DatatypeDec, version: Version) -> Result<(), VersionError> {
+    check_all(&dec.constructors, version, |constructor, version| {
+        check_all(&constructor.selectors, version, |selector, version| {
+            check_sort(&selector.sort, version)
+        })
+    })
+}
+
+/// Fails if the command uses a construct that `version` does not have.
+pub(crate) fn check_command(command: &Command, version: Version) -> Result<(), VersionError> {
+    match command {
+        Command::Assert(term) => check_term(term, version)?,
+        Command::DeclareConst(_, sort) => check_sort(sort, version)?,
+        Command::DeclareDatatype(_, dec) => check_datatype_dec(dec, version)?,
+        Command::DeclareDatatypes(entries) => check_all(entries, version, |(_, dec), version| {
+            check_datatype_dec(dec, version)
+        })?,
+        Command::DeclareFun(_, args, sort) => {
+            check_all(args, version, check_sort)?;
+            check_sort(sort, version)?;
+        }
+        Command::DeclareSortParameter(_) => {
+            require(version, Version::V2_7, "`declare-sort-parameter`")?
+        }
+        Command::DefineConst(_, sort, term) => {
+            require(version, Version::V2_7, "`define-const`")?;
+            check_sort(sort, version)?;
+            check_term(term, version)?;
+        }
+        Command::DefineFun(def) | Command::DefineFunRec(def) => check_function_def(def, version)?,
+        Command::DefineFunsRec(entries) => {
+            for (dec, body) in entries {
+                check_all(&dec.args, version, |var, version| {
+                    check_sort(&var.1, version)
+                })?;
+                check_sort(&dec.sort, version)?;
+                check_term(body, version)?;
+            }
+        }
+        Command::DefineSort(_, _, sort) => check_sort(sort, version)?,
+        Command::GetValue(terms) => check_all(terms, version, check_term)?,
+        Command::CheckSat
+        | Command::CheckSatAssuming(_)
+        | Command::DeclareSort(_, _)
+        | Command::Echo(_)
+        | Command::Exit
+        | Command::GetAssertions
+        | Command::GetAssignment
+        | Command::GetInfo(_)
+        | Command::GetModel
+        | Command::GetOption(_)
+        | Command::GetProof
+        | Command::GetUnsatAssumptions
+        | Command::GetUnsatCore
+        | Command::Pop(_)
+        | Command::Push(_)
+        | Command::Reset
+        | Command::ResetAssertions
+        | Command::SetInfo(_)
+        | Command::SetLogic(_)
+        | Command::SetOption(_) => {}
+    }
+
+    Ok(())
+}
+
+impl Command {
+    /// Makes the command fit for a solver that speaks `version`. `define-const` is rewritten into
+    /// `define-fun` for SMT-LIB 2.6, other constructs the version lacks are an error.
+    pub fn for_version(self, version: Version) -> Result<Command, VersionError> {
+        let command = match self {
+            Command::DefineConst(name, sort, body) if version < Version::V2_7 => {
+                Command::DefineFun(FunctionDef {
+                    name,
+                    args: vec![],
+                    sort,
+                    body,
+                })
+            }
+            command => command,
+        };
+        check_command(&command, version)?;
+
+        Ok(command)
+    }
+}
+
+impl Script {
+    /// Makes every command fit for a solver that speaks `version`, see [`Command::for_version`].
+    pub fn for_version(self, version: Version) -> Result<Script, VersionError> {
+        self.0
+            .into_iter()
+            .map(|command| command.for_version(version))
+            .collect::<Result<_, _>>()
+            .map(Script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{
+        parse_command, parse_command_with_version, parse_script, parse_script_with_version,
+        parse_sort, parse_term,
+    };
+
+    #[test]
+    fn smtlib_2_7() {
+        let sort = parse_sort("(-> Int Int Bool)").unwrap();
+        assert!(sort.is_function());
+        let int = parse_sort("Int").unwrap();
+        let bool = parse_sort("Bool").unwrap();
+        let arrow = Sort::function(vec![int.clone(), int.clone()], bool);
+        assert_eq!(arrow.to_string(), sort.to_string());
+        assert!(!int.is_function());
+
+        let term = parse_term("((lambda ((x Int)) x) 1)").unwrap();
+        assert!(matches!(&term, Term::Apply(fun, _) if matches!(**fun, Term::Lambda(_, _))));
+        let borrowed = crate::borrowed::parse_term("((lambda ((x Int)) x) 1)").unwrap();
+        assert!(matches!(borrowed, crate::borrowed::Term::Apply(_, _)));
+
+        // qualified and indexed identifiers are still function symbols
+        let term = parse_term("((as f (-> Int Int)) ((_ extract 1 0) x))").unwrap();
+        assert!(matches!(term, Term::Application(_, _)));
+
+        // `lambda` is not reserved in SMT-LIB 2.6, so it can still name a function
+        let input = "(lambda (f x))";
+        let term = parse_term(input).unwrap();
+        assert!(matches!(&term, Term::Application(qi, args)
+            if qi.identifier().symbol().symbol() == "lambda" && args.len() == 1));
+        assert_eq!(term.to_string(), input);
+        let borrowed = crate::borrowed::parse_term(input).unwrap();
+        assert!(matches!(borrowed, crate::borrowed::Term::Application(_, _)));
+        assert!(matches!(
+            parse_term("(lambda x y)").unwrap(),
+            Term::Application(_, _)
+        ));
+
+        let err = parse_term("(lambda () x)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:9: incomplete term: missing sorted variable"
+        );
+
+        let err = parse_script("(declare-sort-parameter X) (declare-const f (-> Int Int))")
+            .unwrap()
+            .for_version(Version::V2_6)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`declare-sort-parameter` is not part of SMT-LIB 2.6"
+        );
+        let command = parse_command("(declare-const f (-> Int Int))").unwrap();
+        assert!(command.clone().for_version(Version::V2_6).is_err());
+        assert!(command.for_version(Version::V2_7).is_ok());
+    }
+
+    #[test]
+    fn for_version_2_6() {
+        let command =
+            parse_command("(define-const k (Array Int Int) ((as const (Array Int Int)) 0))")
+                .unwrap()
+                .for_version(Version::V2_6)
+                .unwrap();
+        assert_eq!(
+            command.to_string(),
+            "(define-fun k () (Array Int Int) ((as const (Array Int Int)) 0))"
+        );
+        let command = parse_command("(define-const k Int 0)").unwrap();
+        assert_eq!(command.clone().for_version(Version::V2_7), Ok(command));
+
+        let error = |input| {
+            parse_command(input)
+                .unwrap()
+                .for_version(Version::V2_6)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("(assert (forall ((x Int)) (= (lambda ((y Int)) (> y x)) p)))"),
+            "`lambda` is not part of SMT-LIB 2.6"
+        );
+        assert_eq!(
+            error("(assert ((f 1) 2))"),
+            "the application of a term is not part of SMT-LIB 2.6"
+        );
+        assert_eq!(
+            error("(declare-fun g ((-> Int Int)) Int)"),
+            "the `->` sort is not part of SMT-LIB 2.6"
+        );
+        assert_eq!(
+            error("(define-const h (-> Int Int) f)"),
+            "the `->` sort is not part of SMT-LIB 2.6"
+        );
+        assert_eq!(
+            error("(declare-sort-parameter X)"),
+            "`declare-sort-parameter` is not part of SMT-LIB 2.6"
+        );
+
+        let input = "(set-logic ALL)
+(declare-datatypes ((List 1)) ((par (T) ((nil) (cons (head T) (tail (List T)))))))
+(define-fun-rec len ((l (List Int))) Int (match l ((nil 0) ((cons h t) (+ 1 (len t))))))
+(assert (! (forall ((x Int)) (let ((y (lambda x))) (> y 0))) :named a))
+(check-sat)
+";
+        let script = parse_script(input).unwrap();
+        assert_eq!(
+            script.clone().for_version(Version::V2_6),
+            Ok(script.clone())
+        );
+        assert_eq!(script.to_string(), input);
+    }
+
+    #[test]
+    fn reading_versions() {
+        let input = "(declare-const x Int)\n(assert (= (lambda ((y Int)) (> y x)) p))";
+        let err = parse_script_with_version(input, Version::V2_6).unwrap_err();
+        assert_eq!(err.to_string(), "2:1: `lambda` is not part of SMT-LIB 2.6");
+        assert_eq!(
+            parse_script_with_version(input, Version::V2_7).unwrap(),
+            parse_script(input).unwrap()
+        );
+
+        let err = parse_command_with_version("(define-const x Int 1)", Version::V2_6).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1: `define-const` is not part of SMT-LIB 2.6"
+        );
+        assert!(parse_command_with_version("(define-fun x () Int 1)", Version::V2_6).is_ok());
+    }
+}
@@ -0,0 +1,921 @@
+//! Writing s-expressions node by node, without building them first.
+//!
+//! A [`Writer`] is consumed by every write and hands back the writer for what comes next, so the
+//! type system keeps track of how deeply nested the output is: [`Writer::enter`] opens a list and
+//! returns a [`ChildWriter`], and only [`ChildWriter::leave`] gets back to the parent. Every list
+//! that is opened is therefore closed exactly once, and a writer can only be finished at the top
+//! level.
+//!
+//! [`IoWriter`] and [`FmtWriter`] write text to an [`io::Write`](std::io::Write) or
+//! [`fmt::Write`](std::fmt::Write), in the same form as [`Display`] prints it, with every
+//! top-level s-expression on a line of its own. [`SexprBuilder`] collects the s-expressions
+//! instead. The syntax types can be written as a whole with [`Writer::write`], so code generators
+//! can mix them with s-expressions they put together themselves.
+//!
+//! ```
+//! use smtlib_syntax::lexicon::Symbol;
+//! use smtlib_syntax::scripts::CommandType;
+//! use smtlib_syntax::terms::Term;
+//! use smtlib_syntax::writer::{ChildWriter, FmtWriter, Writer};
+//!
+//! let out = FmtWriter::new(String::new())
+//!     .enter()?
+//!     .write(&CommandType::Assert)?
+//!     .enter()?
+//!     .write(&Symbol::new_str(">").unwrap())?
+//!     .write(&Term::from(2))?
+//!     .write(&Term::from(-1))?
+//!     .leave()?
+//!     .leave()?
+//!     .finish();
+//! assert_eq!(out, "(assert (> 2 (- 1)))\n");
+//! # Ok::<(), std::fmt::Error>(())
+//! ```
+//!
+//! Output can only be taken from a writer once all lists are closed:
+//!
+//! ```compile_fail
+//! use smtlib_syntax::writer::{FmtWriter, Writer};
+//!
+//! let list = FmtWriter::new(String::new()).enter().unwrap();
+//! list.finish();
+//! ```
+
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+use super::attributes::{Attribute, AttributeValue};
+use super::identifiers::{Identifier, Index};
+use super::lexicon::{
+    Binary, Decimal, Hexadecimal, Keyword, Numeral, Reserved, StringConstant, Symbol, Text,
+};
+use super::scripts::{
+    Command, CommandType, ConstructorDec, DatatypeDec, FunctionDec, FunctionDef, InfoFlag,
+    PropLiteral, Script, SelectorDec, SmtInfo, SmtOption, SortDec, Source,
+};
+use super::sexprs::{Sexpr, SpecialConstant};
+use super::sorts::Sort;
+use super::terms::{lambda, MatchCase, Pattern, QualifiedIdentifier, SortedVar, Term, VarBinding};
+
+/// A single token that is not a parenthesis.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Atom<'a, T = String> {
+    Numeral(&'a Numeral),
+    Decimal(&'a Decimal),
+    Hexadecimal(&'a Hexadecimal),
+    Binary(&'a Binary),
+    String(&'a StringConstant<T>),
+    Symbol(&'a Symbol<T>),
+    Keyword(&'a Keyword<T>),
+    Reserved(Reserved),
+}
+
+impl<T: Text> Display for Atom<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Atom::Numeral(num) => num.fmt(f),
+            Atom::Decimal(dec) => dec.fmt(f),
+            Atom::Hexadecimal(hex) => hex.fmt(f),
+            Atom::Binary(bin) => bin.fmt(f),
+            Atom::String(s) => s.fmt(f),
+            Atom::Symbol(sym) => sym.fmt(f),
+            Atom::Keyword(kw) => kw.fmt(f),
+            Atom::Reserved(r) => r.fmt(f),
+        }
+    }
+}
+
+impl<T: Text> From<Atom<'_, T>> for Sexpr {
+    fn from(value: Atom<'_, T>) -> Self {
+        match value {
+            Atom::Numeral(num) => num.clone().into(),
+            Atom::Decimal(dec) => dec.clone().into(),
+            Atom::Hexadecimal(hex) => hex.clone().into(),
+            Atom::Binary(bin) => bin.clone().into(),
+            Atom::String(s) => s.clone().into_owned().into(),
+            Atom::Symbol(sym) => sym.clone().into_owned().into(),
+            Atom::Keyword(kw) => kw.clone().into_owned().into(),
+            Atom::Reserved(r) => r.into(),
+        }
+    }
+}
+
+/// Writes s-expressions one after the other, at the top level or inside a list.
+pub trait Writer: Sized {
+    type Error;
+    /// The writer for the s-expressions that follow the one just written.
+    type Next;
+    /// The writer for the elements of a list.
+    type Child: ChildWriter<Error = Self::Error, Parent = Self::Next>;
+
+    /// Opens a list, whose elements are written with the returned writer.
+    fn enter(self) -> Result<Self::Child, Self::Error>;
+
+    fn write_atom<T: Text>(self, atom: Atom<'_, T>) -> Result<Self::Next, Self::Error>;
+
+    /// Writes a value as a whole, e.g. a term or a command.
+    fn write<T: WriteSexpr + ?Sized>(self, value: &T) -> Result<Self::Next, Self::Error> {
+        value.write_sexpr(self)
+    }
+}
+
+/// Writes the elements of a list.
+pub trait ChildWriter: Writer<Next = Self> {
+    type Parent;
+
+    /// Closes the list.
+    fn leave(self) -> Result<Self::Parent, Self::Error>;
+}
+
+/// Values that are written as a single s-expression.
+pub trait WriteSexpr {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error>;
+}
+
+/// Values that are written as several elements of the surrounding list, e.g. the keyword and
+/// value of an attribute.
+pub(crate) trait WriteElements {
+    fn write_elements<C: ChildWriter>(&self, c: C) -> Result<C, C::Error>;
+}
+
+/// The top level of a [`IoWriter`] or [`FmtWriter`], outside of any list.
+pub struct Top;
+
+/// Inside a list that was entered at depth `D`.
+pub struct Nested<D>(PhantomData<D>);
+
+/// Defines a writer of text to an output implementing `$write`, which has `write_fmt`.
+///
+/// The depth of the public writer is part of its type. Values are written with `$raw` instead,
+/// which tracks the depth at runtime, since writing a recursive value like a term would otherwise
+/// need a writer type for every depth it reaches. That keeps the parentheses balanced all the
+/// same, because [`WriteSexpr`] is implemented for any writer and so can not tell them apart.
+macro_rules! text_writer {
+    ($(#[$attr:meta])* $name:ident, $raw:ident, $write:path, $error:ty) => {
+        $(#[$attr])*
+        pub struct $name<W, D = Top> {
+            raw: $raw<W>,
+            depth: PhantomData<D>,
+        }
+
+        impl<W: $write> $name<W> {
+            pub fn new(out: W) -> Self {
+                $name {
+                    raw: $raw {
+                        out,
+                        first: true,
+                        depth: 0,
+                        lines: true,
+                    },
+                    depth: PhantomData,
+                }
+            }
+
+            pub fn finish(self) -> W {
+                self.raw.out
+            }
+        }
+
+        impl<W: $write, D> Writer for $name<W, D> {
+            type Error = $error;
+            type Next = Self;
+            type Child = $name<W, Nested<D>>;
+
+            fn enter(self) -> Result<Self::Child, Self::Error> {
+                Ok($name {
+                    raw: self.raw.enter()?,
+                    depth: PhantomData,
+                })
+            }
+
+            fn write_atom<T: Text>(self, atom: Atom<'_, T>) -> Result<Self::Next, Self::Error> {
+                Ok($name {
+                    raw: self.raw.write_atom(atom)?,
+                    depth: PhantomData,
+                })
+            }
+
+            fn write<T: WriteSexpr + ?Sized>(self, value: &T) -> Result<Self::Next, Self::Error> {
+                Ok($name {
+                    raw: value.write_sexpr(self.raw)?,
+                    depth: PhantomData,
+                })
+            }
+        }
+
+        impl<W: $write, D> ChildWriter for $name<W, Nested<D>> {
+            type Parent = $name<W, D>;
+
+            fn leave(self) -> Result<Self::Parent, Self::Error> {
+                Ok($name {
+                    raw: self.raw.leave()?,
+                    depth: PhantomData,
+                })
+            }
+        }
+
+        struct $raw<W> {
+            out: W,
+            /// Whether nothing was written in the current list yet.
+            first: bool,
+            depth: usize,
+            /// Whether top-level s-expressions end with a newline.
+            lines: bool,
+        }
+
+        impl<W: $write> $raw<W> {
+            /// A writer whose output is embedded in other text, starting at `depth` and without
+            /// newlines.
+            fn embedded(out: W, depth: usize) -> Self {
+                $raw {
+                    out,
+                    first: true,
+                    depth,
+                    lines: false,
+                }
+            }
+
+            fn separate(&mut self) -> Result<(), $error> {
+                if self.depth > 0 && !self.first {
+                    write!(self.out, " ")?;
+                }
+                self.first = false;
+                Ok(())
+            }
+
+            /// Ends a top-level s-expression with a newline.
+            fn end(mut self) -> Result<Self, $error> {
+                if self.depth == 0 && self.lines {
+                    writeln!(self.out)?;
+                }
+                Ok(self)
+            }
+        }
+
+        impl<W: $write> Writer for $raw<W> {
+            type Error = $error;
+            type Next = Self;
+            type Child = Self;
+
+            fn enter(mut self) -> Result<Self::Child, Self::Error> {
+                self.separate()?;
+                write!(self.out, "(")?;
+                self.first = true;
+                self.depth += 1;
+                Ok(self)
+            }
+
+            fn write_atom<T: Text>(mut self, atom: Atom<'_, T>) -> Result<Self::Next, Self::Error> {
+                self.separate()?;
+                write!(self.out, "{atom}")?;
+                self.end()
+            }
+        }
+
+        impl<W: $write> ChildWriter for $raw<W> {
+            type Parent = Self;
+
+            fn leave(mut self) -> Result<Self::Parent, Self::Error> {
+                write!(self.out, ")")?;
+                self.first = false;
+                self.depth -= 1;
+                self.end()
+            }
+        }
+    };
+}
+
+text_writer!(
+    /// Writes s-expressions to an [`io::Write`](std::io::Write), e.g. a file. Wrap it in a
+    /// [`BufWriter`](std::io::BufWriter), since atoms are written one by one.
+    IoWriter,
+    RawIoWriter,
+    std::io::Write,
+    std::io::Error
+);
+
+text_writer!(
+    /// Writes s-expressions to a [`fmt::Write`](std::fmt::Write), e.g. a [`String`].
+    FmtWriter,
+    RawFmtWriter,
+    std::fmt::Write,
+    std::fmt::Error
+);
+
+/// Prints a value as a [`FmtWriter`] writes it, but without the newline, for the [`Display`]
+/// implementations of the syntax types.
+pub(crate) fn display<T: WriteSexpr + ?Sized>(
+    value: &T,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    value.write_sexpr(RawFmtWriter::embedded(f, 0)).map(drop)
+}
+
+/// Prints the elements of a value separated by spaces, as they appear in the surrounding list.
+pub(crate) fn display_elements<T: WriteElements + ?Sized>(
+    value: &T,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    value.write_elements(RawFmtWriter::embedded(f, 1)).map(drop)
+}
+
+/// Writes a value as [`display`] prints it. The output is buffered, so the writer does not need
+/// to be.
+pub(crate) fn write_io<T: WriteSexpr + ?Sized>(
+    value: &T,
+    writer: impl std::io::Write,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let raw = RawIoWriter::embedded(std::io::BufWriter::new(writer), 0);
+    value.write_sexpr(raw)?.out.flush()
+}
+
+/// Collects the s-expressions that are written, e.g. to test what a code generator emits.
+pub struct SexprBuilder<P = ()> {
+    items: Vec<Sexpr>,
+    parent: P,
+}
+
+impl SexprBuilder {
+    pub fn new() -> Self {
+        SexprBuilder {
+            items: vec![],
+            parent: (),
+        }
+    }
+
+    /// The top-level s-expressions that were written.
+    pub fn finish(self) -> Vec<Sexpr> {
+        self.items
+    }
+}
+
+impl Default for SexprBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Writer for SexprBuilder<P> {
+    type Error = Infallible;
+    type Next = Self;
+    type Child = SexprBuilder<Self>;
+
+    fn enter(self) -> Result<Self::Child, Self::Error> {
+        Ok(SexprBuilder {
+            items: vec![],
+            parent: self,
+        })
+    }
+
+    fn write_atom<T: Text>(mut self, atom: Atom<'_, T>) -> Result<Self::Next, Self::Error> {
+        self.items.push(atom.into());
+        Ok(self)
+    }
+
+    fn write<T: WriteSexpr + ?Sized>(mut self, value: &T) -> Result<Self::Next, Self::Error> {
+        let raw = RawSexprBuilder {
+            lists: vec![vec![]],
+        };
+        let RawSexprBuilder { lists } = value.write_sexpr(raw)?;
+        self.items.extend(lists.into_iter().flatten());
+        Ok(self)
+    }
+}
+
+impl<P> ChildWriter for SexprBuilder<SexprBuilder<P>> {
+    type Parent = SexprBuilder<P>;
+
+    fn leave(self) -> Result<Self::Parent, Self::Error> {
+        let mut parent = self.parent;
+        parent.items.push(Sexpr::Sequence(self.items));
+        Ok(parent)
+    }
+}
+
+/// Builds values with the depth tracked at runtime, like the text writers do.
+struct RawSexprBuilder {
+    /// The elements of the lists that are open, innermost last.
+    lists: Vec<Vec<Sexpr>>,
+}
+
+impl RawSexprBuilder {
+    fn current(&mut self) -> &mut Vec<Sexpr> {
+        self.lists.last_mut().expect("the top level is never left")
+    }
+}
+
+impl Writer for RawSexprBuilder {
+    type Error = Infallible;
+    type Next = Self;
+    type Child = Self;
+
+    fn enter(mut self) -> Result<Self::Child, Self::Error> {
+        self.lists.push(vec![]);
+        Ok(self)
+    }
+
+    fn write_atom<T: Text>(mut self, atom: Atom<'_, T>) -> Result<Self::Next, Self::Error> {
+        self.current().push(atom.into());
+        Ok(self)
+    }
+}
+
+impl ChildWriter for RawSexprBuilder {
+    type Parent = Self;
+
+    fn leave(mut self) -> Result<Self::Parent, Self::Error> {
+        let items = self
+            .lists
+            .pop()
+            .expect("only lists that were entered are left");
+        self.current().push(Sexpr::Sequence(items));
+        Ok(self)
+    }
+}
+
+/// Writes the items as a list.
+fn write_list<W: Writer, T: WriteSexpr>(w: W, items: &[T]) -> Result<W::Next, W::Error> {
+    let mut c = w.enter()?;
+    for item in items {
+        c = item.write_sexpr(c)?;
+    }
+    c.leave()
+}
+
+impl<T: Text> WriteSexpr for Sexpr<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        match self {
+            Sexpr::SpecialConstant(sc) => sc.write_sexpr(w),
+            Sexpr::Symbol(sym) => sym.write_sexpr(w),
+            Sexpr::Keyword(kw) => kw.write_sexpr(w),
+            Sexpr::Reserved(r) => r.write_sexpr(w),
+            Sexpr::Sequence(seq) => write_list(w, seq),
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for SpecialConstant<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        match self {
+            SpecialConstant::Numeral(num) => w.write_atom(Atom::<T>::Numeral(num)),
+            SpecialConstant::Decimal(dec) => w.write_atom(Atom::<T>::Decimal(dec)),
+            SpecialConstant::Hexadecimal(hex) => w.write_atom(Atom::<T>::Hexadecimal(hex)),
+            SpecialConstant::Binary(bin) => w.write_atom(Atom::<T>::Binary(bin)),
+            SpecialConstant::String(s) => w.write_atom(Atom::String(s)),
+        }
+    }
+}
+
+impl WriteSexpr for Numeral {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.write_atom(Atom::<String>::Numeral(self))
+    }
+}
+
+impl<T: Text> WriteSexpr for StringConstant<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.write_atom(Atom::String(self))
+    }
+}
+
+impl<T: Text> WriteSexpr for Symbol<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.write_atom(Atom::Symbol(self))
+    }
+}
+
+impl<T: Text> WriteSexpr for Keyword<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.write_atom(Atom::Keyword(self))
+    }
+}
+
+impl WriteSexpr for Reserved {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.write_atom(Atom::<String>::Reserved(self.clone()))
+    }
+}
+
+impl WriteSexpr for CommandType {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.write_atom(Atom::<String>::Reserved(Reserved::Command(self.clone())))
+    }
+}
+
+impl<T: Text> WriteSexpr for Index<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        match self {
+            Index::Numeral(num) => num.write_sexpr(w),
+            Index::Symbol(sym) => sym.write_sexpr(w),
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for Identifier<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        if self.indexes.is_empty() {
+            return self.symbol.write_sexpr(w);
+        }
+
+        let mut c = w.write_atom_list(Reserved::Underscore)?;
+        c = c.write(&self.symbol)?;
+        for index in &self.indexes {
+            c = c.write(index)?;
+        }
+        c.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for Sort<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        if self.parameters.is_empty() {
+            return self.identifier.write_sexpr(w);
+        }
+
+        let mut c = w.enter()?.write(&self.identifier)?;
+        for param in &self.parameters {
+            c = c.write(param)?;
+        }
+        c.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for QualifiedIdentifier<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        let QualifiedIdentifier(id, sort) = self;
+
+        match sort {
+            Some(sort) => w
+                .write_atom_list(Reserved::As)?
+                .write(id)?
+                .write(sort)?
+                .leave(),
+            None => id.write_sexpr(w),
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for VarBinding<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.enter()?.write(&self.0)?.write(&self.1)?.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for SortedVar<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.enter()?.write(&self.0)?.write(&self.1)?.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for Pattern<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        if self.binders().is_empty() {
+            return self.head().write_sexpr(w);
+        }
+
+        let mut c = w.enter()?.write(self.head())?;
+        for binder in self.binders() {
+            c = c.write(binder)?;
+        }
+        c.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for MatchCase<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.enter()?.write(&self.0)?.write(&self.1)?.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for AttributeValue<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        match self {
+            AttributeValue::SpecialConstant(sc) => sc.write_sexpr(w),
+            AttributeValue::Symbol(sym) => sym.write_sexpr(w),
+            AttributeValue::Sexprs(seq) => write_list(w, seq),
+        }
+    }
+}
+
+/// Writes the keyword and the value of an attribute or option, which are two elements of the
+/// surrounding list.
+fn write_keyword_value<C: ChildWriter, K: Text, T: WriteSexpr>(
+    c: C,
+    keyword: &Keyword<K>,
+    value: Option<&T>,
+) -> Result<C, C::Error> {
+    let c = c.write(keyword)?;
+    match value {
+        Some(value) => c.write(value),
+        None => Ok(c),
+    }
+}
+
+impl<T: Text> WriteElements for Attribute<T> {
+    fn write_elements<C: ChildWriter>(&self, c: C) -> Result<C, C::Error> {
+        let c = c.write(&self.keyword())?;
+        match self {
+            Attribute::Named(name) => c.write(name),
+            Attribute::Pattern(terms) => write_list(c, terms.terms()),
+            Attribute::NoPattern(term) => c.write(term),
+            Attribute::Other(_, Some(value)) => c.write(value),
+            Attribute::Other(_, None) => Ok(c),
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for Term<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        match self {
+            Term::SpecialConstant(sc) => sc.write_sexpr(w),
+            Term::QualifiedIdentifier(qi) => qi.write_sexpr(w),
+            Term::Application(name, args) => {
+                let mut c = w.enter()?.write(name)?;
+                for arg in args {
+                    c = c.write(arg)?;
+                }
+                c.leave()
+            }
+            Term::Let(bindings, body) => {
+                let c = w.write_atom_list(Reserved::Let)?;
+                write_list(c, bindings)?.write(&**body)?.leave()
+            }
+            Term::Forall(quants, body) => {
+                let c = w.write_atom_list(Reserved::Forall)?;
+                write_list(c, quants)?.write(&**body)?.leave()
+            }
+            Term::Exists(quants, body) => {
+                let c = w.write_atom_list(Reserved::Exists)?;
+                write_list(c, quants)?.write(&**body)?.leave()
+            }
+            Term::Match(term, cases) => {
+                let c = w.write_atom_list(Reserved::Match)?.write(&**term)?;
+                write_list(c, cases)?.leave()
+            }
+            Term::Lambda(vars, body) => {
+                let c = w.enter()?.write(&lambda())?;
+                write_list(c, vars)?.write(&**body)?.leave()
+            }
+            Term::Apply(fun, args) => {
+                let mut c = w.enter()?.write(&**fun)?;
+                for arg in args {
+                    c = c.write(arg)?;
+                }
+                c.leave()
+            }
+            Term::Annotated(term, attributes) => {
+                let mut c = w.write_atom_list(Reserved::Bang)?.write(&**term)?;
+                for attribute in attributes {
+                    c = attribute.write_elements(c)?;
+                }
+                c.leave()
+            }
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for SortDec<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.enter()?.write(&self.0)?.write(&self.1)?.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for SelectorDec<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        w.enter()?.write(&self.name)?.write(&self.sort)?.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for ConstructorDec<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        let mut c = w.enter()?.write(&self.name)?;
+        for selector in &self.selectors {
+            c = c.write(selector)?;
+        }
+        c.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for DatatypeDec<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        if self.parameters().is_empty() {
+            write_list(w, self.constructors())
+        } else {
+            let c = w.write_atom_list(Reserved::Par)?;
+            let c = write_list(c, self.parameters())?;
+            write_list(c, self.constructors())?.leave()
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for FunctionDec<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        let c = w.enter()?.write(&self.name)?;
+        write_list(c, &self.args)?.write(&self.sort)?.leave()
+    }
+}
+
+impl<T: Text> WriteSexpr for PropLiteral<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        if self.is_negated() {
+            let not = Symbol::new("not".to_string()).expect("`not` is a valid symbol");
+            w.enter()?.write(&not)?.write(self.symbol())?.leave()
+        } else {
+            self.symbol().write_sexpr(w)
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for Source<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        match self {
+            Source::Symbol(sym) => sym.write_sexpr(w),
+            Source::String(s) => s.write_sexpr(w),
+        }
+    }
+}
+
+impl<T: Text> WriteSexpr for InfoFlag<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        self.keyword().write_sexpr(w)
+    }
+}
+
+/// Writes the name, arguments, sort and body of `define-fun` and `define-fun-rec`.
+impl<T: Text> WriteElements for FunctionDef<T> {
+    fn write_elements<C: ChildWriter>(&self, c: C) -> Result<C, C::Error> {
+        let c = c.write(self.name())?;
+        write_list(c, self.args())?
+            .write(self.sort())?
+            .write(self.body())
+    }
+}
+
+impl<T: Text> WriteElements for SmtOption<T> {
+    fn write_elements<C: ChildWriter>(&self, c: C) -> Result<C, C::Error> {
+        let b_value =
+            |b: bool| Symbol::new(b.to_string()).expect("`true` and `false` are valid symbols");
+        let kw = self.keyword();
+
+        match self {
+            SmtOption::DiagnosticOutputChannel(s) | SmtOption::RegularOutputChannel(s) => {
+                write_keyword_value(c, &kw, Some(s))
+            }
+            SmtOption::GlobalDeclarations(b)
+            | SmtOption::InteractiveMode(b)
+            | SmtOption::PrintSuccess(b)
+            | SmtOption::ProduceAssertions(b)
+            | SmtOption::ProduceAssignments(b)
+            | SmtOption::ProduceModels(b)
+            | SmtOption::ProduceProofs(b)
+            | SmtOption::ProduceUnsatAssumptions(b)
+            | SmtOption::ProduceUnsatCores(b) => write_keyword_value(c, &kw, Some(&b_value(*b))),
+            SmtOption::RandomSeed(n)
+            | SmtOption::ReproducibleResourceLimit(n)
+            | SmtOption::Verbosity(n) => write_keyword_value(c, &kw, Some(n)),
+            SmtOption::Other(_, value) => write_keyword_value(c, &kw, value.as_ref()),
+        }
+    }
+}
+
+impl<T: Text> WriteElements for SmtInfo<T> {
+    fn write_elements<C: ChildWriter>(&self, c: C) -> Result<C, C::Error> {
+        let kw = self.keyword();
+
+        match self {
+            SmtInfo::SmtLibVersion(version) => {
+                c.write(&kw)?.write_atom(Atom::<T>::Decimal(version))
+            }
+            SmtInfo::Source(source) => write_keyword_value(c, &kw, Some(source)),
+            SmtInfo::License(s) | SmtInfo::Category(s) => write_keyword_value(c, &kw, Some(s)),
+            SmtInfo::Status(status) => {
+                let status =
+                    Symbol::new(status.name().to_string()).expect("statuses are valid symbols");
+                write_keyword_value(c, &kw, Some(&status))
+            }
+            SmtInfo::Other(_, value) => write_keyword_value(c, &kw, value.as_ref()),
+        }
+    }
+}
+
+/// Writes the pairs of `declare-datatypes` and `define-funs-rec` as two lists, of the first and
+/// of the second items.
+fn write_unzipped<C: ChildWriter, A: WriteSexpr, B: WriteSexpr>(
+    c: C,
+    entries: &[(A, B)],
+) -> Result<C, C::Error> {
+    let mut firsts = c.enter()?;
+    for (a, _) in entries {
+        firsts = firsts.write(a)?;
+    }
+    let mut seconds = firsts.leave()?.enter()?;
+    for (_, b) in entries {
+        seconds = seconds.write(b)?;
+    }
+    seconds.leave()
+}
+
+impl<T: Text> WriteSexpr for Command<T> {
+    fn write_sexpr<W: Writer>(&self, w: W) -> Result<W::Next, W::Error> {
+        let c = w.enter()?.write(&self.command_type())?;
+        let c = match self {
+            Command::Exit
+            | Command::GetAssertions
+            | Command::GetAssignment
+            | Command::GetModel
+            | Command::GetProof
+            | Command::GetUnsatAssumptions
+            | Command::GetUnsatCore
+            | Command::Reset
+            | Command::ResetAssertions
+            | Command::CheckSat => c,
+            Command::Assert(term) => c.write(term)?,
+            Command::CheckSatAssuming(props) => write_list(c, props)?,
+            Command::DeclareConst(name, sort) => c.write(name)?.write(sort)?,
+            Command::DeclareDatatype(name, datatype_dec) => c.write(name)?.write(datatype_dec)?,
+            Command::DeclareDatatypes(entries) => write_unzipped(c, entries)?,
+            Command::DeclareFun(name, arg_sorts, ret_sort) => {
+                write_list(c.write(name)?, arg_sorts)?.write(ret_sort)?
+            }
+            Command::DeclareSort(name, num) => c.write(name)?.write(num)?,
+            Command::DeclareSortParameter(name) => c.write(name)?,
+            Command::DefineConst(name, sort, term) => c.write(name)?.write(sort)?.write(term)?,
+            Command::DefineFun(fun_def) | Command::DefineFunRec(fun_def) => {
+                fun_def.write_elements(c)?
+            }
+            Command::DefineFunsRec(entries) => write_unzipped(c, entries)?,
+            Command::DefineSort(name, syms, sort) => {
+                write_list(c.write(name)?, syms)?.write(sort)?
+            }
+            Command::Echo(string) => c.write(string)?,
+            Command::GetInfo(flag) => c.write(flag)?,
+            Command::GetOption(kw) => c.write(kw)?,
+            Command::GetValue(terms) => write_list(c, terms)?,
+            Command::Pop(num) | Command::Push(num) => c.write(num)?,
+            Command::SetLogic(sym) => c.write(sym)?,
+            Command::SetInfo(info) => info.write_elements(c)?,
+            Command::SetOption(option) => option.write_elements(c)?,
+        };
+        c.leave()
+    }
+}
+
+/// Writes the commands of the script one after the other, e.g. each on its own line at the top
+/// level of a text writer.
+pub fn write_script<W: Writer<Next = W>, T: Text>(w: W, script: &Script<T>) -> Result<W, W::Error> {
+    script.0.iter().try_fold(w, |w, command| w.write(command))
+}
+
+/// Opens a list that starts with a reserved word.
+trait WriteAtomList: Writer {
+    fn write_atom_list(self, reserved: Reserved) -> Result<Self::Child, Self::Error> {
+        self.enter()?.write_atom(Atom::<String>::Reserved(reserved))
+    }
+}
+
+impl<W: Writer> WriteAtomList for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_script;
+
+    #[test]
+    fn writes_like_display() {
+        let input = "(set-option :produce-models true)\n\
+            (set-info :source |a b|)\n\
+            (declare-datatypes ((List 1)) ((par (T) ((nil) (cons (head T) (tail (List T)))))))\n\
+            (define-fun f ((x (_ BitVec 8))) Bool (! (forall ((y Int)) (= ((_ extract 0 0) x) #b1)) :named a :pattern ((g x))))\n\
+            (assert (let ((a 1.5)) (match l ((nil a) ((cons h t) ((lambda ((z Real)) z) h))))))\n\
+            (check-sat-assuming (p (not q)))\n\
+            (get-value ((as x Int) \"s\"))\n";
+        let script = parse_script(input).unwrap();
+
+        let io = write_script(IoWriter::new(vec![]), &script).unwrap();
+        assert_eq!(io.finish(), input.as_bytes());
+
+        let mut fmt = FmtWriter::new(String::new());
+        let mut builder = SexprBuilder::new();
+        for command in &script.0 {
+            fmt = fmt.write(command).unwrap();
+            builder = builder.write(command).unwrap();
+        }
+
+        assert_eq!(fmt.finish(), input);
+        assert_eq!(script.to_string(), input);
+        let mut out = vec![];
+        script.write_to(&mut out).unwrap();
+        assert_eq!(out, input.as_bytes());
+        let lines: Vec<String> = script.0.iter().map(Command::to_string).collect();
+        assert_eq!(lines, input.lines().collect::<Vec<_>>());
+        if let Command::SetOption(option) = &script.0[0] {
+            assert_eq!(option.to_string(), ":produce-models true");
+        }
+
+        let sexprs: Vec<Sexpr> = script.0.into_iter().map(Sexpr::from).collect();
+        assert_eq!(builder.finish(), sexprs);
+    }
+}